@@ -42,6 +42,7 @@ export class NewsletterApi extends Construct {
       },
       billingMode: BillingMode.PAY_PER_REQUEST,
       stream: StreamViewType.NEW_IMAGE,
+      timeToLiveAttribute: "ttl",
       removalPolicy: cdk.RemovalPolicy.DESTROY,
     });
 
@@ -58,15 +59,55 @@ export class NewsletterApi extends Construct {
       projectionType: ProjectionType.KEYS_ONLY,
     });
 
+    // The projection of GSI1 can't be changed in place, items are read through this index instead
+    this.NewsletterTable.addGlobalSecondaryIndex({
+      indexName: "GSI1All",
+      partitionKey: {
+        name: "GSI1PK",
+        type: AttributeType.STRING,
+      },
+      sortKey: {
+        name: "GSI1SK",
+        type: AttributeType.STRING,
+      },
+      projectionType: ProjectionType.ALL,
+    });
+
+    // Sessions and idempotency keys expire through the ttl
     const auth_table = new Table(this, "NewsletterAuthTable", {
       partitionKey: {
         name: "PK",
         type: AttributeType.STRING,
       },
       billingMode: BillingMode.PAY_PER_REQUEST,
+      timeToLiveAttribute: "ttl",
       removalPolicy: cdk.RemovalPolicy.DESTROY,
     });
 
+    // CloudFormation adds one index per update, so an existing stack needs a deploy for each
+    auth_table.addGlobalSecondaryIndex({
+      indexName: "SessionsByUser",
+      partitionKey: {
+        name: "SessionUserId",
+        type: AttributeType.STRING,
+      },
+      projectionType: ProjectionType.ALL,
+    });
+
+    // Users are listed from GSI1, under GSI1PK = User
+    auth_table.addGlobalSecondaryIndex({
+      indexName: "GSI1",
+      partitionKey: {
+        name: "GSI1PK",
+        type: AttributeType.STRING,
+      },
+      sortKey: {
+        name: "GSI1SK",
+        type: AttributeType.STRING,
+      },
+      projectionType: ProjectionType.ALL,
+    });
+
     this.NewsletterStorageBucket = new Bucket(this, "NewsletterStorage", {
       bucketName: "james-eastham-newsletter-metadata",
       removalPolicy: cdk.RemovalPolicy.DESTROY,
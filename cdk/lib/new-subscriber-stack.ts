@@ -21,7 +21,14 @@ export class NewSubscriberProcessingStack extends Construct {
   constructor(scope: Construct, id: string, props: NewSubscriberProcessingStackProps) {
     super(scope, id);
 
-    const new_subscriber_queue = new Queue(this, "NewSubscriberQueue");
+    const new_subscriber_queue = new Queue(this, "NewSubscriberQueue", {
+      deadLetterQueue: {
+        queue: new Queue(this, "NewSubscriberDLQ"),
+        // Every failed record is retried until it is moved to the DLQ, including ones that can
+        // never be handled, like an unparseable body. The handlers log those as errors.
+        maxReceiveCount: 5
+      }
+    });
 
     var pipeRole = new Role(this, "PipeIntegrationRole", {
         assumedBy: new ServicePrincipal("pipes.amazonaws.com")
@@ -80,7 +87,8 @@ export class NewSubscriberProcessingStack extends Construct {
       });
   
       send_confirmation_function.addEventSource(new SqsEventSource(new_subscriber_queue, {
-        batchSize: 10
+        batchSize: 10,
+        reportBatchItemFailures: true
       }));
 
       props.configParameter.grantRead(send_confirmation_function);
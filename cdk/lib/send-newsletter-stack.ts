@@ -4,6 +4,8 @@ import { ITable } from 'aws-cdk-lib/aws-dynamodb';
 import { Role, ServicePrincipal } from 'aws-cdk-lib/aws-iam';
 import { Architecture, DockerImageCode, DockerImageFunction, LayerVersion, StartingPosition } from 'aws-cdk-lib/aws-lambda';
 import { DynamoEventSource, SqsEventSource } from 'aws-cdk-lib/aws-lambda-event-sources';
+import { Rule, Schedule } from 'aws-cdk-lib/aws-events';
+import { LambdaFunction } from 'aws-cdk-lib/aws-events-targets';
 import { CfnPipe } from 'aws-cdk-lib/aws-pipes';
 import { IBucket } from 'aws-cdk-lib/aws-s3';
 import { Queue } from 'aws-cdk-lib/aws-sqs';
@@ -21,7 +23,14 @@ export class SendNewsletterProcessingStack extends Construct {
   constructor(scope: Construct, id: string, props: SendNewsletterProcessingStackProps) {
     super(scope, id);
 
-    const sendNewsletterQueue = new Queue(this, "SendNewsletterQueue");
+    const sendNewsletterQueue = new Queue(this, "SendNewsletterQueue", {
+      deadLetterQueue: {
+        queue: new Queue(this, "SendNewsletterDLQ"),
+        // Every failed record is retried until it is moved to the DLQ, including ones that can
+        // never be handled, like an unparseable body. The handlers log those as errors.
+        maxReceiveCount: 5
+      }
+    });
 
     var pipeRole = new Role(this, "PipeIntegrationRole", {
         assumedBy: new ServicePrincipal("pipes.amazonaws.com")
@@ -52,12 +61,55 @@ export class SendNewsletterProcessingStack extends Construct {
           inputTemplate: `{
             "trace_parent": <$.dynamodb.NewImage.TraceParent.S>,
             "parent_span": <$.dynamodb.NewImage.ParentSpan.S>,
+            "issue_id": <$.dynamodb.NewImage.IssueId.S>,
             "issue_title": <$.dynamodb.NewImage.IssueTitle.S>,
             "s3_pointer": <$.dynamodb.NewImage.S3Pointer.S>
           }`
         }
       })
   
+      // Test sends of a draft go through the same queue, but only to a single recipient
+      var testSendPipe = new CfnPipe(this, "NewsletterTestSendPipe", {
+        roleArn: pipeRole.roleArn,
+        source: (props.newsletterTable.tableStreamArn ?? ""),
+        sourceParameters: {
+          dynamoDbStreamParameters: {
+            startingPosition: "TRIM_HORIZON",
+            batchSize: 10
+          },
+          filterCriteria: {
+            filters: [{
+              pattern: '{"eventName": ["INSERT"], "dynamodb.NewImage.Type.S": ["NewsletterTestSend"]}'
+            }]
+          },
+        },
+        target: sendNewsletterQueue.queueArn,
+        targetParameters: {
+          sqsQueueParameters:{
+
+          },
+          inputTemplate: `{
+            "trace_parent": <$.dynamodb.NewImage.TraceParent.S>,
+            "parent_span": <$.dynamodb.NewImage.ParentSpan.S>,
+            "issue_id": <$.dynamodb.NewImage.IssueId.S>,
+            "issue_title": <$.dynamodb.NewImage.IssueTitle.S>,
+            "s3_pointer": <$.dynamodb.NewImage.S3Pointer.S>,
+            "test_recipient": <$.dynamodb.NewImage.Recipient.S>
+          }`
+        }
+      })
+
+      const sendNewsletterChunkQueue = new Queue(this, "SendNewsletterChunkQueue", {
+        deadLetterQueue: {
+          queue: new Queue(this, "SendNewsletterChunkDLQ"),
+          // Every failed record is retried until it is moved to the DLQ, including ones that can
+          // never be handled, like an unparseable body. The handlers log those as errors.
+          maxReceiveCount: 5
+        }
+      });
+
+      const ddExtension = LayerVersion.fromLayerVersionArn(this, "DDExtension", "arn:aws:lambda:eu-west-1:464622532012:layer:Datadog-Extension-ARM:55");
+
       const send_newsletter_function = new RustFunction(this, "NewsletterFunction", {
         entry: '../src/Cargo.toml',
         functionName: 'Zero2ProdSendNewsletterFunction',
@@ -66,6 +118,8 @@ export class SendNewsletterProcessingStack extends Construct {
         environment: {
           "APP_DATABASE__DATABASE_NAME": props.newsletterTable.tableName,
           "APP_DATABASE__NEWSLETTER_STORAGE_BUCKET": props.newsletterStorageBucket.bucketName,
+          "APP_FAN_OUT__QUEUE_URL": sendNewsletterChunkQueue.queueUrl,
+          "APP_FAN_OUT__CHUNK_SIZE": "50",
           LOG_LEVEL: "error",
           CONFIG_PARAMETER_NAME: props.configParameter.parameterName,
           APP_ENVIRONMENT: "production",
@@ -77,17 +131,85 @@ export class SendNewsletterProcessingStack extends Construct {
           DD_SERVICE: "zero2prod-send-newsletter"
         },
         layers: [
-          LayerVersion.fromLayerVersionArn(this, "DDExtension", "arn:aws:lambda:eu-west-1:464622532012:layer:Datadog-Extension-ARM:55")
+          ddExtension
         ],
         architecture: Architecture.ARM_64,
       });
   
       send_newsletter_function.addEventSource(new SqsEventSource(sendNewsletterQueue, {
-        batchSize: 10
+        batchSize: 10,
+        reportBatchItemFailures: true
       }));
   
       props.newsletterStorageBucket.grantRead(send_newsletter_function);
-      props.newsletterTable.grantReadData(send_newsletter_function);
+      props.newsletterTable.grantReadWriteData(send_newsletter_function);
       props.configParameter.grantRead(send_newsletter_function);
+      sendNewsletterChunkQueue.grantSendMessages(send_newsletter_function);
+
+      const send_newsletter_chunk_function = new RustFunction(this, "NewsletterChunkFunction", {
+        entry: '../src/Cargo.toml',
+        functionName: 'Zero2ProdSendNewsletterChunkFunction',
+        binaryName: 'send_newsletter_chunk',
+        timeout: Duration.seconds(60),
+        environment: {
+          "APP_DATABASE__DATABASE_NAME": props.newsletterTable.tableName,
+          "APP_DATABASE__NEWSLETTER_STORAGE_BUCKET": props.newsletterStorageBucket.bucketName,
+          LOG_LEVEL: "error",
+          CONFIG_PARAMETER_NAME: props.configParameter.parameterName,
+          APP_ENVIRONMENT: "production",
+          DD_OTLP_CONFIG_RECEIVER_PROTOCOLS_HTTP_ENDPOINT: "localhost:4318",
+          AWS_LAMBDA_EXEC_WRAPPER: "/opt/datadog_wrapper",
+          DD_SITE: "datadoghq.eu",
+          DD_API_KEY: process.env.DATADOG_API_KEY ?? "",
+          DD_ENV: "production",
+          DD_SERVICE: "zero2prod-send-newsletter"
+        },
+        layers: [
+          ddExtension
+        ],
+        architecture: Architecture.ARM_64,
+      });
+
+      send_newsletter_chunk_function.addEventSource(new SqsEventSource(sendNewsletterChunkQueue, {
+        batchSize: 1,
+        reportBatchItemFailures: true
+      }));
+
+      props.newsletterStorageBucket.grantRead(send_newsletter_chunk_function);
+      props.newsletterTable.grantReadWriteData(send_newsletter_chunk_function);
+      props.configParameter.grantRead(send_newsletter_chunk_function);
+
+      // Releases scheduled issues once they are due, the pipe above then sends them
+      const publish_scheduled_function = new RustFunction(this, "PublishScheduledNewslettersFunction", {
+        entry: '../src/Cargo.toml',
+        functionName: 'Zero2ProdPublishScheduledNewslettersFunction',
+        binaryName: 'publish_scheduled_newsletters',
+        timeout: Duration.seconds(30),
+        environment: {
+          "APP_DATABASE__DATABASE_NAME": props.newsletterTable.tableName,
+          "APP_DATABASE__NEWSLETTER_STORAGE_BUCKET": props.newsletterStorageBucket.bucketName,
+          LOG_LEVEL: "error",
+          CONFIG_PARAMETER_NAME: props.configParameter.parameterName,
+          APP_ENVIRONMENT: "production",
+          DD_OTLP_CONFIG_RECEIVER_PROTOCOLS_HTTP_ENDPOINT: "localhost:4318",
+          AWS_LAMBDA_EXEC_WRAPPER: "/opt/datadog_wrapper",
+          DD_SITE: "datadoghq.eu",
+          DD_API_KEY: process.env.DATADOG_API_KEY ?? "",
+          DD_ENV: "production",
+          DD_SERVICE: "zero2prod-send-newsletter"
+        },
+        layers: [
+          ddExtension
+        ],
+        architecture: Architecture.ARM_64,
+      });
+
+      new Rule(this, "PublishScheduledNewslettersSchedule", {
+        schedule: Schedule.rate(Duration.minutes(1)),
+        targets: [new LambdaFunction(publish_scheduled_function)]
+      });
+
+      props.newsletterTable.grantReadWriteData(publish_scheduled_function);
+      props.configParameter.grantRead(publish_scheduled_function);
 }
 }
@@ -13,46 +13,15 @@ docker run -d -e COLLECTOR_OTLP_ENABLED=true -p4317:4317 -p4318:4318 -p6831:6831
 
 docker run -d -p 8000:8000 amazon/dynamodb-local:latest
 
->&2 echo "DynamoDB Local Started, creating table"
+>&2 echo "DynamoDB Local Started, applying migrations"
 
 sleep 2
 
-aws dynamodb create-table \
-    --table-name newsletter \
-    --attribute-definitions AttributeName=PK,AttributeType=S AttributeName=GSI1PK,AttributeType=S AttributeName=GSI1SK,AttributeType=S  \
-    --key-schema AttributeName=PK,KeyType=HASH \
-    --provisioned-throughput ReadCapacityUnits=5,WriteCapacityUnits=5 \
-    --endpoint-url http://localhost:8000 \
-    --region us-east-1 \
-    --global-secondary-indexes \
-            "[
-                {
-                    \"IndexName\": \"GSI1\",
-                    \"KeySchema\": [
-                        {\"AttributeName\":\"GSI1PK\",\"KeyType\":\"HASH\"},
-                        {\"AttributeName\":\"GSI1SK\",\"KeyType\":\"RANGE\"}
-                    ],
-                    \"Projection\": {
-                        \"ProjectionType\":\"KEYS_ONLY\"
-                    },
-                    \"ProvisionedThroughput\": {
-                        \"ReadCapacityUnits\": 5,
-                        \"WriteCapacityUnits\": 5
-                    }
-                }
-            ]" > create-result.json
-
-aws dynamodb create-table \
-    --table-name auth \
-    --attribute-definitions AttributeName=PK,AttributeType=S  \
-    --key-schema AttributeName=PK,KeyType=HASH \
-    --provisioned-throughput ReadCapacityUnits=5,WriteCapacityUnits=5 \
-    --endpoint-url http://localhost:8000 \
-    --region us-east-1  > auth-table-create-result.json
+# Creates the newsletter and auth tables, run from the api folder so the configuration is found
+cd "$(dirname "$0")/.."
+cargo run --bin migrate
 
-sleep 2
-
->&2 echo "Table created"
+>&2 echo "Tables created"
 
 >&2 echo "Setting dummy environment variables for DynamoDB Local"
 
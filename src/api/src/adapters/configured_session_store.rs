@@ -0,0 +1,160 @@
+use crate::adapters::dynamo_db_session_store::{DynamoDbSessionStore, SessionState};
+use crate::adapters::in_memory_session_store::InMemorySessionStore;
+use crate::adapters::session_encryption::{SessionEncryption, SessionEncryptionKey};
+use crate::configuration::{DatabaseSettings, SessionBackend, SessionSettings};
+use crate::session_state::{ActiveSession, SessionRegistry, SessionsNotTracked};
+use actix_session::storage::{
+    CookieSessionStore, LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::time::Duration;
+
+/// The session store selected by the `session` settings.
+#[derive(Clone)]
+pub enum ConfiguredSessionStore {
+    DynamoDb(Box<DynamoDbSessionStore>),
+    InMemory(InMemorySessionStore),
+    /// The session state is stored in the session cookie itself, encrypted by the session
+    /// middleware. Nothing is stored on the server, so listing or revoking the sessions of a user
+    /// fails with `SessionsNotTracked`.
+    Cookie,
+}
+
+impl ConfiguredSessionStore {
+    pub async fn build(
+        session_settings: &SessionSettings,
+        db_settings: &DatabaseSettings,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(match session_settings.backend {
+            SessionBackend::DynamoDb => {
+                let mut builder = DynamoDbSessionStore::builder()
+                    .table_name(db_settings.auth_database_name.clone())
+                    .key_name("PK".to_string())
+                    .use_dynamo_db_local(db_settings.use_local);
+                if let Some(encryption) = session_encryption(session_settings)? {
+                    builder = builder.encryption(encryption);
+                }
+
+                ConfiguredSessionStore::DynamoDb(Box::new(builder.build().await?))
+            }
+            SessionBackend::Memory => {
+                ConfiguredSessionStore::InMemory(InMemorySessionStore::default())
+            }
+            SessionBackend::Cookie => ConfiguredSessionStore::Cookie,
+        })
+    }
+}
+
+/// The first key encrypts, the others only decrypt.
+fn session_encryption(
+    session_settings: &SessionSettings,
+) -> Result<Option<SessionEncryption>, anyhow::Error> {
+    let mut keys = session_settings
+        .encryption_keys
+        .iter()
+        .map(|key| SessionEncryptionKey::parse(key.id.clone(), &key.key))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+
+    Ok(keys
+        .next()
+        .map(|current_key| SessionEncryption::new(current_key, keys.collect())))
+}
+
+#[async_trait::async_trait(?Send)]
+impl SessionStore for ConfiguredSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => store.load(session_key).await,
+            ConfiguredSessionStore::InMemory(store) => store.load(session_key).await,
+            ConfiguredSessionStore::Cookie => CookieSessionStore::default().load(session_key).await,
+        }
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => store.save(session_state, ttl).await,
+            ConfiguredSessionStore::InMemory(store) => store.save(session_state, ttl).await,
+            ConfiguredSessionStore::Cookie => {
+                CookieSessionStore::default().save(session_state, ttl).await
+            }
+        }
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => {
+                store.update(session_key, session_state, ttl).await
+            }
+            ConfiguredSessionStore::InMemory(store) => {
+                store.update(session_key, session_state, ttl).await
+            }
+            ConfiguredSessionStore::Cookie => {
+                CookieSessionStore::default()
+                    .update(session_key, session_state, ttl)
+                    .await
+            }
+        }
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => store.update_ttl(session_key, ttl).await,
+            ConfiguredSessionStore::InMemory(store) => store.update_ttl(session_key, ttl).await,
+            ConfiguredSessionStore::Cookie => {
+                CookieSessionStore::default()
+                    .update_ttl(session_key, ttl)
+                    .await
+            }
+        }
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => store.delete(session_key).await,
+            ConfiguredSessionStore::InMemory(store) => store.delete(session_key).await,
+            ConfiguredSessionStore::Cookie => {
+                CookieSessionStore::default().delete(session_key).await
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionRegistry for ConfiguredSessionStore {
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<ActiveSession>, anyhow::Error> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => store.list_sessions(user_id).await,
+            ConfiguredSessionStore::InMemory(store) => store.list_sessions(user_id).await,
+            ConfiguredSessionStore::Cookie => Err(SessionsNotTracked.into()),
+        }
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: &str,
+        revocation_id: &str,
+    ) -> Result<bool, anyhow::Error> {
+        match self {
+            ConfiguredSessionStore::DynamoDb(store) => {
+                store.revoke_session(user_id, revocation_id).await
+            }
+            ConfiguredSessionStore::InMemory(store) => {
+                store.revoke_session(user_id, revocation_id).await
+            }
+            ConfiguredSessionStore::Cookie => Err(SessionsNotTracked.into()),
+        }
+    }
+}
@@ -1,6 +1,8 @@
+use crate::adapters::session_encryption::{is_encrypted, SessionEncryption};
+use crate::session_state::{ActiveSession, SessionRegistry};
 use actix_session::storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError};
 use actix_web::cookie::time::Duration;
-use anyhow::Error;
+use anyhow::{Context, Error};
 use aws_config::default_provider::credentials::DefaultCredentialsChain;
 use aws_config::meta::region::RegionProviderChain;
 use aws_config::BehaviorVersion;
@@ -10,6 +12,7 @@ use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::{Client, Config};
 use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
 use rand::{distributions::Alphanumeric, rngs::OsRng, Rng as _};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::ops::Add;
 use std::sync::Arc;
@@ -30,6 +33,10 @@ pub struct CacheConfiguration {
     key_name: String,
     ttl_name: String,
     session_data_name: String,
+    user_id_key: String,
+    user_id_name: String,
+    user_id_index_name: String,
+    encryption: Option<SessionEncryption>,
     use_dynamo_db_local: bool,
     dynamo_db_local_endpoint: String,
     sdk_config: Option<Config>,
@@ -46,6 +53,10 @@ impl Default for CacheConfiguration {
             key_name: "SessionId".to_string(),
             ttl_name: "ttl".to_string(),
             session_data_name: "session_data".to_string(),
+            user_id_key: "user_id".to_string(),
+            user_id_name: "SessionUserId".to_string(),
+            user_id_index_name: "SessionsByUser".to_string(),
+            encryption: None,
             dynamo_db_local_endpoint: "http://localhost:8000".to_string(),
             sdk_config: None,
             region: None,
@@ -123,6 +134,33 @@ impl DynamoDbSessionStoreBuilder {
         self
     }
 
+    /// Set the session state key holding the id of the logged in user. Defaults to 'user_id'.
+    pub fn user_id_key(mut self, user_id_key: String) -> Self {
+        self.configuration.user_id_key = user_id_key;
+        self
+    }
+
+    /// Set the name of the DynamoDB column the user id is copied to, so the sessions of a user
+    /// can be found. Defaults to 'SessionUserId'.
+    pub fn user_id_name(mut self, user_id_name: String) -> Self {
+        self.configuration.user_id_name = user_id_name;
+        self
+    }
+
+    /// Set the name of the global secondary index keyed on the user id column. Defaults to
+    /// 'SessionsByUser'.
+    pub fn user_id_index_name(mut self, user_id_index_name: String) -> Self {
+        self.configuration.user_id_index_name = user_id_index_name;
+        self
+    }
+
+    /// Encrypt the session data before it is stored. Without it, session data is stored as plain
+    /// JSON.
+    pub fn encryption(mut self, encryption: SessionEncryption) -> Self {
+        self.configuration.encryption = Some(encryption);
+        self
+    }
+
     /// Finalise the builder and return a [`DynamoDbSessionStore`] instance.
     ///
     /// [`DynamoDbSessionStore`]: crate::storage::DynamoDbSessionStore
@@ -159,21 +197,40 @@ impl SessionStore for DynamoDbSessionStore {
             .client
             .get_item()
             .table_name(&self.configuration.table_name)
-            .key(&self.configuration.key_name, AttributeValue::S(cache_key))
+            .key(
+                &self.configuration.key_name,
+                AttributeValue::S(cache_key.clone()),
+            )
             .send()
             .await
             .map_err(Into::into)
             .map_err(LoadError::Other)?;
 
-        match cache_value.item {
-            None => Ok(None),
-            Some(item) => match item["session_data"].as_s() {
-                Ok(value) => Ok(serde_json::from_str(value)
-                    .map_err(Into::into)
-                    .map_err(LoadError::Deserialization)?),
-                Err(_) => Ok(None),
-            },
-        }
+        let item = match cache_value.item {
+            Some(item) if !has_expired(&item, &self.configuration.ttl_name) => item,
+            _ => return Ok(None),
+        };
+
+        let value = match item
+            .get(&self.configuration.session_data_name)
+            .and_then(|session_data| session_data.as_s().ok())
+        {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        // Sessions encrypted with a key that has since been dropped are logged out
+        let value = match self.decrypt_session_data(value, &cache_key) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Session data can't be decrypted: {:?}", e);
+                return Ok(None);
+            }
+        };
+
+        Ok(serde_json::from_str(&value)
+            .map_err(Into::into)
+            .map_err(LoadError::Deserialization)?)
     }
 
     async fn save(
@@ -186,17 +243,33 @@ impl SessionStore for DynamoDbSessionStore {
             .map_err(SaveError::Serialization)?;
         let session_key = generate_session_key();
         let cache_key = (self.configuration.cache_keygen)(session_key.as_ref());
+        let body = self
+            .encrypt_session_data(body, &cache_key)
+            .map_err(SaveError::Other)?;
 
-        let _ = self
+        let mut put_item = self
             .client
             .put_item()
             .table_name(&self.configuration.table_name)
             .item(&self.configuration.key_name, AttributeValue::S(cache_key))
-            .item("session_data", AttributeValue::S(body))
+            .item(
+                &self.configuration.session_data_name,
+                AttributeValue::S(body),
+            )
             .item(
                 &self.configuration.ttl_name,
-                AttributeValue::N(get_epoch_ms(*ttl).to_string()),
+                AttributeValue::N(get_epoch_seconds(*ttl).to_string()),
             )
+            .item(
+                "CreatedAt",
+                AttributeValue::N(get_epoch_seconds(Duration::ZERO).to_string()),
+            );
+
+        if let Some(user_id) = self.session_user_id(&session_state) {
+            put_item = put_item.item(&self.configuration.user_id_name, AttributeValue::S(user_id));
+        }
+
+        let _ = put_item
             .condition_expression(
                 format!("attribute_not_exists({})", self.configuration.key_name).to_string(),
             )
@@ -219,22 +292,46 @@ impl SessionStore for DynamoDbSessionStore {
             .map_err(UpdateError::Serialization)?;
 
         let cache_key = (self.configuration.cache_keygen)(session_key.as_ref());
+        let body = self
+            .encrypt_session_data(body, &cache_key)
+            .map_err(UpdateError::Other)?;
 
-        let put_res = self
+        // Updated in place rather than replaced, to keep the time the session was created
+        let update_item = self
             .client
-            .put_item()
+            .update_item()
             .table_name(&self.configuration.table_name)
-            .item(&self.configuration.key_name, AttributeValue::S(cache_key))
-            .item("session_data", AttributeValue::S(body))
-            .item(
-                &self.configuration.ttl_name,
-                AttributeValue::N(get_epoch_ms(*ttl).to_string()),
+            .key(&self.configuration.key_name, AttributeValue::S(cache_key))
+            .expression_attribute_names("#session_data", &self.configuration.session_data_name)
+            .expression_attribute_names("#ttl", &self.configuration.ttl_name)
+            .expression_attribute_names("#user_id", &self.configuration.user_id_name)
+            .expression_attribute_values(":session_data", AttributeValue::S(body))
+            .expression_attribute_values(
+                ":ttl",
+                AttributeValue::N(get_epoch_seconds(*ttl).to_string()),
+            )
+            .expression_attribute_values(
+                ":now",
+                AttributeValue::N(get_epoch_seconds(Duration::ZERO).to_string()),
             )
             .condition_expression(
                 format!("attribute_exists({})", self.configuration.key_name).to_string(),
-            )
-            .send()
-            .await;
+            );
+
+        let update_item = match self.session_user_id(&session_state) {
+            Some(user_id) => update_item
+                .update_expression(
+                    "SET #session_data = :session_data, #ttl = :ttl, \
+                     CreatedAt = if_not_exists(CreatedAt, :now), #user_id = :user_id",
+                )
+                .expression_attribute_values(":user_id", AttributeValue::S(user_id)),
+            None => update_item.update_expression(
+                "SET #session_data = :session_data, #ttl = :ttl, \
+                 CreatedAt = if_not_exists(CreatedAt, :now) REMOVE #user_id",
+            ),
+        };
+
+        let put_res = update_item.send().await;
 
         match put_res {
             Ok(_) => Ok(session_key),
@@ -267,10 +364,11 @@ impl SessionStore for DynamoDbSessionStore {
             .update_item()
             .table_name(&self.configuration.table_name)
             .key(&self.configuration.key_name, AttributeValue::S(cache_key))
-            .update_expression("SET ttl = :value")
+            .update_expression("SET #ttl = :value")
+            .expression_attribute_names("#ttl", &self.configuration.ttl_name)
             .expression_attribute_values(
                 ":value",
-                AttributeValue::N(get_epoch_ms(*ttl).to_string()),
+                AttributeValue::N(get_epoch_seconds(*ttl).to_string()),
             )
             .send()
             .await
@@ -295,10 +393,155 @@ impl SessionStore for DynamoDbSessionStore {
     }
 }
 
+impl DynamoDbSessionStore {
+    fn encrypt_session_data(&self, body: String, cache_key: &str) -> Result<String, anyhow::Error> {
+        match &self.configuration.encryption {
+            Some(encryption) => encryption.encrypt(&body, cache_key),
+            None => Ok(body),
+        }
+    }
+
+    fn decrypt_session_data(&self, value: &str, cache_key: &str) -> Result<String, anyhow::Error> {
+        match &self.configuration.encryption {
+            Some(encryption) => encryption.decrypt(value, cache_key),
+            None if is_encrypted(value) => Err(anyhow::anyhow!(
+                "The session data is encrypted, but no session encryption is configured"
+            )),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// The id of the logged in user, `None` for anonymous sessions.
+    fn session_user_id(&self, session_state: &SessionState) -> Option<String> {
+        session_state
+            .get(&self.configuration.user_id_key)
+            .and_then(|user_id| serde_json::from_str(user_id).ok())
+    }
+
+    async fn query_sessions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>, anyhow::Error> {
+        let query_results: Result<Vec<_>, _> = self
+            .client
+            .query()
+            .table_name(&self.configuration.table_name)
+            .index_name(&self.configuration.user_id_index_name)
+            .key_condition_expression("#user_id = :user_id")
+            .expression_attribute_names("#user_id", &self.configuration.user_id_name)
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .into_paginator()
+            .items()
+            .send()
+            .collect()
+            .await;
+
+        query_results.context(format!(
+            "Failure querying the sessions of a user. Using table {}",
+            &self.configuration.table_name
+        ))
+    }
+
+    fn revocation_id(&self, item: &HashMap<String, AttributeValue>) -> Option<String> {
+        item.get(&self.configuration.key_name)
+            .and_then(|cache_key| cache_key.as_s().ok())
+            .map(|cache_key| format!("{:x}", Sha256::digest(cache_key.as_bytes())))
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionRegistry for DynamoDbSessionStore {
+    #[tracing::instrument(skip(self))]
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<ActiveSession>, anyhow::Error> {
+        let mut sessions = Vec::new();
+
+        for item in self.query_sessions(user_id).await? {
+            if has_expired(&item, &self.configuration.ttl_name) {
+                continue;
+            }
+
+            let cache_key = item
+                .get(&self.configuration.key_name)
+                .and_then(|cache_key| cache_key.as_s().ok())
+                .context("Session has no key")?;
+            let state = item
+                .get(&self.configuration.session_data_name)
+                .and_then(|session_data| session_data.as_s().ok())
+                .context("Session has no session data")?;
+            // Can't be loaded either, so it is logged out already
+            let state = match self.decrypt_session_data(state, cache_key) {
+                Ok(state) => state,
+                Err(_) => continue,
+            };
+
+            sessions.push(ActiveSession {
+                revocation_id: self.revocation_id(&item).context("Session has no key")?,
+                created_at: item
+                    .get("CreatedAt")
+                    .and_then(|created_at| created_at.as_n().ok())
+                    .and_then(|created_at| created_at.parse().ok())
+                    .unwrap_or(0),
+                state: serde_json::from_str(&state).context("Failure parsing the session data")?,
+            });
+        }
+
+        sessions.sort_by_key(|session| session.created_at);
+
+        Ok(sessions)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn revoke_session(
+        &self,
+        user_id: &str,
+        revocation_id: &str,
+    ) -> Result<bool, anyhow::Error> {
+        // Only the index knows which session keys belong to the user
+        let session = self
+            .query_sessions(user_id)
+            .await?
+            .into_iter()
+            .find(|item| self.revocation_id(item).as_deref() == Some(revocation_id));
+
+        let cache_key =
+            match session.and_then(|item| item.get(&self.configuration.key_name).cloned()) {
+                Some(cache_key) => cache_key,
+                None => return Ok(false),
+            };
+
+        let delete_res = self
+            .client
+            .delete_item()
+            .table_name(&self.configuration.table_name)
+            .key(&self.configuration.key_name, cache_key)
+            .condition_expression("#user_id = :user_id")
+            .expression_attribute_names("#user_id", &self.configuration.user_id_name)
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .send()
+            .await;
+
+        match delete_res {
+            Ok(_) => Ok(true),
+            // The session was logged out, or expired, since the query
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure revoking a session. Using table {}",
+                &self.configuration.table_name
+            )),
+        }
+    }
+}
+
 /// Session key generation routine that follows [OWASP recommendations].
 ///
 /// [OWASP recommendations]: https://cheatsheetseries.owasp.org/cheatsheets/Session_Management_Cheat_Sheet.html#session-id-entropy
-fn generate_session_key() -> SessionKey {
+pub(crate) fn generate_session_key() -> SessionKey {
     let value = std::iter::repeat(())
         .map(|()| OsRng.sample(Alphanumeric))
         .take(64)
@@ -309,12 +552,36 @@ fn generate_session_key() -> SessionKey {
     String::from_utf8(value).unwrap().try_into().unwrap()
 }
 
-fn get_epoch_ms(duration: Duration) -> u128 {
+fn get_epoch_seconds(duration: Duration) -> u64 {
     SystemTime::now()
         .add(duration)
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_millis()
+        .as_secs()
+}
+
+/// Ttls above this are in milliseconds, as they were written before the store used seconds. In
+/// seconds it is over a thousand years away.
+const MAX_TTL_SECONDS: u64 = 100_000_000_000;
+
+/// DynamoDB removes expired items eventually, up to a few days after they expire, so expired
+/// sessions can still be read.
+fn has_expired(item: &HashMap<String, AttributeValue>, ttl_name: &str) -> bool {
+    is_expired_at(item, ttl_name, get_epoch_seconds(Duration::ZERO))
+}
+
+fn is_expired_at(item: &HashMap<String, AttributeValue>, ttl_name: &str, now: u64) -> bool {
+    let expires_at = match item
+        .get(ttl_name)
+        .and_then(|ttl| ttl.as_n().ok())
+        .and_then(|ttl| ttl.parse::<u64>().ok())
+    {
+        Some(ttl) if ttl > MAX_TTL_SECONDS => ttl / 1000,
+        Some(ttl) => ttl,
+        None => return true,
+    };
+
+    expires_at <= now
 }
 
 async fn make_credentials_provider(region: Region) -> Result<Credentials, anyhow::Error> {
@@ -358,3 +625,41 @@ async fn make_config(
 fn make_region_provider() -> RegionProviderChain {
     RegionProviderChain::default_provider().or_else(Region::new("us-east-1"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_expired_at;
+    use aws_sdk_dynamodb::types::AttributeValue;
+    use std::collections::HashMap;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn item_with_ttl(ttl: u64) -> HashMap<String, AttributeValue> {
+        HashMap::from([("ttl".to_string(), AttributeValue::N(ttl.to_string()))])
+    }
+
+    #[test]
+    fn a_session_with_a_ttl_in_the_future_has_not_expired() {
+        assert!(!is_expired_at(&item_with_ttl(NOW + 60), "ttl", NOW));
+    }
+
+    #[test]
+    fn a_session_with_a_ttl_in_the_past_has_expired() {
+        assert!(is_expired_at(&item_with_ttl(NOW - 60), "ttl", NOW));
+    }
+
+    #[test]
+    fn ttls_written_in_milliseconds_are_read_as_seconds() {
+        assert!(is_expired_at(&item_with_ttl((NOW - 60) * 1000), "ttl", NOW));
+        assert!(!is_expired_at(
+            &item_with_ttl((NOW + 60) * 1000),
+            "ttl",
+            NOW
+        ));
+    }
+
+    #[test]
+    fn a_session_without_a_ttl_has_expired() {
+        assert!(is_expired_at(&HashMap::new(), "ttl", NOW));
+    }
+}
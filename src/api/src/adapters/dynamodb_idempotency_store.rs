@@ -0,0 +1,208 @@
+use crate::idempotency::{IdempotencyKey, IdempotencyStore, SavedResponse};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+/// How long a key, and the response saved for it, is kept. Retries after that are processed as
+/// a new request.
+const IDEMPOTENCY_KEY_VALIDITY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Stores one `IdempotencyKey` item per (user, key) pair in the auth table, removed by the table
+/// ttl once it is no longer valid.
+#[derive(Debug, Clone)]
+pub struct DynamoDbIdempotencyStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbIdempotencyStore {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for DynamoDbIdempotencyStore {
+    #[tracing::instrument(skip(self))]
+    async fn try_claim(&self, key: &IdempotencyKey, user_id: &str) -> Result<bool, anyhow::Error> {
+        let now = chrono::Utc::now().timestamp();
+
+        let put_res = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("PK", AttributeValue::S(idempotency_key(key, user_id)))
+            .item("Type", AttributeValue::S("IdempotencyKey".to_string()))
+            .item("UserId", AttributeValue::S(user_id.to_string()))
+            .item(
+                "IdempotencyKey",
+                AttributeValue::S(key.as_ref().to_string()),
+            )
+            .item("CreatedAt", AttributeValue::N(now.to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + IDEMPOTENCY_KEY_VALIDITY_SECONDS).to_string()),
+            )
+            .condition_expression("attribute_not_exists(PK)")
+            .send()
+            .await;
+
+        match put_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure claiming idempotency key in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_saved_response(
+        &self,
+        key: &IdempotencyKey,
+        user_id: &str,
+    ) -> Result<Option<SavedResponse>, anyhow::Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(idempotency_key(key, user_id)))
+            .consistent_read(true)
+            .send()
+            .await
+            .context(format!(
+                "Failure reading idempotency key from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        match get_res.item {
+            Some(item) if item.contains_key("ResponseStatusCode") => {
+                Ok(Some(parse_saved_response(&item)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(skip(self, response))]
+    async fn save_response(
+        &self,
+        key: &IdempotencyKey,
+        user_id: &str,
+        response: &SavedResponse,
+    ) -> Result<(), anyhow::Error> {
+        let headers = response
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                AttributeValue::M(HashMap::from([
+                    ("Name".to_string(), AttributeValue::S(name.clone())),
+                    (
+                        "Value".to_string(),
+                        AttributeValue::B(Blob::new(value.clone())),
+                    ),
+                ]))
+            })
+            .collect();
+
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(idempotency_key(key, user_id)))
+            .update_expression(
+                "SET ResponseStatusCode = :status_code, ResponseHeaders = :headers, \
+                 ResponseBody = :body",
+            )
+            .condition_expression("attribute_exists(PK)")
+            .expression_attribute_values(
+                ":status_code",
+                AttributeValue::N(response.status_code.to_string()),
+            )
+            .expression_attribute_values(":headers", AttributeValue::L(headers))
+            .expression_attribute_values(
+                ":body",
+                AttributeValue::B(Blob::new(response.body.clone())),
+            )
+            .send()
+            .await
+            .context(format!(
+                "Failure saving idempotent response to DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn release(&self, key: &IdempotencyKey, user_id: &str) -> Result<(), anyhow::Error> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(idempotency_key(key, user_id)))
+            .send()
+            .await
+            .context(format!(
+                "Failure releasing idempotency key in DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(())
+    }
+}
+
+fn idempotency_key(key: &IdempotencyKey, user_id: &str) -> String {
+    format!("Idempotency#{}#{}", user_id, key.as_ref())
+}
+
+fn parse_saved_response(
+    item: &HashMap<String, AttributeValue>,
+) -> Result<SavedResponse, anyhow::Error> {
+    let status_code = item
+        .get("ResponseStatusCode")
+        .and_then(|status_code| status_code.as_n().ok())
+        .and_then(|status_code| status_code.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Saved response has no ResponseStatusCode"))?;
+
+    let headers = item
+        .get("ResponseHeaders")
+        .and_then(|headers| headers.as_l().ok())
+        .ok_or_else(|| anyhow::anyhow!("Saved response has no ResponseHeaders"))?
+        .iter()
+        .map(|header| {
+            let header = header
+                .as_m()
+                .map_err(|_| anyhow::anyhow!("Saved response header is not a map"))?;
+            let name = header
+                .get("Name")
+                .and_then(|name| name.as_s().ok())
+                .ok_or_else(|| anyhow::anyhow!("Saved response header has no Name"))?;
+            let value = header
+                .get("Value")
+                .and_then(|value| value.as_b().ok())
+                .ok_or_else(|| anyhow::anyhow!("Saved response header has no Value"))?;
+
+            Ok((name.clone(), value.as_ref().to_vec()))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    let body = item
+        .get("ResponseBody")
+        .and_then(|body| body.as_b().ok())
+        .map(|body| body.as_ref().to_vec())
+        .ok_or_else(|| anyhow::anyhow!("Saved response has no ResponseBody"))?;
+
+    Ok(SavedResponse {
+        status_code,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,184 @@
+use crate::authentication::{LoginAttemptSource, LoginAttemptStore, FAILED_LOGIN_WINDOW_SECONDS};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use sha2::{Digest, Sha256};
+
+/// Stores one `LoginAttempts` item per source in the auth table, removed by the table ttl once
+/// its window is over. Items are keyed on a hash of the source, so usernames typed into the login
+/// form are not stored.
+#[derive(Debug, Clone)]
+pub struct DynamoDbLoginAttemptStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbLoginAttemptStore {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    /// Count a failed attempt in a window that is still open. Returns `false` if there is none.
+    async fn increment_failed_attempts(
+        &self,
+        source: &LoginAttemptSource,
+        now: i64,
+    ) -> Result<bool, anyhow::Error> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(login_attempts_key(source)))
+            .update_expression("ADD FailedAttempts :one")
+            .condition_expression("#ttl > :now")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure recording a failed login attempt in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+
+    /// Start a new window with a single failed attempt. Returns `false` if another request opened
+    /// a window in the meantime.
+    async fn start_window(
+        &self,
+        source: &LoginAttemptSource,
+        now: i64,
+    ) -> Result<bool, anyhow::Error> {
+        let put_res = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("PK", AttributeValue::S(login_attempts_key(source)))
+            .item("Type", AttributeValue::S("LoginAttempts".to_string()))
+            .item("FailedAttempts", AttributeValue::N("1".to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + FAILED_LOGIN_WINDOW_SECONDS).to_string()),
+            )
+            .condition_expression("attribute_not_exists(PK) OR #ttl <= :now")
+            .expression_attribute_names("#ttl", "ttl")
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .send()
+            .await;
+
+        match put_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure recording a failed login attempt in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl LoginAttemptStore for DynamoDbLoginAttemptStore {
+    #[tracing::instrument(skip(self, source))]
+    async fn failed_attempts(&self, source: &LoginAttemptSource) -> Result<u32, anyhow::Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(login_attempts_key(source)))
+            .consistent_read(true)
+            .send()
+            .await
+            .context(format!(
+                "Failure reading failed login attempts from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        let item = match get_res.item {
+            Some(item) => item,
+            None => return Ok(0),
+        };
+
+        // DynamoDB removes expired items eventually, not as soon as they expire
+        let expires_at: i64 = item
+            .get("ttl")
+            .and_then(|ttl| ttl.as_n().ok())
+            .and_then(|ttl| ttl.parse().ok())
+            .unwrap_or(0);
+        if expires_at <= chrono::Utc::now().timestamp() {
+            return Ok(0);
+        }
+
+        Ok(item
+            .get("FailedAttempts")
+            .and_then(|failed_attempts| failed_attempts.as_n().ok())
+            .and_then(|failed_attempts| failed_attempts.parse().ok())
+            .unwrap_or(0))
+    }
+
+    #[tracing::instrument(skip(self, source))]
+    async fn record_failed_attempt(
+        &self,
+        source: &LoginAttemptSource,
+    ) -> Result<(), anyhow::Error> {
+        let now = chrono::Utc::now().timestamp();
+
+        // A window is either open, and counted in, or it isn't and is started. The second try
+        // covers a window opened or closed by a concurrent request.
+        for _ in 0..2 {
+            if self.increment_failed_attempts(source, now).await?
+                || self.start_window(source, now).await?
+            {
+                return Ok(());
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "Failure recording a failed login attempt, the window kept changing"
+        ))
+    }
+
+    #[tracing::instrument(skip(self, source))]
+    async fn clear_failed_attempts(
+        &self,
+        source: &LoginAttemptSource,
+    ) -> Result<(), anyhow::Error> {
+        self.client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(login_attempts_key(source)))
+            .send()
+            .await
+            .context(format!(
+                "Failure clearing failed login attempts in DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(())
+    }
+}
+
+fn login_attempts_key(source: &LoginAttemptSource) -> String {
+    format!(
+        "LoginAttempts#{:x}",
+        Sha256::digest(source.to_string().as_bytes())
+    )
+}
@@ -1,14 +1,30 @@
 use crate::domain::new_subscriber::NewSubscriber;
 
-use crate::domain::subscriber_repository::{DatabaseError, SubscriberRepository};
+use crate::domain::subscriber_repository::{
+    DatabaseError, SubscriberRepository, SubscriptionStatus,
+};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, TransactWriteItem, Update};
 use aws_sdk_dynamodb::Client;
+use chrono::Utc;
+use std::collections::HashMap;
 use opentelemetry::trace::TraceContextExt;
 use tracing::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+/// Subscription tokens can be used to confirm a subscription for 24 hours.
+const TOKEN_VALIDITY_SECONDS: i64 = 24 * 60 * 60;
+
+/// A new confirmation token is only issued to a subscriber once every 5 minutes, so the resend
+/// and subscribe forms can't be used to flood an address with confirmation emails.
+const TOKEN_COOLDOWN_SECONDS: i64 = 5 * 60;
+
+/// Name of the attribute DynamoDB uses to expire items from the newsletter table.
+const TTL_ATTRIBUTE: &str = "ttl";
+
 #[derive(Debug, Clone)]
 pub struct DynamoDbSubscriberRepository {
     client: Client,
@@ -23,130 +39,363 @@ impl DynamoDbSubscriberRepository {
 
 #[async_trait]
 impl SubscriberRepository for DynamoDbSubscriberRepository {
-    #[tracing::instrument(skip(new_subscriber))]
-    async fn insert_subscriber(
+    #[tracing::instrument(skip(new_subscriber, subscription_token))]
+    async fn insert_subscriber_with_token(
         &self,
         new_subscriber: &NewSubscriber,
-    ) -> Result<String, anyhow::Error> {
+        subscription_token: &str,
+    ) -> Result<String, DatabaseError> {
         let trace_details = get_trace_and_span_id();
+        let created_at = Utc::now().timestamp();
+        let subscriber_id = new_subscriber.email.to_string();
 
-        let mut _put_res_builder = self
-            .client
-            .put_item()
+        let mut _put_subscriber_builder = Put::builder()
             .table_name(&self.table_name)
-            .item("PK", AttributeValue::S(new_subscriber.email.to_string()))
+            .item("PK", AttributeValue::S(subscriber_id.clone()))
             .item("Type", AttributeValue::S("Subscriber".to_string()))
+            .item("EmailAddress", AttributeValue::S(subscriber_id.clone()))
+            .item(
+                "Name",
+                AttributeValue::S(new_subscriber.name.as_ref().to_string()),
+            )
+            .item("SubscribedAt", AttributeValue::N(created_at.to_string()))
+            .item(
+                "LastTokenCreatedAt",
+                AttributeValue::N(created_at.to_string()),
+            )
+            .item(
+                "SubscriptionStatus",
+                AttributeValue::S("pending".to_string()),
+            )
+            .condition_expression("attribute_not_exists(PK)");
+
+        let mut _put_token_builder = Put::builder()
+            .table_name(&self.table_name)
+            .item("PK", AttributeValue::S(subscription_token.to_string()))
+            .item("EmailAddress", AttributeValue::S(subscriber_id.clone()))
+            .item("Type", AttributeValue::S("SubscriberToken".to_string()))
+            .item("CreatedAt", AttributeValue::N(created_at.to_string()))
             .item(
-                "EmailAddress",
-                AttributeValue::S(new_subscriber.email.to_string()),
+                TTL_ATTRIBUTE,
+                AttributeValue::N((created_at + TOKEN_VALIDITY_SECONDS).to_string()),
             )
-            .condition_expression("attribute_not_exists(PK)".to_string());
+            .condition_expression("attribute_not_exists(PK)");
 
-        _put_res_builder = match trace_details {
-            None => _put_res_builder,
-            Some((trace_id, span_id)) => _put_res_builder
+        // The token stream sends the confirmation email, and continues the trace from these
+        if let Some((trace_id, span_id)) = trace_details {
+            _put_subscriber_builder = _put_subscriber_builder
+                .item("TraceParent", AttributeValue::S(trace_id.clone()))
+                .item("ParentSpan", AttributeValue::S(span_id.clone()));
+            _put_token_builder = _put_token_builder
                 .item("TraceParent", AttributeValue::S(trace_id))
-                .item("ParentSpan", AttributeValue::S(span_id)),
-        };
+                .item("ParentSpan", AttributeValue::S(span_id));
+        }
 
-        _put_res_builder.send().await.context(format!(
-            "Failure inserting record to DynamoDB. Using table {}",
-            &self.table_name
-        ))?;
+        let put_subscriber = _put_subscriber_builder
+            .build()
+            .context("Failure building the subscriber item")?;
+        let put_token = _put_token_builder
+            .build()
+            .context("Failure building the subscriber token item")?;
+
+        let transact_res = self
+            .client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().put(put_subscriber).build())
+            .transact_items(TransactWriteItem::builder().put(put_token).build())
+            .send()
+            .await;
 
-        Ok(new_subscriber.email.to_string())
+        match transact_res {
+            Ok(_) => Ok(subscriber_id),
+            Err(e) if is_subscriber_condition_failure(&e) => Err(DatabaseError::UserExists(
+                "A subscriber with this email already exists".to_string(),
+            )),
+            Err(e) => Err(DatabaseError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure inserting record to DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
     }
+
     #[tracing::instrument(skip(subscriber_id, subscription_token))]
     async fn store_token(
         &self,
         subscriber_id: String,
         subscription_token: &str,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<bool, anyhow::Error> {
         let trace_details = get_trace_and_span_id();
+        let created_at = Utc::now().timestamp();
 
-        let mut _put_res_builder = self
-            .client
-            .put_item()
+        // Subscribers stored before the cooldown was introduced have no LastTokenCreatedAt. An
+        // unsubscribed subscriber asking for a token is subscribing again, so is pending again
+        let update_subscriber = Update::builder()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(subscriber_id.clone()))
+            .update_expression(
+                "SET LastTokenCreatedAt = :created_at, SubscriptionStatus = :pending",
+            )
+            .condition_expression(
+                "attribute_exists(PK) AND (attribute_not_exists(LastTokenCreatedAt) \
+                OR LastTokenCreatedAt <= :cooldown_start) \
+                AND (attribute_not_exists(SubscriptionStatus) OR SubscriptionStatus <> :confirmed)",
+            )
+            .expression_attribute_values(":created_at", AttributeValue::N(created_at.to_string()))
+            .expression_attribute_values(":pending", AttributeValue::S("pending".to_string()))
+            .expression_attribute_values(":confirmed", AttributeValue::S("confirmed".to_string()))
+            .expression_attribute_values(
+                ":cooldown_start",
+                AttributeValue::N((created_at - TOKEN_COOLDOWN_SECONDS).to_string()),
+            )
+            .build()
+            .context("Failure building the subscriber update")?;
+
+        let mut _put_token_builder = Put::builder()
             .table_name(&self.table_name)
             .item("PK", AttributeValue::S(subscription_token.to_string()))
             .item("EmailAddress", AttributeValue::S(subscriber_id.to_string()))
             .item("Type", AttributeValue::S("SubscriberToken".to_string()))
-            .condition_expression("attribute_not_exists(PK)".to_string());
+            .item("CreatedAt", AttributeValue::N(created_at.to_string()))
+            .item(
+                TTL_ATTRIBUTE,
+                AttributeValue::N((created_at + TOKEN_VALIDITY_SECONDS).to_string()),
+            )
+            .condition_expression("attribute_not_exists(PK)");
 
-        _put_res_builder = match trace_details {
-            None => _put_res_builder,
-            Some((trace_id, span_id)) => _put_res_builder
+        _put_token_builder = match trace_details {
+            None => _put_token_builder,
+            Some((trace_id, span_id)) => _put_token_builder
                 .item("TraceParent", AttributeValue::S(trace_id))
                 .item("ParentSpan", AttributeValue::S(span_id)),
         };
 
-        _put_res_builder.send().await.context(format!(
-            "Failure inserting record to DynamoDB. Using table {}",
-            &self.table_name
-        ))?;
+        let put_token = _put_token_builder
+            .build()
+            .context("Failure building the subscriber token item")?;
+
+        let transact_res = self
+            .client
+            .transact_write_items()
+            .transact_items(
+                TransactWriteItem::builder()
+                    .update(update_subscriber)
+                    .build(),
+            )
+            .transact_items(TransactWriteItem::builder().put(put_token).build())
+            .send()
+            .await;
 
-        Ok(())
+        match transact_res {
+            Ok(_) => Ok(true),
+            Err(e) if is_subscriber_condition_failure(&e) => Ok(false),
+            Err(e) => Err(e).context(format!(
+                "Failure inserting record to DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
     }
 
     #[tracing::instrument(skip(subscription_token))]
     async fn get_subscriber_id_from_token(
         &self,
         subscription_token: &str,
-    ) -> Result<Option<String>, anyhow::Error> {
-        let query_res = self
+    ) -> Result<String, DatabaseError> {
+        let get_res = self
             .client
-            .query()
+            .get_item()
             .table_name(&self.table_name)
-            .key_condition_expression("#pk = :pk")
-            .expression_attribute_names("#pk", "PK")
-            .expression_attribute_values(":pk", AttributeValue::S(subscription_token.to_string()))
+            .key("PK", AttributeValue::S(subscription_token.to_string()))
             .send()
-            .await?;
-
-        if let Some(items) = query_res.items {
-            if items.is_empty() {
-                Err(DatabaseError::TokenNotFoundError("Token not found".to_string()).into())
-            } else {
-                let first_item = items.first().unwrap();
+            .await
+            .context(format!(
+                "Failure reading token from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
 
-                Ok(Some(first_item["EmailAddress"].as_s().unwrap().clone()))
+        let item = match get_res.item {
+            Some(item) if item_type(&item) == Some("SubscriberToken") => item,
+            _ => {
+                return Err(DatabaseError::TokenNotFoundError(
+                    "Token not found".to_string(),
+                ))
             }
-        } else {
-            Err(DatabaseError::TokenNotFoundError("Token not found".to_string()).into())
+        };
+
+        // Tokens written before expiry was introduced have no TTL and are treated as expired
+        let expires_at = item
+            .get(TTL_ATTRIBUTE)
+            .and_then(|ttl| ttl.as_n().ok())
+            .and_then(|ttl| ttl.parse::<i64>().ok())
+            .unwrap_or(0);
+
+        if expires_at <= Utc::now().timestamp() {
+            return Err(DatabaseError::TokenExpired("Token has expired".to_string()));
         }
+
+        item.get("EmailAddress")
+            .and_then(|email| email.as_s().ok())
+            .cloned()
+            .ok_or_else(|| {
+                DatabaseError::UnexpectedError(anyhow::anyhow!(
+                    "Subscriber token has no email address"
+                ))
+            })
+    }
+
+    #[tracing::instrument(skip(subscriber_id))]
+    async fn get_subscription_status(
+        &self,
+        subscriber_id: &str,
+    ) -> Result<Option<SubscriptionStatus>, anyhow::Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(subscriber_id.to_string()))
+            .send()
+            .await
+            .context(format!(
+                "Failure reading subscriber from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        let item = match get_res.item {
+            Some(item) if item_type(&item) == Some("Subscriber") => item,
+            _ => return Ok(None),
+        };
+
+        let is_confirmed = item
+            .get("GSI1PK")
+            .and_then(|gsi1pk| gsi1pk.as_s().ok())
+            .map(|gsi1pk| gsi1pk == "confirmed")
+            .unwrap_or(false);
+
+        // Subscribers stored before the status was recorded are only confirmed through GSI1
+        let status = match item.get("SubscriptionStatus").and_then(|s| s.as_s().ok()) {
+            Some(status) if status == "unsubscribed" => SubscriptionStatus::Unsubscribed,
+            Some(status) if status == "confirmed" => SubscriptionStatus::Confirmed,
+            Some(status) if status == "pending" => SubscriptionStatus::Pending,
+            _ if is_confirmed => SubscriptionStatus::Confirmed,
+            _ => SubscriptionStatus::Pending,
+        };
+
+        Ok(Some(status))
     }
 
     #[tracing::instrument(skip(subscriber_id))]
-    async fn confirm_subscriber(&self, subscriber_id: String) -> Result<(), anyhow::Error> {
+    async fn confirm_subscriber(&self, subscriber_id: String) -> Result<bool, anyhow::Error> {
         let trace_details = get_trace_and_span_id();
 
-        let mut _put_res_builder = self
+        let mut update_expression = "SET GSI1PK = :gsi1pk, GSI1SK = :gsi1sk, \
+            SubscriptionStatus = :status, ConfirmedAt = :confirmed_at"
+            .to_string();
+
+        let mut _update_res_builder = self
             .client
-            .put_item()
+            .update_item()
             .table_name(&self.table_name)
-            .item("PK", AttributeValue::S(subscriber_id.to_string()))
-            .item("Type", AttributeValue::S("Subscriber".to_string()))
-            .item("EmailAddress", AttributeValue::S(subscriber_id.to_string()))
-            .item("GSI1PK", AttributeValue::S("confirmed".to_string()))
-            .item("GSI1SK", AttributeValue::S(subscriber_id.to_string()));
+            .key("PK", AttributeValue::S(subscriber_id.to_string()))
+            // Tokens issued before unsubscribing stay valid until they expire
+            .condition_expression(
+                "attribute_exists(PK) AND (attribute_not_exists(SubscriptionStatus) \
+                OR SubscriptionStatus <> :unsubscribed)",
+            )
+            .expression_attribute_values(":gsi1pk", AttributeValue::S("confirmed".to_string()))
+            .expression_attribute_values(":gsi1sk", AttributeValue::S(subscriber_id.to_string()))
+            .expression_attribute_values(":status", AttributeValue::S("confirmed".to_string()))
+            .expression_attribute_values(
+                ":confirmed_at",
+                AttributeValue::N(Utc::now().timestamp().to_string()),
+            )
+            .expression_attribute_values(
+                ":unsubscribed",
+                AttributeValue::S("unsubscribed".to_string()),
+            );
 
-        _put_res_builder = match trace_details {
-            None => _put_res_builder,
-            Some((trace_id, span_id)) => _put_res_builder
-                .item("TraceParent", AttributeValue::S(trace_id))
-                .item("ParentSpan", AttributeValue::S(span_id)),
+        _update_res_builder = match trace_details {
+            None => _update_res_builder,
+            Some((trace_id, span_id)) => {
+                update_expression
+                    .push_str(", TraceParent = :trace_parent, ParentSpan = :parent_span");
+
+                _update_res_builder
+                    .expression_attribute_values(":trace_parent", AttributeValue::S(trace_id))
+                    .expression_attribute_values(":parent_span", AttributeValue::S(span_id))
+            }
         };
 
-        _put_res_builder.send().await.context(format!(
-            "Failure inserting record to DynamoDB. Using table {}",
-            &self.table_name
-        ))?;
+        let update_res = _update_res_builder
+            .update_expression(update_expression)
+            .send()
+            .await;
 
-        Ok(())
+        match update_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure confirming subscriber in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
     }
 
-    async fn apply_migrations(&self) -> Result<(), anyhow::Error> {
-        todo!()
+    #[tracing::instrument(skip(subscriber_id))]
+    async fn unsubscribe(&self, subscriber_id: String) -> Result<(), anyhow::Error> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(subscriber_id))
+            .update_expression("SET SubscriptionStatus = :status REMOVE GSI1PK, GSI1SK")
+            .condition_expression("attribute_exists(PK) AND #type = :type")
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(":status", AttributeValue::S("unsubscribed".to_string()))
+            .expression_attribute_values(":type", AttributeValue::S("Subscriber".to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                tracing::info!("Unsubscribe requested for an unknown subscriber");
+                Ok(())
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure unsubscribing subscriber in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+}
+
+fn item_type(item: &HashMap<String, AttributeValue>) -> Option<&str> {
+    item.get("Type")
+        .and_then(|item_type| item_type.as_s().ok())
+        .map(String::as_str)
+}
+
+/// The subscriber is the first item of both transactions, so its cancellation reason is the first.
+fn is_subscriber_condition_failure(e: &SdkError<TransactWriteItemsError>) -> bool {
+    match e.as_service_error() {
+        Some(TransactWriteItemsError::TransactionCanceledException(e)) => {
+            e.cancellation_reasons()
+                .first()
+                .and_then(|reason| reason.code())
+                == Some("ConditionalCheckFailed")
+        }
+        _ => false,
     }
 }
 
@@ -0,0 +1,196 @@
+use crate::authentication::{StoredTwoFactor, TwoFactorStore};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+
+/// Stores the two-factor state on the user items of the auth table.
+#[derive(Debug, Clone)]
+pub struct DynamoDbTwoFactorStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbTwoFactorStore {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl TwoFactorStore for DynamoDbTwoFactorStore {
+    #[tracing::instrument(skip(self))]
+    async fn get_two_factor(&self, username: &str) -> Result<StoredTwoFactor, anyhow::Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .consistent_read(true)
+            .send()
+            .await
+            .context(format!(
+                "Failure reading the two-factor state from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        let item = match get_res.item {
+            Some(item) => item,
+            None => return Ok(StoredTwoFactor::default()),
+        };
+
+        let string_attribute = |name: &str| {
+            item.get(name)
+                .and_then(|value| value.as_s().ok())
+                .map(|value| value.to_string())
+        };
+
+        Ok(StoredTwoFactor {
+            encrypted_secret: string_attribute("TotpSecret"),
+            encrypted_pending_secret: string_attribute("PendingTotpSecret"),
+            recovery_codes_remaining: item
+                .get("RecoveryCodes")
+                .and_then(|codes| codes.as_ss().ok())
+                .map(|codes| codes.len())
+                .unwrap_or(0),
+        })
+    }
+
+    #[tracing::instrument(skip(self, encrypted_secret))]
+    async fn store_pending_secret(
+        &self,
+        username: &str,
+        encrypted_secret: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression("SET PendingTotpSecret = :secret")
+            .condition_expression("attribute_exists(password_hash)")
+            .expression_attribute_values(":secret", AttributeValue::S(encrypted_secret.to_string()))
+            .send()
+            .await
+            .context(format!(
+                "Failure storing the pending TOTP secret in DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, encrypted_secret, recovery_code_hashes))]
+    async fn enable_two_factor(
+        &self,
+        username: &str,
+        encrypted_secret: &str,
+        recovery_code_hashes: &[String],
+        time_step: i64,
+    ) -> Result<bool, anyhow::Error> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression(
+                "SET TotpSecret = :secret, RecoveryCodes = :recovery_codes, \
+                 TotpLastStep = :time_step REMOVE PendingTotpSecret",
+            )
+            .condition_expression("PendingTotpSecret = :secret")
+            .expression_attribute_values(":secret", AttributeValue::S(encrypted_secret.to_string()))
+            .expression_attribute_values(
+                ":recovery_codes",
+                AttributeValue::Ss(recovery_code_hashes.to_vec()),
+            )
+            .expression_attribute_values(":time_step", AttributeValue::N(time_step.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure enabling two-factor authentication in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn use_time_step(&self, username: &str, time_step: i64) -> Result<bool, anyhow::Error> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression("SET TotpLastStep = :time_step")
+            .condition_expression(
+                "attribute_exists(TotpSecret) AND \
+                 (attribute_not_exists(TotpLastStep) OR TotpLastStep < :time_step)",
+            )
+            .expression_attribute_values(":time_step", AttributeValue::N(time_step.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure recording the TOTP time step in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+
+    #[tracing::instrument(skip(self, recovery_code_hash))]
+    async fn use_recovery_code(
+        &self,
+        username: &str,
+        recovery_code_hash: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression("DELETE RecoveryCodes :recovery_code")
+            .condition_expression("contains(RecoveryCodes, :recovery_code_hash)")
+            .expression_attribute_values(
+                ":recovery_code",
+                AttributeValue::Ss(vec![recovery_code_hash.to_string()]),
+            )
+            .expression_attribute_values(
+                ":recovery_code_hash",
+                AttributeValue::S(recovery_code_hash.to_string()),
+            )
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure using a recovery code in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+}
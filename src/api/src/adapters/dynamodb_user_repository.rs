@@ -1,4 +1,7 @@
-use crate::authentication::{compute_password_hash, UserAuthenticationError, UserRepository};
+use crate::authentication::{
+    compute_password_hash, Role, User, UserAuthenticationError, UserCreationError, UserRepository,
+};
+use crate::domain::subscriber_email::SubscriberEmail;
 
 use telemetry::spawn_blocking_with_tracing;
 use anyhow::{Context, Error};
@@ -6,6 +9,11 @@ use async_trait::async_trait;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 use secrecy::{ExposeSecret, Secret};
+use std::collections::HashMap;
+
+/// Users are indexed on GSI1 under a single partition, sorted by username, so they can be listed
+/// without scanning the sessions and idempotency keys stored in the same table.
+const USER_INDEX: &str = "User";
 
 #[derive(Debug, Clone)]
 pub struct DynamoDbUserRepository {
@@ -35,15 +43,26 @@ impl UserRepository for DynamoDbUserRepository {
             .await
             .context("Failed to get user credentials")?;
 
-        match creds_result.item {
-            None => Err(UserAuthenticationError::UserNotFoundError(
-                "User not found".to_string(),
-            )),
-            Some(creds) => Ok(Some((
-                creds["PK"].as_s().unwrap().to_string(),
-                Secret::new(creds["password_hash"].as_s().unwrap().to_string()),
-            ))),
+        let creds = match creds_result.item {
+            // Sessions and idempotency keys are stored in the same table, without a password
+            Some(creds) if creds.contains_key("password_hash") => creds,
+            _ => {
+                return Err(UserAuthenticationError::UserNotFoundError(
+                    "User not found".to_string(),
+                ))
+            }
+        };
+
+        if is_disabled(&creds) {
+            return Err(UserAuthenticationError::UserNotFoundError(
+                "User is disabled".to_string(),
+            ));
         }
+
+        Ok(Some((
+            creds["PK"].as_s().unwrap().to_string(),
+            Secret::new(creds["password_hash"].as_s().unwrap().to_string()),
+        )))
     }
 
     #[tracing::instrument(name = "Changing password", skip(user_id, password))]
@@ -56,13 +75,16 @@ impl UserRepository for DynamoDbUserRepository {
             .await?
             .context("Failed to hash password")?;
 
-        let _put_res = self
+        // Only the hash is replaced, the role and status of the user are kept
+        let _update_res = self
             .client
-            .put_item()
+            .update_item()
             .table_name(&self.table_name)
-            .item("PK", AttributeValue::S(user_id.to_string()))
-            .item(
-                "password_hash",
+            .key("PK", AttributeValue::S(user_id.to_string()))
+            .update_expression("SET password_hash = :password_hash")
+            .condition_expression("attribute_exists(password_hash)")
+            .expression_attribute_values(
+                ":password_hash",
                 AttributeValue::S(password_hash.expose_secret().to_string()),
             )
             .send()
@@ -75,23 +97,211 @@ impl UserRepository for DynamoDbUserRepository {
         Ok(())
     }
 
-    async fn seed(&self) -> Result<(), Error> {
-        let _put_res = self
+    #[tracing::instrument(name = "Creating user", skip(email, password))]
+    async fn create_user(
+        &self,
+        username: &str,
+        email: &SubscriberEmail,
+        password: Secret<String>,
+        role: Role,
+    ) -> std::result::Result<(), UserCreationError> {
+        let password_hash = spawn_blocking_with_tracing(move || compute_password_hash(password))
+            .await
+            .context("Failed to spawn blocking task.")?
+            .context("Failed to hash password")?;
+
+        let put_res = self
             .client
             .put_item()
             .table_name(&self.table_name)
-            .item("PK", AttributeValue::S("admin".to_string()))
+            .item("PK", AttributeValue::S(username.to_string()))
             .item(
                 "password_hash",
-                AttributeValue::S("$argon2id$v=19$m=15000,t=2,p=1$94TDOx1pXBY0GzKpi774dQ$v9LyxFtOk2qYPI5AQNzut0D6H6/3bOurbCVbZqYD1aM".to_string()),
+                AttributeValue::S(password_hash.expose_secret().to_string()),
             )
+            .item("EmailAddress", AttributeValue::S(email.to_string()))
+            .item("Role", AttributeValue::S(role.as_str().to_string()))
+            .item("Disabled", AttributeValue::Bool(false))
+            .item("GSI1PK", AttributeValue::S(USER_INDEX.to_string()))
+            .item("GSI1SK", AttributeValue::S(username.to_string()))
+            .condition_expression("attribute_not_exists(PK)")
+            .send()
+            .await;
+
+        match put_res {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Err(UserCreationError::UserExists(format!(
+                    "A user named {} already exists",
+                    username
+                )))
+            }
+            Err(e) => Err(UserCreationError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure inserting record to DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+
+    #[tracing::instrument(name = "Retrieving user")]
+    async fn get_user(&self, username: &str) -> std::result::Result<Option<User>, Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
             .send()
             .await
             .context(format!(
-                "Failure inserting record to DynamoDB. Using table {}",
+                "Failure reading user from DynamoDB. Using table {}",
                 &self.table_name
             ))?;
 
-        Ok(())
+        match get_res.item {
+            Some(item) if item.contains_key("password_hash") => Ok(Some(parse_user(&item)?)),
+            _ => Ok(None),
+        }
     }
+
+    #[tracing::instrument(name = "Listing users")]
+    async fn list_users(&self) -> std::result::Result<Vec<User>, Error> {
+        let query_results: std::result::Result<Vec<_>, _> = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name("GSI1")
+            .key_condition_expression("GSI1PK = :gsi1pk")
+            .expression_attribute_values(":gsi1pk", AttributeValue::S(USER_INDEX.to_string()))
+            .into_paginator()
+            .items()
+            .send()
+            .collect()
+            .await;
+
+        // GSI1 is sorted by username
+        query_results
+            .context(format!(
+                "Failure listing users in DynamoDB. Using table {}",
+                &self.table_name
+            ))?
+            .iter()
+            .map(parse_user)
+            .collect()
+    }
+
+    #[tracing::instrument(name = "Setting user email", skip(email))]
+    async fn set_email(
+        &self,
+        username: &str,
+        email: &SubscriberEmail,
+    ) -> std::result::Result<(), UserAuthenticationError> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression("SET EmailAddress = :email")
+            .condition_expression("attribute_exists(password_hash)")
+            .expression_attribute_values(":email", AttributeValue::S(email.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Err(UserAuthenticationError::UserNotFoundError(format!(
+                    "There is no user named {}",
+                    username
+                )))
+            }
+            Err(e) => Err(UserAuthenticationError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure setting the user email in DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+
+    #[tracing::instrument(name = "Disabling user")]
+    async fn disable_user(
+        &self,
+        username: &str,
+    ) -> std::result::Result<(), UserAuthenticationError> {
+        let update_res = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .update_expression("SET #disabled = :disabled")
+            .condition_expression("attribute_exists(password_hash)")
+            .expression_attribute_names("#disabled", "Disabled")
+            .expression_attribute_values(":disabled", AttributeValue::Bool(true))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Err(UserAuthenticationError::UserNotFoundError(format!(
+                    "There is no user named {}",
+                    username
+                )))
+            }
+            Err(e) => Err(UserAuthenticationError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure disabling user in DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+}
+
+fn is_disabled(item: &HashMap<String, AttributeValue>) -> bool {
+    item.get("Disabled")
+        .and_then(|disabled| disabled.as_bool().ok())
+        .copied()
+        .unwrap_or(false)
+}
+
+fn parse_user(item: &HashMap<String, AttributeValue>) -> std::result::Result<User, Error> {
+    let username = item
+        .get("PK")
+        .and_then(|pk| pk.as_s().ok())
+        .context("User has no PK")?
+        .to_string();
+
+    // Users stored before roles were introduced are given the owner role by a migration, until
+    // then they can only read
+    let role = match item.get("Role").and_then(|role| role.as_s().ok()) {
+        Some(role) => Role::parse(role).map_err(anyhow::Error::msg)?,
+        None => Role::Viewer,
+    };
+
+    let email = item
+        .get("EmailAddress")
+        .and_then(|email| email.as_s().ok())
+        .cloned();
+
+    Ok(User {
+        username,
+        email,
+        role,
+        disabled: is_disabled(item),
+    })
 }
@@ -0,0 +1,231 @@
+use crate::adapters::dynamo_db_session_store::{generate_session_key, SessionState};
+use crate::session_state::{ActiveSession, SessionRegistry};
+use actix_session::storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError};
+use actix_web::cookie::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The session state key holding the id of the logged in user.
+const USER_ID_KEY: &str = "user_id";
+
+/// Keeps sessions in the memory of the process, for tests and local development without
+/// DynamoDB. Sessions are lost when the process stops, and aren't shared between instances.
+#[derive(Clone, Default)]
+pub struct InMemorySessionStore {
+    sessions: Arc<Mutex<HashMap<String, StoredSession>>>,
+}
+
+struct StoredSession {
+    state: SessionState,
+    created_at: i64,
+    expires_at: i64,
+}
+
+impl StoredSession {
+    fn has_expired(&self) -> bool {
+        self.expires_at <= chrono::Utc::now().timestamp()
+    }
+
+    fn user_id(&self) -> Option<String> {
+        self.state
+            .get(USER_ID_KEY)
+            .and_then(|user_id| serde_json::from_str(user_id).ok())
+    }
+}
+
+impl InMemorySessionStore {
+    fn sessions(&self) -> MutexGuard<'_, HashMap<String, StoredSession>> {
+        // The lock is never held across a panic, so it can't be poisoned
+        self.sessions.lock().unwrap()
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let mut sessions = self.sessions();
+
+        match sessions.get(session_key.as_ref()) {
+            Some(session) if session.has_expired() => {
+                sessions.remove(session_key.as_ref());
+                Ok(None)
+            }
+            Some(session) => Ok(Some(session.state.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let session_key = generate_session_key();
+        let now = chrono::Utc::now().timestamp();
+
+        self.sessions().insert(
+            session_key.as_ref().to_string(),
+            StoredSession {
+                state: session_state,
+                created_at: now,
+                expires_at: now + ttl.whole_seconds(),
+            },
+        );
+
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        {
+            let mut sessions = self.sessions();
+            if let Some(session) = sessions.get_mut(session_key.as_ref()) {
+                if !session.has_expired() {
+                    session.state = session_state;
+                    session.expires_at = chrono::Utc::now().timestamp() + ttl.whole_seconds();
+                    return Ok(session_key);
+                }
+            }
+        }
+
+        // The session expired since it was loaded, store it under a new key
+        self.save(session_state, ttl)
+            .await
+            .map_err(|err| match err {
+                SaveError::Serialization(err) => UpdateError::Serialization(err),
+                SaveError::Other(err) => UpdateError::Other(err),
+            })
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(session) = self.sessions().get_mut(session_key.as_ref()) {
+            session.expires_at = chrono::Utc::now().timestamp() + ttl.whole_seconds();
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        self.sessions().remove(session_key.as_ref());
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionRegistry for InMemorySessionStore {
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<ActiveSession>, anyhow::Error> {
+        let mut sessions: Vec<_> = self
+            .sessions()
+            .iter()
+            .filter(|(_, session)| {
+                !session.has_expired() && session.user_id().as_deref() == Some(user_id)
+            })
+            .map(|(session_key, session)| ActiveSession {
+                revocation_id: revocation_id(session_key),
+                created_at: session.created_at,
+                state: session.state.clone(),
+            })
+            .collect();
+
+        sessions.sort_by_key(|session| session.created_at);
+
+        Ok(sessions)
+    }
+
+    async fn revoke_session(
+        &self,
+        user_id: &str,
+        revocation_id_to_revoke: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let mut sessions = self.sessions();
+
+        let session_key = sessions
+            .iter()
+            .find(|(session_key, session)| {
+                revocation_id(session_key) == revocation_id_to_revoke
+                    && session.user_id().as_deref() == Some(user_id)
+            })
+            .map(|(session_key, _)| session_key.clone());
+
+        Ok(match session_key {
+            Some(session_key) => sessions.remove(&session_key).is_some(),
+            None => false,
+        })
+    }
+}
+
+fn revocation_id(session_key: &str) -> String {
+    format!("{:x}", Sha256::digest(session_key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InMemorySessionStore;
+    use crate::session_state::SessionRegistry;
+    use actix_session::storage::SessionStore;
+    use actix_web::cookie::time::Duration;
+    use std::collections::HashMap;
+
+    fn state_of(user_id: &str) -> HashMap<String, String> {
+        HashMap::from([("user_id".to_string(), format!("\"{}\"", user_id))])
+    }
+
+    #[tokio::test]
+    async fn sessions_round_trip() {
+        let store = InMemorySessionStore::default();
+
+        let session_key = store
+            .save(state_of("ursula"), &Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.load(&session_key).await.unwrap(),
+            Some(state_of("ursula"))
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_not_loaded() {
+        let store = InMemorySessionStore::default();
+
+        let session_key = store
+            .save(state_of("ursula"), &Duration::seconds(-1))
+            .await
+            .unwrap();
+
+        assert_eq!(store.load(&session_key).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn sessions_are_listed_and_revoked_per_user() {
+        let store = InMemorySessionStore::default();
+        let session_key = store
+            .save(state_of("ursula"), &Duration::hours(1))
+            .await
+            .unwrap();
+        store
+            .save(state_of("ged"), &Duration::hours(1))
+            .await
+            .unwrap();
+
+        let sessions = store.list_sessions("ursula").await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        let revocation_id = &sessions[0].revocation_id;
+
+        assert!(!store.revoke_session("ged", revocation_id).await.unwrap());
+        assert!(store.revoke_session("ursula", revocation_id).await.unwrap());
+        assert_eq!(store.load(&session_key).await.unwrap(), None);
+        assert_eq!(store.list_sessions("ged").await.unwrap().len(), 1);
+    }
+}
@@ -1,6 +1,12 @@
+pub mod configured_session_store;
 pub mod dynamo_db_session_store;
+pub mod dynamodb_idempotency_store;
+pub mod dynamodb_login_attempt_store;
 pub mod dynamodb_subscriber_repository;
+pub mod dynamodb_two_factor_store;
 pub mod dynamodb_user_repository;
+pub mod in_memory_session_store;
 mod s3_newsletter_metadata_storage;
+pub mod session_encryption;
 
 pub use crate::adapters::s3_newsletter_metadata_storage::S3NewsletterMetadataStorage;
@@ -1,11 +1,51 @@
-use crate::domain::{NewsletterMetadata, NewsletterStore, NewsletterStoreError};
+use crate::domain::subscriber_email::SubscriberEmail;
+use crate::domain::{
+    NewsletterDraft, NewsletterIssueId, NewsletterIssueSummary, NewsletterMetadata,
+    NewsletterStore, NewsletterStoreError, ScheduledNewsletter,
+};
 use telemetry::get_trace_and_span_id;
 use anyhow::Context;
 use async_trait::async_trait;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{AttributeValue, Delete, Put, TransactWriteItem, Update};
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::Client;
 use serde::Serialize;
+use std::collections::HashMap;
+
+/// Issues waiting for their send time. They are indexed on GSI1 by send time, and the backend
+/// changes their type to `NewsletterIssue` once they are due, which starts the send.
+const SCHEDULED_ISSUE_TYPE: &str = "ScheduledNewsletterIssue";
+
+/// Drafts are indexed on GSI1 by title so they can be listed. They are never picked up by the
+/// send newsletter pipe until they are published.
+const DRAFT_ISSUE_TYPE: &str = "DraftNewsletterIssue";
+
+/// Issues that have been sent, or are being sent, are indexed on GSI1 by their zero padded
+/// publish time, which is the newsletter history and archive.
+const PUBLISHED_ISSUE_INDEX: &str = "PublishedNewsletterIssue";
+
+/// The backend counts the deliveries of an issue on a `NewsletterIssueStats#{issue_id}` item,
+/// indexed on GSI1 by issue id. They are kept off the issue item, as the send newsletter pipe
+/// picks up every update of it.
+const ISSUE_STATS_INDEX: &str = "NewsletterIssueStats";
+
+/// Every issue reserves the slug of its title with a `NewsletterSlug#{slug}` item, written in the
+/// same transaction as the issue. The slug is the address of the issue in the public archive, so
+/// two issues can't share one.
+const SLUG_TYPE: &str = "NewsletterSlug";
+
+/// How long a test send item is kept once the backend has picked it up.
+const TEST_SEND_VALIDITY_SECONDS: i64 = 24 * 60 * 60;
+
+/// The type an issue is stored with decides whether, and when, the backend sends it.
+#[derive(Debug)]
+enum IssueState {
+    Draft,
+    Published,
+    Scheduled(i64),
+}
 
 pub struct S3NewsletterMetadataStorage {
     s3_client: Client,
@@ -39,68 +79,523 @@ impl NewsletterStore for S3NewsletterMetadataStorage {
     async fn store_newsletter_metadata(
         &self,
         metadata: NewsletterMetadata,
-    ) -> Result<String, NewsletterStoreError> {
-        let json_bytes = json_bytes(&metadata);
+    ) -> Result<NewsletterIssueId, NewsletterStoreError> {
+        let state = match metadata.send_at {
+            None => IssueState::Published,
+            Some(send_at) => IssueState::Scheduled(send_at),
+        };
 
-        let body = ByteStream::from(json_bytes);
+        self.store_issue(metadata, state).await
+    }
+
+    #[tracing::instrument(name = "list_scheduled_newsletters", skip(self))]
+    async fn list_scheduled_newsletters(
+        &self,
+    ) -> Result<Vec<ScheduledNewsletter>, NewsletterStoreError> {
+        let items = self.query_gsi1(SCHEDULED_ISSUE_TYPE).await?;
+
+        let scheduled = items
+            .iter()
+            .map(parse_scheduled_newsletter)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(scheduled)
+    }
+
+    #[tracing::instrument(name = "cancel_scheduled_newsletter", skip(self))]
+    async fn cancel_scheduled_newsletter(
+        &self,
+        issue_id: &NewsletterIssueId,
+    ) -> Result<(), NewsletterStoreError> {
+        let cancel_issue = Update::builder()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(issue_key(issue_id)))
+            .update_expression("SET #type = :cancelled, CancelledAt = :now REMOVE GSI1PK, GSI1SK")
+            .condition_expression("#type = :scheduled")
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(
+                ":cancelled",
+                AttributeValue::S("CancelledNewsletterIssue".to_string()),
+            )
+            .expression_attribute_values(
+                ":scheduled",
+                AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+            )
+            .expression_attribute_values(
+                ":now",
+                AttributeValue::N(chrono::Utc::now().timestamp().to_string()),
+            )
+            .build()
+            .context("Failure building the cancel scheduled newsletter request")?;
+
+        // A cancelled issue releases its slug, so the issue can be created again
+        let release_slug = Delete::builder()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(slug_key(issue_id.slug())))
+            .condition_expression("IssueId = :issue_id")
+            .expression_attribute_values(":issue_id", AttributeValue::S(issue_id.to_string()))
+            .build()
+            .context("Failure building the release newsletter slug request")?;
+
+        let transact_res = self
+            .dynamo_db_client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().update(cancel_issue).build())
+            .transact_items(TransactWriteItem::builder().delete(release_slug).build())
+            .send()
+            .await;
+
+        match transact_res {
+            Ok(_) => Ok(()),
+            Err(e) if is_conditional_check_failure(&e) => {
+                Err(NewsletterStoreError::NotScheduled(format!(
+                    "{} is not scheduled. It may already have been sent or cancelled",
+                    issue_id
+                )))
+            }
+            Err(e) => Err(NewsletterStoreError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure cancelling scheduled newsletter in DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+
+    #[tracing::instrument(name = "save_newsletter_draft", skip(self, metadata))]
+    async fn save_draft(
+        &self,
+        metadata: NewsletterMetadata,
+    ) -> Result<NewsletterIssueId, NewsletterStoreError> {
+        self.store_issue(metadata, IssueState::Draft).await
+    }
+
+    #[tracing::instrument(name = "list_newsletter_drafts", skip(self))]
+    async fn list_drafts(&self) -> Result<Vec<NewsletterDraft>, NewsletterStoreError> {
+        let items = self.query_gsi1(DRAFT_ISSUE_TYPE).await?;
+
+        let drafts = items
+            .iter()
+            .map(|item| {
+                Ok(NewsletterDraft {
+                    issue_id: issue_id_attribute(item)?,
+                    issue_title: string_attribute(item, "IssueTitle")?.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        Ok(drafts)
+    }
+
+    #[tracing::instrument(name = "get_newsletter_draft", skip(self))]
+    async fn get_draft(
+        &self,
+        issue_id: &NewsletterIssueId,
+    ) -> Result<NewsletterMetadata, NewsletterStoreError> {
+        let item = self.get_draft_item(issue_id).await?;
+
+        Ok(self.read_content(&item).await?)
+    }
+
+    #[tracing::instrument(name = "list_published_issues", skip(self))]
+    async fn list_published_issues(
+        &self,
+    ) -> Result<Vec<NewsletterIssueSummary>, NewsletterStoreError> {
+        let items = self.query_gsi1(PUBLISHED_ISSUE_INDEX).await?;
+        let stats = self
+            .query_gsi1(ISSUE_STATS_INDEX)
+            .await?
+            .into_iter()
+            .map(|item| Ok((string_attribute(&item, "IssueId")?.to_string(), item)))
+            .collect::<Result<HashMap<_, _>, anyhow::Error>>()?;
 
-        let object_key = format!("{}.json", &metadata.issue_title);
+        let mut issues = Vec::with_capacity(items.len());
+
+        // GSI1 is sorted by publish time, oldest first
+        for item in items.iter().rev() {
+            let issue_id = issue_id_attribute(item)?;
+            let issue_title = string_attribute(item, "IssueTitle")?;
+            let published_at = item
+                .get("PublishedAt")
+                .and_then(|published_at| published_at.as_n().ok())
+                .and_then(|published_at| published_at.parse().ok())
+                .ok_or_else(|| anyhow::anyhow!("Published newsletter item has no PublishedAt"))?;
+
+            // Issues have no stats until the backend starts sending them
+            let issue_stats = stats.get(issue_id.as_ref());
+
+            issues.push(NewsletterIssueSummary {
+                recipient_count: issue_stats
+                    .and_then(|stats| number_attribute(stats, "RecipientCount"))
+                    .unwrap_or(0),
+                audience_size: issue_stats
+                    .and_then(|stats| number_attribute(stats, "AudienceSize")),
+                issue_id,
+                issue_title: issue_title.to_string(),
+                published_at,
+            });
+        }
+
+        Ok(issues)
+    }
+
+    #[tracing::instrument(name = "get_published_issue", skip(self))]
+    async fn get_published_issue(
+        &self,
+        slug: &str,
+    ) -> Result<Option<NewsletterMetadata>, NewsletterStoreError> {
+        let issue_id = match self.get_item(&slug_key(slug)).await? {
+            Some(slug_item) => issue_id_attribute(&slug_item)?,
+            None => return Ok(None),
+        };
+
+        match self.get_item(&issue_key(&issue_id)).await? {
+            Some(item) if item_type(&item) == Some("NewsletterIssue") => {
+                Ok(Some(self.read_content(&item).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[tracing::instrument(name = "send_newsletter_test", skip(self, recipient))]
+    async fn send_test(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<(), NewsletterStoreError> {
+        let item = self.get_draft_item(issue_id).await?;
+        let issue_title = string_attribute(&item, "IssueTitle")?;
+        let object_key = string_attribute(&item, "S3Pointer")?;
+        let now = chrono::Utc::now().timestamp();
+
+        let mut put_request = self
+            .dynamo_db_client
+            .put_item()
+            .table_name(&self.table_name)
+            .item(
+                "PK",
+                AttributeValue::S(format!("NewsletterTestSend#{}", uuid::Uuid::new_v4())),
+            )
+            .item("Type", AttributeValue::S("NewsletterTestSend".to_string()))
+            .item("IssueId", AttributeValue::S(issue_id.to_string()))
+            .item("IssueTitle", AttributeValue::S(issue_title.to_string()))
+            .item("S3Pointer", AttributeValue::S(object_key.to_string()))
+            .item("Recipient", AttributeValue::S(recipient.to_string()))
+            .item("CreatedAt", AttributeValue::N(now.to_string()))
+            .item(
+                "ttl",
+                AttributeValue::N((now + TEST_SEND_VALIDITY_SECONDS).to_string()),
+            );
+
+        put_request = match get_trace_and_span_id() {
+            None => put_request,
+            Some((trace_id, span_id)) => put_request
+                .item("TraceParent", AttributeValue::S(trace_id))
+                .item("ParentSpan", AttributeValue::S(span_id)),
+        };
+
+        put_request.send().await.context(format!(
+            "Failure inserting test send to DynamoDB. Using table {}",
+            &self.table_name
+        ))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "publish_newsletter_draft", skip(self))]
+    async fn publish_draft(
+        &self,
+        issue_id: &NewsletterIssueId,
+        send_at: Option<i64>,
+    ) -> Result<(), NewsletterStoreError> {
+        let now = chrono::Utc::now().timestamp();
+
+        let mut update_expression = match send_at {
+            None => "SET #type = :type, PublishedAt = :now, GSI1PK = :gsi1pk, GSI1SK = :gsi1sk",
+            Some(_) => "SET #type = :type, SendAt = :send_at, GSI1PK = :gsi1pk, GSI1SK = :gsi1sk",
+        }
+        .to_string();
+
+        let mut update = self
+            .dynamo_db_client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(issue_key(issue_id)))
+            .condition_expression("#type = :draft")
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(":draft", AttributeValue::S(DRAFT_ISSUE_TYPE.to_string()));
+
+        update = match send_at {
+            None => update
+                .expression_attribute_values(
+                    ":type",
+                    AttributeValue::S("NewsletterIssue".to_string()),
+                )
+                .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+                .expression_attribute_values(
+                    ":gsi1pk",
+                    AttributeValue::S(PUBLISHED_ISSUE_INDEX.to_string()),
+                )
+                .expression_attribute_values(":gsi1sk", AttributeValue::S(format!("{:010}", now))),
+            Some(send_at) => update
+                .expression_attribute_values(
+                    ":type",
+                    AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+                )
+                .expression_attribute_values(":send_at", AttributeValue::N(send_at.to_string()))
+                .expression_attribute_values(
+                    ":gsi1pk",
+                    AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+                )
+                .expression_attribute_values(
+                    ":gsi1sk",
+                    AttributeValue::S(format!("{:010}", send_at)),
+                ),
+        };
+
+        // The send continues the trace of the publish, not the one that saved the draft
+        if let Some((trace_id, span_id)) = get_trace_and_span_id() {
+            update_expression.push_str(", TraceParent = :trace_parent, ParentSpan = :parent_span");
+            update = update
+                .expression_attribute_values(":trace_parent", AttributeValue::S(trace_id))
+                .expression_attribute_values(":parent_span", AttributeValue::S(span_id));
+        }
+
+        match update.update_expression(update_expression).send().await {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Err(NewsletterStoreError::NotADraft(format!(
+                    "{} is not a draft. It may already have been published",
+                    issue_id
+                )))
+            }
+            Err(e) => Err(NewsletterStoreError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure publishing newsletter draft in DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+}
+
+impl S3NewsletterMetadataStorage {
+    async fn store_issue(
+        &self,
+        metadata: NewsletterMetadata,
+        state: IssueState,
+    ) -> Result<NewsletterIssueId, NewsletterStoreError> {
+        let issue_id =
+            NewsletterIssueId::generate(&metadata.issue_title).map_err(anyhow::Error::msg)?;
+
+        let json_bytes = json_bytes(&metadata);
+
+        let object_key = format!("{}.json", issue_id);
 
         match self.skip_s3 {
             true => {
-                let _ = self
-                    .store_issue_in_dynamo(&metadata.issue_title, &object_key)
-                    .await;
-
-                Ok("OK".to_string())
+                let content = String::from_utf8(json_bytes).ok();
+                self.store_issue_in_dynamo(
+                    &issue_id,
+                    &metadata.issue_title,
+                    &object_key,
+                    state,
+                    content,
+                )
+                .await?;
             }
             false => {
-                let put_object_result = self
-                    .s3_client
+                self.s3_client
                     .put_object()
                     .bucket(&self.bucket_name)
                     .key(&object_key)
-                    .body(body)
+                    .body(ByteStream::from(json_bytes))
                     .send()
-                    .await;
+                    .await
+                    .context(format!(
+                        "Failure storing newsletter content in S3. Using bucket {}",
+                        &self.bucket_name
+                    ))?;
 
-                match put_object_result {
-                    Ok(_ok_res) => {
-                        let _ = self
-                            .store_issue_in_dynamo(&metadata.issue_title, &object_key)
-                            .await;
+                let store_res = self
+                    .store_issue_in_dynamo(
+                        &issue_id,
+                        &metadata.issue_title,
+                        &object_key,
+                        state,
+                        None,
+                    )
+                    .await;
 
-                        Ok(object_key)
-                    }
-                    Err(e) => Err(NewsletterStoreError::UnexpectedError(e.into())),
+                // Nothing points at the content of an issue that wasn't stored
+                if store_res.is_err() {
+                    let _ = self
+                        .s3_client
+                        .delete_object()
+                        .bucket(&self.bucket_name)
+                        .key(&object_key)
+                        .send()
+                        .await;
                 }
+
+                store_res?;
             }
         }
+
+        Ok(issue_id)
+    }
+
+    async fn read_content(
+        &self,
+        item: &HashMap<String, AttributeValue>,
+    ) -> Result<NewsletterMetadata, anyhow::Error> {
+        // Without S3 the content is kept on the issue item instead
+        let content = match self.skip_s3 {
+            true => string_attribute(item, "Content")?.as_bytes().to_vec(),
+            false => self
+                .s3_client
+                .get_object()
+                .bucket(&self.bucket_name)
+                .key(string_attribute(item, "S3Pointer")?)
+                .send()
+                .await
+                .context("Failure reading newsletter content from S3")?
+                .body
+                .collect()
+                .await
+                .context("Failure reading newsletter content from S3")?
+                .to_vec(),
+        };
+
+        serde_json::from_slice(&content).context("Failure deserializing newsletter content")
+    }
+
+    async fn get_draft_item(
+        &self,
+        issue_id: &NewsletterIssueId,
+    ) -> Result<HashMap<String, AttributeValue>, NewsletterStoreError> {
+        match self.get_item(&issue_key(issue_id)).await? {
+            Some(item) if item_type(&item) == Some(DRAFT_ISSUE_TYPE) => Ok(item),
+            _ => Err(NewsletterStoreError::NotADraft(format!(
+                "{} is not a draft",
+                issue_id
+            ))),
+        }
+    }
+
+    async fn get_item(
+        &self,
+        pk: &str,
+    ) -> Result<Option<HashMap<String, AttributeValue>>, anyhow::Error> {
+        let get_res = self
+            .dynamo_db_client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(pk.to_string()))
+            .send()
+            .await
+            .context(format!(
+                "Failure reading newsletter item from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(get_res.item)
+    }
+
+    /// Every item under a GSI1 partition, following `last_evaluated_key` across pages. They are
+    /// read from GSI1All, as GSI1 only projects the keys of the items.
+    async fn query_gsi1(
+        &self,
+        gsi1pk: &str,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>, anyhow::Error> {
+        let mut items = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let query_res = self
+                .dynamo_db_client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("GSI1All")
+                .key_condition_expression("GSI1PK = :gsi1pk")
+                .expression_attribute_values(":gsi1pk", AttributeValue::S(gsi1pk.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .context(format!(
+                    "Failure querying {} items from DynamoDB. Using table {}",
+                    gsi1pk, &self.table_name
+                ))?;
+
+            items.extend(query_res.items.unwrap_or_default());
+
+            exclusive_start_key = query_res.last_evaluated_key;
+
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(items)
     }
-}
 
-impl S3NewsletterMetadataStorage {
     #[tracing::instrument(
-    skip(self),
-    fields(issue_title=%*issue_title)
+    skip(self, issue_title, content),
+    fields(issue_id=%issue_id)
     )]
     async fn store_issue_in_dynamo(
         &self,
+        issue_id: &NewsletterIssueId,
         issue_title: &str,
         s3_uri: &str,
-    ) -> Result<(), anyhow::Error> {
+        state: IssueState,
+        content: Option<String>,
+    ) -> Result<(), NewsletterStoreError> {
         let trace_details = get_trace_and_span_id();
 
-        let mut _put_res_builder = self
-            .dynamo_db_client
-            .put_item()
+        let mut _put_res_builder = Put::builder()
             .table_name(&self.table_name)
-            .item("PK", AttributeValue::S(issue_title.to_string()))
+            .item("PK", AttributeValue::S(issue_key(issue_id)))
+            .item("IssueId", AttributeValue::S(issue_id.to_string()))
             .item("IssueTitle", AttributeValue::S(issue_title.to_string()))
             .item("S3Pointer", AttributeValue::S(s3_uri.to_string()))
-            .item("Type", AttributeValue::S("NewsletterIssue".to_string()))
             .condition_expression("attribute_not_exists(PK)".to_string());
 
+        _put_res_builder = match state {
+            IssueState::Published => {
+                let now = chrono::Utc::now().timestamp();
+
+                _put_res_builder
+                    .item("Type", AttributeValue::S("NewsletterIssue".to_string()))
+                    .item("PublishedAt", AttributeValue::N(now.to_string()))
+                    .item(
+                        "GSI1PK",
+                        AttributeValue::S(PUBLISHED_ISSUE_INDEX.to_string()),
+                    )
+                    .item("GSI1SK", AttributeValue::S(format!("{:010}", now)))
+            }
+            IssueState::Scheduled(send_at) => _put_res_builder
+                .item("Type", AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()))
+                .item("SendAt", AttributeValue::N(send_at.to_string()))
+                .item(
+                    "GSI1PK",
+                    AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+                )
+                .item("GSI1SK", AttributeValue::S(format!("{:010}", send_at))),
+            IssueState::Draft => _put_res_builder
+                .item("Type", AttributeValue::S(DRAFT_ISSUE_TYPE.to_string()))
+                .item("GSI1PK", AttributeValue::S(DRAFT_ISSUE_TYPE.to_string()))
+                .item("GSI1SK", AttributeValue::S(issue_title.to_string())),
+        };
+
+        if let Some(content) = content {
+            _put_res_builder = _put_res_builder.item("Content", AttributeValue::S(content));
+        }
+
         _put_res_builder = match trace_details {
             None => _put_res_builder,
             Some((trace_id, span_id)) => _put_res_builder
@@ -108,15 +603,113 @@ impl S3NewsletterMetadataStorage {
                 .item("ParentSpan", AttributeValue::S(span_id)),
         };
 
-        _put_res_builder.send().await.context(format!(
-            "Failure inserting record to DynamoDB. Using table {}",
-            &self.table_name
-        ))?;
+        let put_issue = _put_res_builder
+            .build()
+            .context("Failure building the newsletter issue item")?;
 
-        Ok(())
+        let reserve_slug = Put::builder()
+            .table_name(&self.table_name)
+            .item("PK", AttributeValue::S(slug_key(issue_id.slug())))
+            .item("Type", AttributeValue::S(SLUG_TYPE.to_string()))
+            .item("IssueId", AttributeValue::S(issue_id.to_string()))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .context("Failure building the newsletter slug item")?;
+
+        let transact_res = self
+            .dynamo_db_client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().put(put_issue).build())
+            .transact_items(TransactWriteItem::builder().put(reserve_slug).build())
+            .send()
+            .await;
+
+        match transact_res {
+            Ok(_) => Ok(()),
+            Err(e) if is_conditional_check_failure(&e) => Err(NewsletterStoreError::IssueExists(
+                format!("An issue titled {} already exists", issue_title),
+            )),
+            Err(e) => Err(NewsletterStoreError::UnexpectedError(
+                anyhow::Error::new(e).context(format!(
+                    "Failure inserting record to DynamoDB. Using table {}",
+                    &self.table_name
+                )),
+            )),
+        }
+    }
+}
+
+fn issue_key(issue_id: &NewsletterIssueId) -> String {
+    format!("NewsletterIssue#{}", issue_id)
+}
+
+fn slug_key(slug: &str) -> String {
+    format!("NewsletterSlug#{}", slug)
+}
+
+/// A cancelled transaction lists a reason per item, `ConditionalCheckFailed` for the items whose
+/// condition didn't hold.
+fn is_conditional_check_failure(e: &SdkError<TransactWriteItemsError>) -> bool {
+    match e.as_service_error() {
+        Some(TransactWriteItemsError::TransactionCanceledException(e)) => e
+            .cancellation_reasons()
+            .iter()
+            .any(|reason| reason.code() == Some("ConditionalCheckFailed")),
+        _ => false,
     }
 }
 
+fn issue_id_attribute(
+    item: &HashMap<String, AttributeValue>,
+) -> Result<NewsletterIssueId, anyhow::Error> {
+    NewsletterIssueId::parse(string_attribute(item, "IssueId")?.to_string())
+        .map_err(anyhow::Error::msg)
+}
+
+fn item_type(item: &HashMap<String, AttributeValue>) -> Option<&str> {
+    item.get("Type")
+        .and_then(|item_type| item_type.as_s().ok())
+        .map(String::as_str)
+}
+
+/// Counters like `RecipientCount` are only stored once they are first incremented.
+fn number_attribute(item: &HashMap<String, AttributeValue>, name: &str) -> Option<usize> {
+    item.get(name)
+        .and_then(|value| value.as_n().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+fn string_attribute<'a>(
+    item: &'a HashMap<String, AttributeValue>,
+    name: &str,
+) -> Result<&'a str, anyhow::Error> {
+    item.get(name)
+        .and_then(|value| value.as_s().ok())
+        .map(String::as_str)
+        .ok_or_else(|| anyhow::anyhow!("Newsletter item has no {}", name))
+}
+
+fn parse_scheduled_newsletter(
+    item: &HashMap<String, AttributeValue>,
+) -> Result<ScheduledNewsletter, anyhow::Error> {
+    let issue_id = issue_id_attribute(item)?;
+    let issue_title = item
+        .get("IssueTitle")
+        .and_then(|title| title.as_s().ok())
+        .ok_or_else(|| anyhow::anyhow!("Scheduled newsletter item has no IssueTitle"))?;
+    let send_at = item
+        .get("SendAt")
+        .and_then(|send_at| send_at.as_n().ok())
+        .and_then(|send_at| send_at.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Scheduled newsletter item has no SendAt"))?;
+
+    Ok(ScheduledNewsletter {
+        issue_id,
+        issue_title: issue_title.clone(),
+        send_at,
+    })
+}
+
 pub fn json_bytes<T>(structure: T) -> Vec<u8>
 where
     T: Serialize,
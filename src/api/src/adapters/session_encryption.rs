@@ -0,0 +1,220 @@
+use aes_gcm::aead::{Aead, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use anyhow::Context;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use secrecy::{ExposeSecret, Secret};
+
+/// Encrypted session data is stored as `enc:v1:{key id}:{base64 of nonce and ciphertext}`.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// AES-GCM nonces are 96 bits.
+const NONCE_LENGTH: usize = 12;
+
+/// A 256 bit AES-GCM key, and the id it is tagged with in the data it encrypts.
+#[derive(Clone, Debug)]
+pub struct SessionEncryptionKey {
+    id: String,
+    key: Secret<[u8; 32]>,
+}
+
+impl SessionEncryptionKey {
+    /// Parse a base64 encoded 256 bit key.
+    pub fn parse(id: String, key: &Secret<String>) -> Result<Self, anyhow::Error> {
+        if id.is_empty() || id.contains(':') {
+            return Err(anyhow::anyhow!(
+                "Session encryption key ids can't be empty or contain ':'"
+            ));
+        }
+
+        let key = STANDARD.decode(key.expose_secret()).context(format!(
+            "The session encryption key {} is not valid base64",
+            id
+        ))?;
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("The session encryption key {} must be 32 bytes", id))?;
+
+        Ok(Self {
+            id,
+            key: Secret::new(key),
+        })
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.key.expose_secret()))
+    }
+}
+
+/// Encrypts session data before it is stored, so a copy of the sessions table doesn't give away
+/// who is logged in or let anyone forge session state. The data of a session is bound to its key,
+/// so it can't be copied onto another session.
+///
+/// New data is encrypted with the current key. Previous keys can still decrypt, so the current
+/// key can be rotated without logging everybody out: make the new key current, keep the old one as
+/// a previous key until the sessions it encrypted have expired, then drop it.
+#[derive(Clone, Debug)]
+pub struct SessionEncryption {
+    current_key: SessionEncryptionKey,
+    previous_keys: Vec<SessionEncryptionKey>,
+}
+
+impl SessionEncryption {
+    pub fn new(
+        current_key: SessionEncryptionKey,
+        previous_keys: Vec<SessionEncryptionKey>,
+    ) -> Self {
+        Self {
+            current_key,
+            previous_keys,
+        }
+    }
+
+    pub fn encrypt(&self, session_data: &str, session_key: &str) -> Result<String, anyhow::Error> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current_key
+            .cipher()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: session_data.as_bytes(),
+                    aad: session_key.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failure encrypting the session data"))?;
+
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(ciphertext);
+        Ok(format!(
+            "{}{}:{}",
+            ENCRYPTED_PREFIX,
+            self.current_key.id,
+            STANDARD.encode(encrypted)
+        ))
+    }
+
+    /// Session data stored before encryption was turned on is returned as it is, so turning it on
+    /// doesn't log everybody out.
+    pub fn decrypt(&self, session_data: &str, session_key: &str) -> Result<String, anyhow::Error> {
+        if !is_encrypted(session_data) {
+            return Ok(session_data.to_string());
+        }
+
+        let (key_id, encrypted) = session_data[ENCRYPTED_PREFIX.len()..]
+            .split_once(':')
+            .context("The encrypted session data has no key id")?;
+        let key = std::iter::once(&self.current_key)
+            .chain(&self.previous_keys)
+            .find(|key| key.id == key_id)
+            .context(format!(
+                "The session data is encrypted with the unknown key {}",
+                key_id
+            ))?;
+
+        let encrypted = STANDARD
+            .decode(encrypted)
+            .context("The encrypted session data is not valid base64")?;
+        if encrypted.len() < NONCE_LENGTH {
+            return Err(anyhow::anyhow!("The encrypted session data is too short"));
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+
+        let session_data = key
+            .cipher()
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: session_key.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failure decrypting the session data"))?;
+
+        String::from_utf8(session_data).context("The decrypted session data is not valid UTF-8")
+    }
+}
+
+pub fn is_encrypted(session_data: &str) -> bool {
+    session_data.starts_with(ENCRYPTED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionEncryption, SessionEncryptionKey};
+    use claims::{assert_err, assert_ok_eq};
+    use secrecy::Secret;
+
+    fn key(id: &str, key: &str) -> SessionEncryptionKey {
+        SessionEncryptionKey::parse(id.to_string(), &Secret::new(key.to_string())).unwrap()
+    }
+
+    fn old_key() -> SessionEncryptionKey {
+        key("2023-01", "6VfjAXpNxNVBiJmlYaVdWNtgoJVxQvl/eDk1G3QoUcs=")
+    }
+
+    fn new_key() -> SessionEncryptionKey {
+        key("2024-01", "NMfV6IQj7tygPhnlSAova6yE7tXkUFgD0rah9KoKcCw=")
+    }
+
+    #[test]
+    fn session_data_round_trips() {
+        let encryption = SessionEncryption::new(new_key(), vec![]);
+        let encrypted = encryption
+            .encrypt(r#"{"user_id":"\"ursula\""}"#, "key")
+            .unwrap();
+
+        assert!(encrypted.starts_with("enc:v1:2024-01:"));
+        assert!(!encrypted.contains("ursula"));
+        assert_ok_eq!(
+            encryption.decrypt(&encrypted, "key"),
+            r#"{"user_id":"\"ursula\""}"#.to_string()
+        );
+    }
+
+    #[test]
+    fn session_data_of_another_session_is_rejected() {
+        let encryption = SessionEncryption::new(new_key(), vec![]);
+        let encrypted = encryption.encrypt("{}", "key").unwrap();
+
+        assert_err!(encryption.decrypt(&encrypted, "another-key"));
+    }
+
+    #[test]
+    fn previous_keys_still_decrypt_after_a_rotation() {
+        let before_rotation = SessionEncryption::new(old_key(), vec![]);
+        let encrypted = before_rotation.encrypt("{}", "key").unwrap();
+
+        let after_rotation = SessionEncryption::new(new_key(), vec![old_key()]);
+
+        assert_ok_eq!(after_rotation.decrypt(&encrypted, "key"), "{}".to_string());
+        assert!(after_rotation
+            .encrypt("{}", "key")
+            .unwrap()
+            .starts_with("enc:v1:2024-01:"));
+    }
+
+    #[test]
+    fn session_data_of_a_dropped_key_is_rejected() {
+        let encrypted = SessionEncryption::new(old_key(), vec![])
+            .encrypt("{}", "key")
+            .unwrap();
+
+        assert_err!(SessionEncryption::new(new_key(), vec![]).decrypt(&encrypted, "key"));
+    }
+
+    #[test]
+    fn unencrypted_session_data_is_read_as_it_is() {
+        let encryption = SessionEncryption::new(new_key(), vec![]);
+
+        assert_ok_eq!(encryption.decrypt("{}", "key"), "{}".to_string());
+    }
+
+    #[test]
+    fn key_ids_cannot_contain_the_separator() {
+        assert_err!(SessionEncryptionKey::parse(
+            "2024:01".to_string(),
+            &Secret::new("NMfV6IQj7tygPhnlSAova6yE7tXkUFgD0rah9KoKcCw=".to_string())
+        ));
+    }
+}
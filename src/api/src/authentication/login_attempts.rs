@@ -0,0 +1,278 @@
+use actix_web::HttpRequest;
+use async_trait::async_trait;
+use std::net::IpAddr;
+
+/// How long failed login attempts are counted for, from the first failure. A locked out username
+/// or IP address can log in again once the window is over.
+pub const FAILED_LOGIN_WINDOW_SECONDS: i64 = 15 * 60;
+
+/// Failed attempts allowed for a single username within the window.
+const MAX_FAILED_ATTEMPTS_PER_USERNAME: u32 = 5;
+
+/// Failed attempts allowed from a single IP address within the window. Higher than the username
+/// limit, as many users can share an address behind a NAT.
+const MAX_FAILED_ATTEMPTS_PER_IP_ADDRESS: u32 = 50;
+
+/// What failed login attempts are counted against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginAttemptSource {
+    Username(String),
+    IpAddress(String),
+}
+
+impl LoginAttemptSource {
+    fn max_failed_attempts(&self) -> u32 {
+        match self {
+            LoginAttemptSource::Username(_) => MAX_FAILED_ATTEMPTS_PER_USERNAME,
+            LoginAttemptSource::IpAddress(_) => MAX_FAILED_ATTEMPTS_PER_IP_ADDRESS,
+        }
+    }
+}
+
+impl std::fmt::Display for LoginAttemptSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginAttemptSource::Username(username) => write!(f, "username:{}", username),
+            LoginAttemptSource::IpAddress(ip_address) => write!(f, "ip:{}", ip_address),
+        }
+    }
+}
+
+/// The proxies in front of the api, like a load balancer, whose `X-Forwarded-For` header is
+/// trusted. Anyone can set the header on a request sent to the api directly.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpAddr>);
+
+impl TrustedProxies {
+    pub fn new(proxies: Vec<IpAddr>) -> Self {
+        Self(proxies)
+    }
+
+    /// The address of the client, rather than of the proxy in front of the api. The last
+    /// `X-Forwarded-For` entry is the one added by the proxy, entries before it are set by the
+    /// client and can't be trusted. Requests that don't come from a trusted proxy are counted
+    /// against their peer address.
+    fn client_ip_address(&self, request: &HttpRequest) -> Option<String> {
+        let peer_ip_address = request.peer_addr().map(|address| address.ip());
+
+        let forwarded_for = match peer_ip_address {
+            Some(peer_ip_address) if self.0.contains(&peer_ip_address) => request
+                .headers()
+                .get("X-Forwarded-For")
+                .and_then(|forwarded_for| forwarded_for.to_str().ok())
+                .and_then(|forwarded_for| forwarded_for.rsplit(',').next())
+                .map(|ip_address| ip_address.trim().to_string())
+                .filter(|ip_address| !ip_address.is_empty()),
+            _ => None,
+        };
+
+        forwarded_for.or_else(|| peer_ip_address.map(|ip_address| ip_address.to_string()))
+    }
+}
+
+/// The sources a login request is counted against, the username first.
+pub fn login_attempt_sources(
+    username: &str,
+    request: &HttpRequest,
+    trusted_proxies: &TrustedProxies,
+) -> Vec<LoginAttemptSource> {
+    let mut sources = vec![LoginAttemptSource::Username(username.to_string())];
+    if let Some(ip_address) = trusted_proxies.client_ip_address(request) {
+        sources.push(LoginAttemptSource::IpAddress(ip_address));
+    }
+
+    sources
+}
+
+#[async_trait]
+pub trait LoginAttemptStore {
+    /// Failed attempts within the current window, 0 if there is none.
+    async fn failed_attempts(&self, source: &LoginAttemptSource) -> Result<u32, anyhow::Error>;
+
+    /// Count a failed attempt, starting a new window if there is none.
+    async fn record_failed_attempt(&self, source: &LoginAttemptSource)
+        -> Result<(), anyhow::Error>;
+
+    async fn clear_failed_attempts(&self, source: &LoginAttemptSource)
+        -> Result<(), anyhow::Error>;
+}
+
+/// Whether any of the sources has used up its failed attempts, in which case the credentials
+/// must not be checked at all.
+pub async fn is_locked_out(
+    store: &(dyn LoginAttemptStore + Send + Sync),
+    sources: &[LoginAttemptSource],
+) -> Result<bool, anyhow::Error> {
+    for source in sources {
+        if store.failed_attempts(source).await? >= source.max_failed_attempts() {
+            tracing::warn!("Login locked out for {}", source);
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+pub async fn record_failed_login(
+    store: &(dyn LoginAttemptStore + Send + Sync),
+    sources: &[LoginAttemptSource],
+) -> Result<(), anyhow::Error> {
+    for source in sources {
+        store.record_failed_attempt(source).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_locked_out, record_failed_login, LoginAttemptSource, LoginAttemptStore, TrustedProxies,
+    };
+    use actix_web::test::TestRequest;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FixedAttempts(Mutex<HashMap<String, u32>>);
+
+    impl FixedAttempts {
+        fn with(attempts: impl IntoIterator<Item = (&'static str, u32)>) -> Self {
+            Self(Mutex::new(
+                attempts
+                    .into_iter()
+                    .map(|(source, attempts)| (source.to_string(), attempts))
+                    .collect(),
+            ))
+        }
+    }
+
+    #[async_trait]
+    impl LoginAttemptStore for FixedAttempts {
+        async fn failed_attempts(&self, source: &LoginAttemptSource) -> Result<u32, anyhow::Error> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .get(&source.to_string())
+                .copied()
+                .unwrap_or(0))
+        }
+
+        async fn record_failed_attempt(
+            &self,
+            source: &LoginAttemptSource,
+        ) -> Result<(), anyhow::Error> {
+            let mut attempts = self.0.lock().unwrap();
+            *attempts.entry(source.to_string()).or_default() += 1;
+            Ok(())
+        }
+
+        async fn clear_failed_attempts(
+            &self,
+            source: &LoginAttemptSource,
+        ) -> Result<(), anyhow::Error> {
+            self.0.lock().unwrap().remove(&source.to_string());
+            Ok(())
+        }
+    }
+
+    fn sources() -> [LoginAttemptSource; 2] {
+        [
+            LoginAttemptSource::Username("ursula".to_string()),
+            LoginAttemptSource::IpAddress("127.0.0.1".to_string()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn a_few_failed_attempts_are_allowed() {
+        let store = FixedAttempts::with([("username:ursula", 4), ("ip:127.0.0.1", 49)]);
+
+        assert!(!is_locked_out(&store, &sources()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn a_username_with_too_many_failed_attempts_is_locked_out() {
+        let store = FixedAttempts::with([("username:ursula", 5)]);
+
+        assert!(is_locked_out(&store, &sources()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn an_ip_address_with_too_many_failed_attempts_is_locked_out() {
+        let store = FixedAttempts::with([("ip:127.0.0.1", 50)]);
+
+        assert!(is_locked_out(&store, &sources()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn recorded_failed_logins_reach_the_lockout() {
+        let store = FixedAttempts::default();
+
+        for _ in 0..4 {
+            record_failed_login(&store, &sources()).await.unwrap();
+        }
+        assert!(!is_locked_out(&store, &sources()).await.unwrap());
+
+        record_failed_login(&store, &sources()).await.unwrap();
+        assert!(is_locked_out(&store, &sources()).await.unwrap());
+        assert_eq!(store.failed_attempts(&sources()[1]).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn clearing_the_username_ends_its_lockout() {
+        let store = FixedAttempts::default();
+        for _ in 0..5 {
+            record_failed_login(&store, &sources()).await.unwrap();
+        }
+
+        store.clear_failed_attempts(&sources()[0]).await.unwrap();
+
+        assert!(!is_locked_out(&store, &sources()).await.unwrap());
+        assert_eq!(store.failed_attempts(&sources()[0]).await.unwrap(), 0);
+    }
+
+    fn request_from(peer_address: &str, forwarded_for: Option<&str>) -> actix_web::HttpRequest {
+        let request = TestRequest::default().peer_addr(peer_address.parse().unwrap());
+        match forwarded_for {
+            Some(forwarded_for) => request.insert_header(("X-Forwarded-For", forwarded_for)),
+            None => request,
+        }
+        .to_http_request()
+    }
+
+    fn proxy() -> TrustedProxies {
+        TrustedProxies::new(vec!["10.0.0.1".parse().unwrap()])
+    }
+
+    #[test]
+    fn the_forwarded_for_header_is_ignored_from_untrusted_peers() {
+        let request = request_from("203.0.113.7:443", Some("198.51.100.1"));
+
+        assert_eq!(
+            proxy().client_ip_address(&request),
+            Some("203.0.113.7".to_string())
+        );
+    }
+
+    #[test]
+    fn the_last_forwarded_for_entry_is_used_behind_a_trusted_proxy() {
+        let request = request_from("10.0.0.1:443", Some("192.0.2.99, 198.51.100.1"));
+
+        assert_eq!(
+            proxy().client_ip_address(&request),
+            Some("198.51.100.1".to_string())
+        );
+    }
+
+    #[test]
+    fn a_trusted_proxy_without_a_forwarded_for_header_is_the_client() {
+        let request = request_from("10.0.0.1:443", None);
+
+        assert_eq!(
+            proxy().client_ip_address(&request),
+            Some("10.0.0.1".to_string())
+        );
+    }
+}
@@ -1,9 +1,10 @@
+use crate::authentication::{Role, UserRepository};
 use crate::session_state::TypedSession;
 use crate::utils::{e500, see_other};
 use actix_web::body::MessageBody;
 use actix_web::dev::{ServiceRequest, ServiceResponse};
 use actix_web::error::InternalError;
-use actix_web::{FromRequest, HttpMessage};
+use actix_web::{web, FromRequest, HttpMessage, HttpResponse};
 use actix_web_lab::middleware::Next;
 
 #[derive(Clone, Debug)]
@@ -21,6 +22,8 @@ impl UserId {
     }
 }
 
+/// Requires a logged in user that is still enabled, and adds their `UserId` and `Role` to the
+/// request. Users disabled since they logged in are logged out.
 pub async fn reject_anonymous_users(
     mut req: ServiceRequest,
     next: Next<impl MessageBody>,
@@ -30,14 +33,65 @@ pub async fn reject_anonymous_users(
         TypedSession::from_request(http_request, payload).await
     }?;
 
-    match session.get_user_id().map_err(e500)? {
-        Some(user_id) => {
+    let user_id = match session.get_user_id().map_err(e500)? {
+        Some(user_id) => user_id,
+        None => {
+            let response = see_other("/login");
+            let e = anyhow::anyhow!("The user has not logged in");
+            return Err(InternalError::from_response(e, response).into());
+        }
+    };
+
+    let user_repo = req
+        .app_data::<web::Data<dyn UserRepository + Send + Sync>>()
+        .cloned()
+        .ok_or_else(|| e500("The user repository is not configured"))?;
+
+    match user_repo.get_user(&user_id).await.map_err(e500)? {
+        Some(user) if !user.disabled => {
             req.extensions_mut().insert(UserId(user_id));
+            req.extensions_mut().insert(user.role);
             next.call(req).await
         }
-        None => {
+        _ => {
+            session.log_out();
             let response = see_other("/login");
-            let e = anyhow::anyhow!("The user has not logged in");
+            let e = anyhow::anyhow!("The user no longer exists or has been disabled");
+            Err(InternalError::from_response(e, response).into())
+        }
+    }
+}
+
+/// Only editors and owners can write and publish newsletter issues. Must run after
+/// `reject_anonymous_users`.
+pub async fn require_editor(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    require_role(Role::Editor, req, next).await
+}
+
+/// Only owners can manage users. Must run after `reject_anonymous_users`.
+pub async fn require_owner(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    require_role(Role::Owner, req, next).await
+}
+
+async fn require_role(
+    required_role: Role,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let role = req.extensions().get::<Role>().copied();
+
+    match role {
+        Some(role) if role >= required_role => next.call(req).await,
+        _ => {
+            let response = HttpResponse::Forbidden()
+                .body(format!("You need to be an {} to do this", required_role));
+            let e = anyhow::anyhow!("The user is not an {}", required_role);
             Err(InternalError::from_response(e, response).into())
         }
     }
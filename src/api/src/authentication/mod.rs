@@ -1,8 +1,22 @@
+mod login_attempts;
 mod middleware;
 mod password;
+mod two_factor;
+mod user;
 mod user_repository;
 
-pub use middleware::reject_anonymous_users;
+pub use login_attempts::{
+    is_locked_out, login_attempt_sources, record_failed_login, LoginAttemptSource,
+    LoginAttemptStore, TrustedProxies, FAILED_LOGIN_WINDOW_SECONDS,
+};
 pub use middleware::UserId;
-pub use password::{compute_password_hash, validate_credentials, AuthError, Credentials};
-pub use user_repository::{UserAuthenticationError, UserRepository};
+pub use middleware::{reject_anonymous_users, require_editor, require_owner};
+pub use password::{
+    compute_password_hash, validate_credentials, validate_new_password, AuthError, Credentials,
+};
+pub use two_factor::{
+    generate_recovery_codes, hash_recovery_code, verify_two_factor_code, StoredTwoFactor,
+    TotpSecret, TwoFactorEncryptionKey, TwoFactorStore,
+};
+pub use user::{Role, User};
+pub use user_repository::{UserAuthenticationError, UserCreationError, UserRepository};
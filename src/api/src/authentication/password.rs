@@ -76,6 +76,24 @@ fn verify_password_hash(
         .map_err(AuthError::InvalidCredentials)
 }
 
+/// Passwords need at least this many characters, and at most `MAX_PASSWORD_LENGTH`.
+const MIN_PASSWORD_LENGTH: usize = 12;
+const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// Check a password chosen for a new user.
+pub fn validate_new_password(password: &Secret<String>) -> Result<(), String> {
+    let length = password.expose_secret().chars().count();
+
+    if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length) {
+        return Err(format!(
+            "The password needs between {} and {} characters",
+            MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn compute_password_hash(password: Secret<String>) -> Result<Secret<String>, anyhow::Error> {
     let salt = SaltString::generate(&mut rand::thread_rng());
     let password_hash = Argon2::new(
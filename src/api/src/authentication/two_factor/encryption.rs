@@ -0,0 +1,108 @@
+use aes_gcm::aead::{Aead, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use anyhow::Context;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use secrecy::{ExposeSecret, Secret};
+
+/// AES-GCM nonces are 96 bits.
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypts TOTP secrets before they are stored, so a copy of the auth table is not enough to
+/// generate codes. Each secret is bound to its user, so it can't be copied onto another user.
+#[derive(Clone, Debug)]
+pub struct TwoFactorEncryptionKey(Secret<[u8; 32]>);
+
+impl TwoFactorEncryptionKey {
+    /// Parse a base64 encoded 256 bit key.
+    pub fn parse(key: &Secret<String>) -> Result<Self, anyhow::Error> {
+        let key = STANDARD
+            .decode(key.expose_secret())
+            .context("The two-factor encryption key is not valid base64")?;
+        let key: [u8; 32] = key
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("The two-factor encryption key must be 32 bytes"))?;
+
+        Ok(Self(Secret::new(key)))
+    }
+
+    /// The nonce followed by the ciphertext, base64 encoded.
+    pub fn encrypt(&self, plaintext: &[u8], username: &str) -> Result<String, anyhow::Error> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: username.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failure encrypting the TOTP secret"))?;
+
+        let mut encrypted = nonce.to_vec();
+        encrypted.extend(ciphertext);
+        Ok(STANDARD.encode(encrypted))
+    }
+
+    pub fn decrypt(&self, encrypted: &str, username: &str) -> Result<Vec<u8>, anyhow::Error> {
+        let encrypted = STANDARD
+            .decode(encrypted)
+            .context("The encrypted TOTP secret is not valid base64")?;
+        if encrypted.len() < NONCE_LENGTH {
+            return Err(anyhow::anyhow!("The encrypted TOTP secret is too short"));
+        }
+        let (nonce, ciphertext) = encrypted.split_at(NONCE_LENGTH);
+
+        self.cipher()
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: username.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failure decrypting the TOTP secret"))
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(self.0.expose_secret()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TwoFactorEncryptionKey;
+    use claims::{assert_err, assert_ok_eq};
+    use secrecy::Secret;
+
+    fn key() -> TwoFactorEncryptionKey {
+        TwoFactorEncryptionKey::parse(&Secret::new(
+            "6VfjAXpNxNVBiJmlYaVdWNtgoJVxQvl/eDk1G3QoUcs=".to_string(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn secrets_round_trip() {
+        let key = key();
+        let encrypted = key.encrypt(b"secret", "ursula").unwrap();
+
+        assert_ok_eq!(key.decrypt(&encrypted, "ursula"), b"secret".to_vec());
+    }
+
+    #[test]
+    fn secrets_of_another_user_are_rejected() {
+        let key = key();
+        let encrypted = key.encrypt(b"secret", "ursula").unwrap();
+
+        assert_err!(key.decrypt(&encrypted, "ged"));
+    }
+
+    #[test]
+    fn keys_must_be_256_bits() {
+        assert_err!(TwoFactorEncryptionKey::parse(&Secret::new(
+            "dG9vLXNob3J0".to_string()
+        )));
+    }
+}
@@ -0,0 +1,34 @@
+mod encryption;
+mod recovery_codes;
+mod totp;
+mod two_factor_store;
+
+pub use encryption::TwoFactorEncryptionKey;
+pub use recovery_codes::{generate_recovery_codes, hash_recovery_code};
+pub use totp::TotpSecret;
+pub use two_factor_store::{StoredTwoFactor, TwoFactorStore};
+
+/// Check the second factor of a user, either a code from their authenticator app or one of their
+/// recovery codes. Accepted codes are used up.
+#[tracing::instrument(name = "Verify two-factor code", skip(store, encryption_key, code))]
+pub async fn verify_two_factor_code(
+    store: &(dyn TwoFactorStore + Send + Sync),
+    encryption_key: &TwoFactorEncryptionKey,
+    username: &str,
+    code: &str,
+) -> Result<bool, anyhow::Error> {
+    let encrypted_secret = match store.get_two_factor(username).await?.encrypted_secret {
+        Some(encrypted_secret) => encrypted_secret,
+        None => return Ok(false),
+    };
+    let secret = TotpSecret::from_bytes(encryption_key.decrypt(&encrypted_secret, username)?);
+
+    match secret.verify(code, chrono::Utc::now().timestamp()) {
+        Some(time_step) => store.use_time_step(username, time_step).await,
+        None => {
+            store
+                .use_recovery_code(username, &hash_recovery_code(code))
+                .await
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use data_encoding::BASE32_NOPAD;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// How many recovery codes a user gets when they enable two-factor authentication.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// 80 bits, long enough that a fast hash is safe to store them with.
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// Single use codes that stand in for an authenticator app code, for users who lost their
+/// device. Formatted as four groups of four characters, to be easier to copy down.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut code = [0u8; RECOVERY_CODE_LENGTH];
+            rand::thread_rng().fill_bytes(&mut code);
+
+            BASE32_NOPAD
+                .encode(&code)
+                .to_lowercase()
+                .as_bytes()
+                .chunks(4)
+                .map(|group| std::str::from_utf8(group).unwrap())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect()
+}
+
+/// The hash stored in place of a recovery code. Ignores case, dashes and whitespace, which users
+/// may or may not type in.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalised: String = code
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    format!("{:x}", Sha256::digest(normalised.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_recovery_codes, hash_recovery_code};
+    use std::collections::HashSet;
+
+    #[test]
+    fn recovery_codes_are_distinct() {
+        let codes = generate_recovery_codes();
+        let distinct: HashSet<_> = codes.iter().collect();
+
+        assert_eq!(codes.len(), 10);
+        assert_eq!(distinct.len(), 10);
+        assert!(codes.iter().all(|code| code.len() == 19));
+    }
+
+    #[test]
+    fn recovery_codes_are_hashed_regardless_of_formatting() {
+        let code = &generate_recovery_codes()[0];
+
+        assert_eq!(
+            hash_recovery_code(code),
+            hash_recovery_code(&code.replace('-', "").to_uppercase())
+        );
+        assert_ne!(
+            hash_recovery_code(code),
+            hash_recovery_code(&generate_recovery_codes()[0])
+        );
+    }
+}
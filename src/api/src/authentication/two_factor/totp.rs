@@ -0,0 +1,156 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use secrecy::{ExposeSecret, Secret};
+use sha1::Sha1;
+
+/// 160 bits, the length RFC 4226 recommends for the shared secret.
+const SECRET_LENGTH: usize = 20;
+
+const TIME_STEP_SECONDS: i64 = 30;
+
+const DIGITS: usize = 6;
+
+/// Codes from the steps either side of the current one are accepted too, to allow for the clock
+/// of the authenticator app drifting.
+const ALLOWED_DRIFT_STEPS: i64 = 1;
+
+/// Shown next to the account in authenticator apps.
+const ISSUER: &str = "zero2prod";
+
+/// The secret shared with an authenticator app, generating a new code every 30 seconds as
+/// RFC 6238 describes. Uses the SHA-1, 6 digit variant every authenticator app supports.
+pub struct TotpSecret(Secret<Vec<u8>>);
+
+impl TotpSecret {
+    pub fn generate() -> Self {
+        let mut secret = vec![0u8; SECRET_LENGTH];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self(Secret::new(secret))
+    }
+
+    pub fn from_bytes(secret: Vec<u8>) -> Self {
+        Self(Secret::new(secret))
+    }
+
+    pub fn expose_bytes(&self) -> &[u8] {
+        self.0.expose_secret()
+    }
+
+    /// The secret as users type it into an authenticator app that can't scan QR codes.
+    pub fn to_base32(&self) -> String {
+        BASE32_NOPAD.encode(self.0.expose_secret())
+    }
+
+    /// The `otpauth://` URI authenticator apps read from a QR code.
+    pub fn provisioning_uri(&self, username: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            percent_encode(ISSUER),
+            percent_encode(username),
+            self.to_base32(),
+            percent_encode(ISSUER),
+            DIGITS,
+            TIME_STEP_SECONDS
+        )
+    }
+
+    /// The code an authenticator app shows at `unix_time`.
+    pub fn generate_code(&self, unix_time: i64) -> String {
+        self.code_at(unix_time / TIME_STEP_SECONDS)
+    }
+
+    /// Check a code typed in by the user at `unix_time`. Returns the time step the code belongs
+    /// to, so it can be rejected if it is used again.
+    pub fn verify(&self, code: &str, unix_time: i64) -> Option<i64> {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        if code.len() != DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let current_step = unix_time / TIME_STEP_SECONDS;
+        (current_step - ALLOWED_DRIFT_STEPS..=current_step + ALLOWED_DRIFT_STEPS)
+            .find(|step| self.code_at(*step) == code)
+    }
+
+    fn code_at(&self, step: i64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(self.0.expose_secret())
+            .expect("HMAC can take a key of any size");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        // Dynamic truncation, RFC 4226 section 5.3
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(DIGITS as u32),
+            width = DIGITS
+        )
+    }
+}
+
+/// Percent encode everything but the unreserved characters of RFC 3986.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TotpSecret;
+
+    /// The SHA-1 secret of the RFC 6238 test vectors.
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret::from_bytes(b"12345678901234567890".to_vec())
+    }
+
+    #[test]
+    fn codes_match_the_rfc_6238_test_vectors() {
+        // The RFC lists 8 digit codes, these are their last 6 digits
+        let secret = rfc_secret();
+        assert_eq!(secret.generate_code(59), "287082");
+        assert_eq!(secret.generate_code(1111111109), "081804");
+        assert_eq!(secret.generate_code(1234567890), "005924");
+        assert_eq!(secret.generate_code(2000000000), "279037");
+    }
+
+    #[test]
+    fn codes_of_the_neighbouring_steps_are_accepted() {
+        let secret = rfc_secret();
+        assert_eq!(secret.verify("287082", 59), Some(1));
+        assert_eq!(secret.verify("287 082", 89), Some(1));
+        assert_eq!(secret.verify("287082", 30 * 3), None);
+    }
+
+    #[test]
+    fn codes_that_are_not_six_digits_are_rejected() {
+        let secret = rfc_secret();
+        assert_eq!(secret.verify("94287082", 59), None);
+        assert_eq!(secret.verify("28708a", 59), None);
+        assert_eq!(secret.verify("", 59), None);
+    }
+
+    #[test]
+    fn the_username_is_encoded_in_the_provisioning_uri() {
+        let uri = rfc_secret().provisioning_uri("ursula le guin");
+        assert_eq!(
+            uri,
+            "otpauth://totp/zero2prod:ursula%20le%20guin?\
+             secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=zero2prod&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+
+/// The two-factor authentication state of a user. Secrets are stored encrypted with the
+/// `TwoFactorEncryptionKey`.
+#[derive(Debug, Clone, Default)]
+pub struct StoredTwoFactor {
+    /// Set once the user has confirmed enrollment with a first code.
+    pub encrypted_secret: Option<String>,
+    /// Set while enrollment waits for the first code.
+    pub encrypted_pending_secret: Option<String>,
+    pub recovery_codes_remaining: usize,
+}
+
+impl StoredTwoFactor {
+    pub fn is_enabled(&self) -> bool {
+        self.encrypted_secret.is_some()
+    }
+}
+
+/// Recovery codes are stored as hashes, see `hash_recovery_code`.
+#[async_trait]
+pub trait TwoFactorStore {
+    async fn get_two_factor(&self, username: &str) -> Result<StoredTwoFactor, anyhow::Error>;
+
+    async fn store_pending_secret(
+        &self,
+        username: &str,
+        encrypted_secret: &str,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Make the pending secret the second factor of the user, recording `time_step` as used.
+    /// Returns `false` if the pending secret was replaced since it was read.
+    async fn enable_two_factor(
+        &self,
+        username: &str,
+        encrypted_secret: &str,
+        recovery_code_hashes: &[String],
+        time_step: i64,
+    ) -> Result<bool, anyhow::Error>;
+
+    /// Record the time step of an accepted code. Returns `false` if a code of that step, or a
+    /// later one, was accepted before, so codes can't be replayed.
+    async fn use_time_step(&self, username: &str, time_step: i64) -> Result<bool, anyhow::Error>;
+
+    /// Remove a recovery code. Returns `false` if the user doesn't have it.
+    async fn use_recovery_code(
+        &self,
+        username: &str,
+        recovery_code_hash: &str,
+    ) -> Result<bool, anyhow::Error>;
+}
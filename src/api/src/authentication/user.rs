@@ -0,0 +1,77 @@
+/// What an admin user can do. Every role can do everything the roles before it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Can read newsletter issues, drafts and history.
+    Viewer,
+    /// Can write, schedule and publish newsletter issues.
+    Editor,
+    /// Can manage users.
+    Owner,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Viewer, Role::Editor, Role::Owner];
+
+    pub fn parse(s: &str) -> Result<Role, String> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "owner" => Ok(Role::Owner),
+            other => Err(format!(
+                "{} is not a role. Use either viewer, editor or owner",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct User {
+    pub username: String,
+    /// Where tests of draft issues are sent. Users created before it was recorded have none
+    /// until an owner sets it.
+    pub email: Option<String>,
+    pub role: Role,
+    /// Disabled users can't log in, and their existing sessions are rejected.
+    pub disabled: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Role;
+    use claims::assert_err;
+
+    #[test]
+    fn roles_round_trip_through_their_name() {
+        for role in Role::ALL {
+            assert_eq!(Role::parse(role.as_str()), Ok(role));
+        }
+    }
+
+    #[test]
+    fn unknown_roles_are_rejected() {
+        assert_err!(Role::parse("admin"));
+        assert_err!(Role::parse("Owner"));
+    }
+
+    #[test]
+    fn owners_can_do_everything_editors_can() {
+        assert!(Role::Owner >= Role::Editor);
+        assert!(Role::Editor >= Role::Viewer);
+        assert!(Role::Viewer < Role::Editor);
+    }
+}
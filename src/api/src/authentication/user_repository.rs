@@ -1,3 +1,5 @@
+use crate::authentication::{Role, User};
+use crate::domain::subscriber_email::SubscriberEmail;
 use crate::utils::error_chain_fmt;
 use async_trait::async_trait;
 use secrecy::Secret;
@@ -16,8 +18,24 @@ impl std::fmt::Debug for UserAuthenticationError {
     }
 }
 
+#[derive(thiserror::Error)]
+pub enum UserCreationError {
+    #[error("{0}")]
+    UserExists(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for UserCreationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
 #[async_trait]
 pub trait UserRepository {
+    /// Disabled users are reported as `UserAuthenticationError::UserNotFoundError`, so they can't
+    /// log in.
     async fn get_stored_credentials(
         &self,
         username: &str,
@@ -29,5 +47,28 @@ pub trait UserRepository {
         password: Secret<String>,
     ) -> Result<(), anyhow::Error>;
 
-    async fn seed(&self) -> Result<(), anyhow::Error>;
+    /// Store a new user with the given password. Returns `UserCreationError::UserExists` rather
+    /// than overwriting a user with the same username.
+    async fn create_user(
+        &self,
+        username: &str,
+        email: &SubscriberEmail,
+        password: Secret<String>,
+        role: Role,
+    ) -> Result<(), UserCreationError>;
+
+    /// Returns `UserAuthenticationError::UserNotFoundError` if there is no user to update.
+    async fn set_email(
+        &self,
+        username: &str,
+        email: &SubscriberEmail,
+    ) -> Result<(), UserAuthenticationError>;
+
+    async fn get_user(&self, username: &str) -> Result<Option<User>, anyhow::Error>;
+
+    /// Every user, including disabled users, ordered by username.
+    async fn list_users(&self) -> Result<Vec<User>, anyhow::Error>;
+
+    /// Returns `UserAuthenticationError::UserNotFoundError` if there is no user to disable.
+    async fn disable_user(&self, username: &str) -> Result<(), UserAuthenticationError>;
 }
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use std::io::Write;
+use zero2prod::adapters::dynamodb_user_repository::DynamoDbUserRepository;
+use zero2prod::authentication::{validate_new_password, Role, UserCreationError, UserRepository};
+use zero2prod::configuration::get_configuration;
+use zero2prod::domain::subscriber_email::SubscriberEmail;
+use zero2prod::startup::configure_dynamodb_client;
+
+/// Create the first admin user, as an owner, from the `bootstrap_admin` configuration. The username
+/// and email address are prompted for on stdin when they aren't configured. The password is only
+/// read from the configuration, typically `APP_BOOTSTRAP_ADMIN__PASSWORD`, so it is never echoed
+/// to the terminal. An existing user is never overwritten, use the change password form to change
+/// its password instead.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let configuration = get_configuration()
+        .await
+        .expect("Failed to read configuration");
+    let settings = configuration.bootstrap_admin;
+
+    let username = match settings.username {
+        Some(username) => username,
+        None => prompt("Admin username: ")?,
+    };
+    let email = match settings.email {
+        Some(email) => email,
+        None => prompt("Admin email address: ")?,
+    };
+    let password = settings.password.context(
+        "Set the admin password in the bootstrap_admin configuration, \
+        for example with APP_BOOTSTRAP_ADMIN__PASSWORD",
+    )?;
+
+    if username.trim().is_empty() {
+        anyhow::bail!("The admin username can't be empty");
+    }
+    let email = SubscriberEmail::parse(email.trim().to_string()).map_err(anyhow::Error::msg)?;
+    validate_new_password(&password).map_err(anyhow::Error::msg)?;
+
+    let dynamodb_client = configure_dynamodb_client(&configuration.database).await;
+    let user_repo = DynamoDbUserRepository::new(
+        dynamodb_client,
+        configuration.database.auth_database_name.clone(),
+    );
+
+    match user_repo
+        .create_user(&username, &email, password, Role::Owner)
+        .await
+    {
+        Ok(_) => println!("Created admin user {} as an owner", username),
+        Err(UserCreationError::UserExists(e)) => {
+            anyhow::bail!("{}, it has not been changed", e)
+        }
+        Err(e) => return Err(anyhow::anyhow!(e)),
+    }
+
+    Ok(())
+}
+
+fn prompt(message: &str) -> Result<String, anyhow::Error> {
+    eprint!("{}", message);
+    std::io::stderr().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .context("Failed to read from stdin")?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
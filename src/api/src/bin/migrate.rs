@@ -0,0 +1,30 @@
+use telemetry::{get_subscriber, init_subscriber, init_tracer};
+use zero2prod::configuration::get_configuration;
+use zero2prod::migrations::apply_migrations;
+use zero2prod::startup::configure_dynamodb_client;
+
+/// Apply the schema migrations to the configured tables, without starting the server.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let configuration = get_configuration()
+        .await
+        .expect("Failed to read configuration");
+
+    let tracer = init_tracer(&configuration.telemetry);
+    let subscriber = get_subscriber(
+        configuration.telemetry.dataset_name.clone(),
+        "info".into(),
+        std::io::stdout,
+        &configuration.telemetry,
+        &tracer,
+    );
+
+    init_subscriber(subscriber);
+
+    let dynamodb_client = configure_dynamodb_client(&configuration.database).await;
+    let version = apply_migrations(&dynamodb_client, &configuration.database).await?;
+
+    println!("Schema is at version {}", version);
+
+    Ok(())
+}
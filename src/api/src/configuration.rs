@@ -7,6 +7,7 @@ use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
 use config::FileFormat;
 use secrecy::Secret;
 use serde::Deserialize;
+use std::net::IpAddr;
 use telemetry::TelemetrySettings;
 
 #[derive(Deserialize, Clone)]
@@ -14,6 +15,11 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub telemetry: TelemetrySettings,
     pub application: ApplicationSettings,
+    #[serde(default)]
+    pub session: SessionSettings,
+    /// Only read by the `bootstrap_admin` binary.
+    #[serde(default)]
+    pub bootstrap_admin: BootstrapAdminSettings,
 }
 
 #[derive(Deserialize, Clone)]
@@ -22,6 +28,57 @@ pub struct ApplicationSettings {
     pub host_name: String,
     pub base_url: String,
     pub hmac_secret: Secret<String>,
+    /// Base64 encoded 256 bit key the TOTP secrets of two-factor authentication are encrypted
+    /// with.
+    pub two_factor_encryption_key: Secret<String>,
+    /// Addresses of the proxies in front of the api. The client address of a request is only
+    /// read from `X-Forwarded-For` when it comes from one of them.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct SessionSettings {
+    #[serde(default)]
+    pub backend: SessionBackend,
+    /// Keys the session data is encrypted with. The first one encrypts, the others only decrypt
+    /// sessions encrypted before a rotation. Session data is stored unencrypted without any. Only
+    /// used by the DynamoDB backend, cookie sessions are encrypted with the HMAC secret.
+    #[serde(default)]
+    pub encryption_keys: Vec<SessionEncryptionKeySettings>,
+}
+
+/// Where sessions are stored.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SessionBackend {
+    /// In the auth table.
+    #[default]
+    #[serde(rename = "dynamodb")]
+    DynamoDb,
+    /// In the memory of the process, for tests and local development. Sessions are lost on
+    /// restart.
+    #[serde(rename = "memory")]
+    Memory,
+    /// In the session cookie.
+    #[serde(rename = "cookie")]
+    Cookie,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SessionEncryptionKeySettings {
+    /// Stored with the data the key encrypts, to find the key to decrypt it with.
+    pub id: String,
+    /// Base64 encoded 256 bit key.
+    pub key: Secret<String>,
+}
+
+/// The first admin user. The username and email address are prompted for on stdin when they aren't
+/// configured, the password has to be configured.
+#[derive(Deserialize, Clone, Default)]
+pub struct BootstrapAdminSettings {
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<Secret<String>>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -30,6 +87,10 @@ pub struct DatabaseSettings {
     pub auth_database_name: String,
     pub use_local: bool,
     pub newsletter_storage_bucket: String,
+    /// Apply the schema migrations before the server starts. Deployed environments run the
+    /// `migrate` binary instead.
+    #[serde(default)]
+    pub run_migrations: bool,
 }
 
 pub async fn get_configuration() -> Result<Settings, config::ConfigError> {
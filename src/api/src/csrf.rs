@@ -0,0 +1,99 @@
+use crate::session_state::TypedSession;
+use crate::utils::{e500, html_escape};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::{web, FromRequest, HttpMessage, HttpResponse};
+use actix_web_lab::middleware::Next;
+use subtle::ConstantTimeEq;
+
+/// The form field forms carry the CSRF token of the session in.
+const CSRF_TOKEN_FIELD: &str = "csrf_token";
+
+/// Header requests sent by scripts can carry the CSRF token in instead.
+const CSRF_TOKEN_HEADER: &str = "X-CSRF-Token";
+
+/// A hidden input with the CSRF token of the session, to embed in every form that posts to a
+/// route behind `csrf_protection`.
+pub fn csrf_token_input(session: &TypedSession) -> Result<String, actix_web::Error> {
+    let csrf_token = session.get_or_issue_csrf_token().map_err(e500)?;
+
+    Ok(format!(
+        r#"<input type="hidden" name="{}" value="{}">"#,
+        CSRF_TOKEN_FIELD,
+        html_escape(&csrf_token)
+    ))
+}
+
+/// Rejects requests that change anything unless they carry the CSRF token of the session. Another
+/// site can make the browser send the session cookie along with a form, but can't read the token
+/// to put in it.
+pub async fn csrf_protection(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if req.method().is_safe() {
+        return next.call(req).await;
+    }
+
+    let session = {
+        let (http_request, payload) = req.parts_mut();
+        TypedSession::from_request(http_request, payload).await
+    }?;
+    let expected_csrf_token = session.get_csrf_token().map_err(e500)?;
+
+    let header_csrf_token = req
+        .headers()
+        .get(CSRF_TOKEN_HEADER)
+        .and_then(|csrf_token| csrf_token.to_str().ok())
+        .map(str::to_string);
+    let csrf_token = match header_csrf_token {
+        Some(csrf_token) => Some(csrf_token),
+        None => form_csrf_token(&mut req).await?,
+    };
+
+    match (expected_csrf_token, csrf_token) {
+        (Some(expected_csrf_token), Some(csrf_token))
+            if bool::from(expected_csrf_token.as_bytes().ct_eq(csrf_token.as_bytes())) =>
+        {
+            next.call(req).await
+        }
+        (_, csrf_token) => {
+            let response = HttpResponse::Forbidden().body(
+                "The form has expired or was not sent from this site. Reload the page and try again.",
+            );
+            let e = match csrf_token {
+                Some(_) => anyhow::anyhow!("The CSRF token does not match the session"),
+                None => anyhow::anyhow!("The request has no CSRF token"),
+            };
+            Err(InternalError::from_response(e, response).into())
+        }
+    }
+}
+
+/// Read the token from a form body, putting the body back for the handler to read.
+async fn form_csrf_token(req: &mut ServiceRequest) -> Result<Option<String>, actix_web::Error> {
+    if req.content_type() != "application/x-www-form-urlencoded" {
+        return Ok(None);
+    }
+
+    let body = req.extract::<web::Bytes>().await?;
+    let csrf_token = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body)
+        .ok()
+        .and_then(|fields| {
+            fields
+                .into_iter()
+                .find(|(name, _)| name == CSRF_TOKEN_FIELD)
+                .map(|(_, csrf_token)| csrf_token)
+        });
+
+    req.set_payload(bytes_to_payload(body));
+
+    Ok(csrf_token)
+}
+
+fn bytes_to_payload(body: web::Bytes) -> Payload {
+    let (_, mut payload) = actix_http::h1::Payload::create(true);
+    payload.unread_data(body);
+    Payload::from(payload)
+}
@@ -1,9 +1,15 @@
 pub mod new_subscriber;
+mod newsletter_issue_id;
 mod newsletter_metadata;
 mod newsletter_store;
+pub mod newsletter_template;
 pub mod subscriber_email;
 pub mod subscriber_name;
 pub mod subscriber_repository;
+pub mod unsubscribe_token;
 
-pub use crate::domain::newsletter_metadata::NewsletterMetadata;
+pub use crate::domain::newsletter_issue_id::NewsletterIssueId;
+pub use crate::domain::newsletter_metadata::{
+    issue_slug, NewsletterDraft, NewsletterIssueSummary, NewsletterMetadata, ScheduledNewsletter,
+};
 pub use crate::domain::newsletter_store::{NewsletterStore, NewsletterStoreError};
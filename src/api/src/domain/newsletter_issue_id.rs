@@ -0,0 +1,114 @@
+use crate::domain::newsletter_metadata::issue_slug;
+use ulid_rs::Ulid;
+
+/// Length of a ULID in its Crockford base32 form.
+const ULID_LENGTH: usize = 26;
+
+/// Identifies a newsletter issue as `{ulid}-{slug}`. The ULID keeps issues with the same title
+/// apart and sorts by creation time, the slug keeps the id readable in S3 keys and logs.
+/// The backend parses the same format from the send newsletter messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewsletterIssueId(String);
+
+impl NewsletterIssueId {
+    pub fn generate(issue_title: &str) -> Result<NewsletterIssueId, String> {
+        let slug = issue_slug(issue_title);
+
+        if slug.is_empty() {
+            return Err(format!(
+                "{} needs at least one letter or digit to identify the issue",
+                issue_title
+            ));
+        }
+
+        let timestamp = u64::try_from(chrono::Utc::now().timestamp_millis()).unwrap_or_default();
+        let ulid = Ulid::new(timestamp, rand::random::<u8>);
+
+        Ok(Self(format!("{}-{}", ulid.to_string(), slug)))
+    }
+
+    pub fn parse(s: String) -> Result<NewsletterIssueId, String> {
+        let is_valid = match (s.get(..ULID_LENGTH), s.get(ULID_LENGTH..)) {
+            (Some(ulid), Some(rest)) => {
+                let slug = rest.strip_prefix('-').unwrap_or_default();
+
+                Ulid::unmarshal(ulid).is_ok() && !slug.is_empty() && issue_slug(slug) == slug
+            }
+            _ => false,
+        };
+
+        match is_valid {
+            true => Ok(Self(s)),
+            false => Err(format!("{} is not a valid newsletter issue id", s)),
+        }
+    }
+
+    /// The slug of the title the issue was created with.
+    pub fn slug(&self) -> &str {
+        &self.0[ULID_LENGTH + 1..]
+    }
+}
+
+impl AsRef<str> for NewsletterIssueId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for NewsletterIssueId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NewsletterIssueId;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn generated_ids_end_with_the_title_slug() {
+        let id = NewsletterIssueId::generate("Issue #1: Hello/World").unwrap();
+
+        assert_eq!(id.slug(), "issue-1-hello-world");
+        assert_ok!(NewsletterIssueId::parse(id.to_string()));
+    }
+
+    #[test]
+    fn the_same_title_generates_different_ids() {
+        let first = NewsletterIssueId::generate("Issue #1").unwrap();
+        let second = NewsletterIssueId::generate("Issue #1").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn titles_without_letters_or_digits_are_rejected() {
+        assert_err!(NewsletterIssueId::generate("!?/"));
+    }
+
+    #[test]
+    fn ids_without_a_ulid_are_rejected() {
+        assert_err!(NewsletterIssueId::parse("issue-1".to_string()));
+        assert_err!(NewsletterIssueId::parse(
+            "not-a-ulid-but-26-characters-issue-1".to_string()
+        ));
+    }
+
+    #[test]
+    fn ids_without_a_slug_are_rejected() {
+        assert_err!(NewsletterIssueId::parse(
+            "01HQ3Z5K8M2N4P6R8T0V2X4Z6B".to_string()
+        ));
+        assert_err!(NewsletterIssueId::parse(
+            "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-".to_string()
+        ));
+    }
+
+    #[test]
+    fn ids_with_an_unslugged_suffix_are_rejected() {
+        assert_err!(NewsletterIssueId::parse(
+            "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-Issue/1".to_string()
+        ));
+    }
+}
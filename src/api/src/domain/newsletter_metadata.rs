@@ -1,3 +1,4 @@
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -5,14 +6,84 @@ pub struct NewsletterMetadata {
     pub issue_title: String,
     pub text_content: String,
     pub html_content: String,
+    /// Unix timestamp, in seconds, to send the issue at. Issues without one are sent at once.
+    #[serde(default)]
+    pub send_at: Option<i64>,
 }
 
 impl NewsletterMetadata {
-    pub fn new(issue_title: &str, text_content: &str, html_content: &str) -> Self {
+    pub fn new(
+        issue_title: &str,
+        text_content: &str,
+        html_content: &str,
+        send_at: Option<i64>,
+    ) -> Self {
         Self {
             issue_title: issue_title.to_string(),
             text_content: text_content.to_string(),
             html_content: html_content.to_string(),
+            send_at,
         }
     }
 }
+
+/// An issue that has been saved but not published yet.
+pub struct NewsletterDraft {
+    pub issue_id: NewsletterIssueId,
+    pub issue_title: String,
+}
+
+/// An issue waiting for its `send_at` time, that can still be cancelled.
+pub struct ScheduledNewsletter {
+    pub issue_id: NewsletterIssueId,
+    pub issue_title: String,
+    pub send_at: i64,
+}
+
+/// A published issue, as listed in the newsletter history.
+pub struct NewsletterIssueSummary {
+    pub issue_id: NewsletterIssueId,
+    pub issue_title: String,
+    pub published_at: i64,
+    /// Subscribers the issue has been delivered to so far, counted by the backend.
+    pub recipient_count: usize,
+    /// Subscribers the issue is sent to. Recorded by the backend once it has enqueued every chunk
+    /// of the audience, or sent the issue to all of it, so `None` while that is still going on.
+    pub audience_size: Option<usize>,
+}
+
+/// The URL friendly form of an issue title, used by the public newsletter archive and as the
+/// readable part of a `NewsletterIssueId`.
+pub fn issue_slug(issue_title: &str) -> String {
+    let mut slug = String::with_capacity(issue_title.len());
+
+    for c in issue_title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::issue_slug;
+
+    #[test]
+    fn titles_are_lowercased_and_separated_by_dashes() {
+        assert_eq!(issue_slug("Issue #1: Hello, World!"), "issue-1-hello-world");
+    }
+
+    #[test]
+    fn leading_and_trailing_separators_are_dropped() {
+        assert_eq!(issue_slug("  -- Rust news --  "), "rust-news");
+    }
+
+    #[test]
+    fn unicode_letters_are_kept() {
+        assert_eq!(issue_slug("Café Ünïcode"), "café-ünïcode");
+    }
+}
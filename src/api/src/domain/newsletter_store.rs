@@ -1,4 +1,8 @@
-use crate::domain::newsletter_metadata::NewsletterMetadata;
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use crate::domain::newsletter_metadata::{
+    NewsletterDraft, NewsletterIssueSummary, NewsletterMetadata, ScheduledNewsletter,
+};
+use crate::domain::subscriber_email::SubscriberEmail;
 use async_trait::async_trait;
 
 use crate::utils::error_chain_fmt;
@@ -6,6 +10,10 @@ use crate::utils::error_chain_fmt;
 pub enum NewsletterStoreError {
     #[error("{0}")]
     IssueExists(String),
+    #[error("{0}")]
+    NotScheduled(String),
+    #[error("{0}")]
+    NotADraft(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -18,8 +26,56 @@ impl std::fmt::Debug for NewsletterStoreError {
 
 #[async_trait]
 pub trait NewsletterStore {
+    /// Fails with `IssueExists` when another issue was already stored with the same title slug.
     async fn store_newsletter_metadata(
         &self,
         metadata: NewsletterMetadata,
-    ) -> Result<String, NewsletterStoreError>;
+    ) -> Result<NewsletterIssueId, NewsletterStoreError>;
+
+    async fn list_scheduled_newsletters(
+        &self,
+    ) -> Result<Vec<ScheduledNewsletter>, NewsletterStoreError>;
+
+    async fn cancel_scheduled_newsletter(
+        &self,
+        issue_id: &NewsletterIssueId,
+    ) -> Result<(), NewsletterStoreError>;
+
+    /// Store an issue without sending it. Drafts are only sent by `publish_draft`.
+    async fn save_draft(
+        &self,
+        metadata: NewsletterMetadata,
+    ) -> Result<NewsletterIssueId, NewsletterStoreError>;
+
+    async fn list_drafts(&self) -> Result<Vec<NewsletterDraft>, NewsletterStoreError>;
+
+    async fn get_draft(
+        &self,
+        issue_id: &NewsletterIssueId,
+    ) -> Result<NewsletterMetadata, NewsletterStoreError>;
+
+    /// Send a draft to a single address, through the same pipeline as a published issue.
+    async fn send_test(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<(), NewsletterStoreError>;
+
+    /// Issues that have been published, most recent first.
+    async fn list_published_issues(
+        &self,
+    ) -> Result<Vec<NewsletterIssueSummary>, NewsletterStoreError>;
+
+    /// The content of a published issue. `None` when no published issue has this slug.
+    async fn get_published_issue(
+        &self,
+        slug: &str,
+    ) -> Result<Option<NewsletterMetadata>, NewsletterStoreError>;
+
+    /// Send a draft to the confirmed audience, either now or at `send_at`.
+    async fn publish_draft(
+        &self,
+        issue_id: &NewsletterIssueId,
+        send_at: Option<i64>,
+    ) -> Result<(), NewsletterStoreError>;
 }
@@ -0,0 +1,110 @@
+use crate::utils::html_escape;
+
+/// Placeholders the backend fills in for each subscriber when an issue is sent.
+const PLACEHOLDERS: [&str; 3] = ["name", "unsubscribe_url", "issue_title"];
+
+/// Newsletter content that only uses the supported `{{ placeholder }}` syntax.
+#[derive(Debug)]
+pub struct NewsletterTemplate(String);
+
+impl NewsletterTemplate {
+    pub fn parse(s: String) -> Result<NewsletterTemplate, String> {
+        let mut remaining = s.as_str();
+
+        while let Some(start) = remaining.find("{{") {
+            let after_open = &remaining[start + 2..];
+            let end = after_open
+                .find("}}")
+                .ok_or_else(|| "A {{ placeholder is never closed with }}".to_string())?;
+
+            let placeholder = after_open[..end].trim();
+            if !PLACEHOLDERS.contains(&placeholder) {
+                return Err(format!(
+                    "{{{{ {} }}}} is not a supported placeholder. Use one of {}",
+                    placeholder,
+                    PLACEHOLDERS.join(", ")
+                ));
+            }
+
+            remaining = &after_open[end + 2..];
+        }
+
+        Ok(Self(s))
+    }
+}
+
+/// Render the HTML of a published issue for the public archive, where there is no subscriber to
+/// personalise it for and nothing to unsubscribe from.
+pub fn render_for_archive(html_content: &str, issue_title: &str) -> String {
+    let mut rendered = String::with_capacity(html_content.len());
+    let mut remaining = html_content;
+
+    while let Some(start) = remaining.find("{{") {
+        let after_open = &remaining[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            break;
+        };
+
+        rendered.push_str(&remaining[..start]);
+        match after_open[..end].trim() {
+            "name" => rendered.push_str("subscriber"),
+            "issue_title" => rendered.push_str(&html_escape(issue_title)),
+            "unsubscribe_url" => rendered.push('#'),
+            _ => rendered.push_str(&remaining[start..start + 2 + end + 2]),
+        }
+
+        remaining = &after_open[end + 2..];
+    }
+
+    rendered.push_str(remaining);
+    rendered
+}
+
+impl AsRef<str> for NewsletterTemplate {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_for_archive, NewsletterTemplate};
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn content_without_placeholders_is_valid() {
+        assert_ok!(NewsletterTemplate::parse("<p>Hello</p>".to_string()));
+    }
+
+    #[test]
+    fn supported_placeholders_are_valid() {
+        assert_ok!(NewsletterTemplate::parse(
+            "Hi {{ name }}, {{issue_title}} is out. {{  unsubscribe_url }}".to_string()
+        ));
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let error = NewsletterTemplate::parse("Hi {{ first_name }}".to_string()).unwrap_err();
+
+        assert!(error.contains("{{ first_name }}"));
+    }
+
+    #[test]
+    fn unterminated_placeholders_are_rejected() {
+        assert_err!(NewsletterTemplate::parse("Hi {{ name".to_string()));
+    }
+
+    #[test]
+    fn archived_issues_are_rendered_without_a_subscriber() {
+        let rendered = render_for_archive(
+            "<h1>{{ issue_title }}</h1><p>Hi {{name}}</p><a href=\"{{ unsubscribe_url }}\">x</a>",
+            "Tips & tricks",
+        );
+
+        assert_eq!(
+            rendered,
+            "<h1>Tips &amp; tricks</h1><p>Hi subscriber</p><a href=\"#\">x</a>"
+        );
+    }
+}
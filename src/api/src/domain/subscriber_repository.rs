@@ -10,6 +10,8 @@ pub enum DatabaseError {
     UserExists(String),
     #[error("{0}")]
     TokenNotFoundError(String),
+    #[error("{0}")]
+    TokenExpired(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -20,25 +22,50 @@ impl std::fmt::Debug for DatabaseError {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    Pending,
+    Confirmed,
+    Unsubscribed,
+}
+
 #[async_trait]
 pub trait SubscriberRepository {
-    async fn insert_subscriber(
+    /// Store a pending subscriber together with their first confirmation token, atomically.
+    /// Returns `DatabaseError::UserExists` if a subscriber with the same email is already stored,
+    /// in which case the token is not stored either.
+    async fn insert_subscriber_with_token(
         &self,
         new_subscriber: &NewSubscriber,
-    ) -> Result<String, anyhow::Error>;
+        subscription_token: &str,
+    ) -> Result<String, DatabaseError>;
 
+    /// Store a new confirmation token for an existing subscriber, who is pending again until it is
+    /// used if they had unsubscribed. Returns `false`, without storing the token, when the
+    /// subscriber was issued a token too recently for another one, or is already confirmed.
     async fn store_token(
         &self,
         subscriber_id: String,
         subscription_token: &str,
-    ) -> Result<(), anyhow::Error>;
+    ) -> Result<bool, anyhow::Error>;
 
+    /// Returns `DatabaseError::TokenExpired` once the token is older than its time to live, even
+    /// if DynamoDB has not deleted the item yet.
     async fn get_subscriber_id_from_token(
         &self,
         subscription_token: &str,
-    ) -> Result<Option<String>, anyhow::Error>;
+    ) -> Result<String, DatabaseError>;
+
+    async fn get_subscription_status(
+        &self,
+        subscriber_id: &str,
+    ) -> Result<Option<SubscriptionStatus>, anyhow::Error>;
 
-    async fn confirm_subscriber(&self, subscriber_id: String) -> Result<(), anyhow::Error>;
+    /// Returns `false`, leaving the subscriber as they are, if they have unsubscribed since the
+    /// token was issued.
+    async fn confirm_subscriber(&self, subscriber_id: String) -> Result<bool, anyhow::Error>;
 
-    async fn apply_migrations(&self) -> Result<(), anyhow::Error>;
+    /// Remove the subscriber from the confirmed audience. Unsubscribing an unknown subscriber is
+    /// not an error, so the response doesn't reveal who is on the list.
+    async fn unsubscribe(&self, subscriber_id: String) -> Result<(), anyhow::Error>;
 }
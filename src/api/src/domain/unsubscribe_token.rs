@@ -0,0 +1,107 @@
+use crate::domain::subscriber_email::SubscriberEmail;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+
+/// An unsubscribe token is the base64 encoded subscriber email followed by an HMAC signature of
+/// that email, so the link in every newsletter can only unsubscribe its own recipient.
+/// The backend generates the same tokens when it sends a newsletter.
+pub struct UnsubscribeToken;
+
+impl UnsubscribeToken {
+    pub fn generate(email: &SubscriberEmail, hmac_secret: &Secret<String>) -> String {
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(email.as_ref()),
+            URL_SAFE_NO_PAD.encode(sign(email.as_ref(), hmac_secret).finalize().into_bytes())
+        )
+    }
+
+    /// Verify the token signature, returning the subscriber the token was issued for.
+    pub fn parse(token: &str, hmac_secret: &Secret<String>) -> Result<SubscriberEmail, String> {
+        let (encoded_email, encoded_signature) = token
+            .split_once('.')
+            .ok_or_else(|| "The unsubscribe token is malformed".to_string())?;
+
+        let email = URL_SAFE_NO_PAD
+            .decode(encoded_email)
+            .ok()
+            .and_then(|email| String::from_utf8(email).ok())
+            .ok_or_else(|| "The unsubscribe token is malformed".to_string())?;
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(encoded_signature)
+            .map_err(|_| "The unsubscribe token is malformed".to_string())?;
+
+        sign(&email, hmac_secret)
+            .verify_slice(&signature)
+            .map_err(|_| "The unsubscribe token signature is invalid".to_string())?;
+
+        SubscriberEmail::parse(email)
+    }
+}
+
+fn sign(email: &str, hmac_secret: &Secret<String>) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_secret.expose_secret().as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(b"unsubscribe:");
+    mac.update(email.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnsubscribeToken;
+    use crate::domain::subscriber_email::SubscriberEmail;
+    use claims::{assert_err, assert_ok};
+    use secrecy::Secret;
+
+    fn secret(value: &str) -> Secret<String> {
+        Secret::new(value.to_string())
+    }
+
+    #[test]
+    fn a_generated_token_parses_back_to_its_subscriber() {
+        let email = SubscriberEmail::parse("ursula@domain.com".to_string()).unwrap();
+        let token = UnsubscribeToken::generate(&email, &secret("a-secret"));
+
+        let parsed = UnsubscribeToken::parse(&token, &secret("a-secret"));
+
+        assert_ok!(&parsed);
+        assert_eq!(parsed.unwrap().as_ref(), "ursula@domain.com");
+    }
+
+    #[test]
+    fn a_token_signed_with_another_secret_is_rejected() {
+        let email = SubscriberEmail::parse("ursula@domain.com".to_string()).unwrap();
+        let token = UnsubscribeToken::generate(&email, &secret("another-secret"));
+
+        assert_err!(UnsubscribeToken::parse(&token, &secret("a-secret")));
+    }
+
+    #[test]
+    fn a_token_for_a_different_email_is_rejected() {
+        let email = SubscriberEmail::parse("ursula@domain.com".to_string()).unwrap();
+        let other = SubscriberEmail::parse("other@domain.com".to_string()).unwrap();
+        let token = UnsubscribeToken::generate(&email, &secret("a-secret"));
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{}",
+            UnsubscribeToken::generate(&other, &secret("a-secret"))
+                .split_once('.')
+                .unwrap()
+                .0,
+            signature
+        );
+
+        assert_err!(UnsubscribeToken::parse(&forged, &secret("a-secret")));
+    }
+
+    #[test]
+    fn a_malformed_token_is_rejected() {
+        assert_err!(UnsubscribeToken::parse("not-a-token", &secret("a-secret")));
+        assert_err!(UnsubscribeToken::parse("%%%.%%%", &secret("a-secret")));
+    }
+}
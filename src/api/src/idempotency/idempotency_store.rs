@@ -0,0 +1,34 @@
+use crate::idempotency::IdempotencyKey;
+use async_trait::async_trait;
+
+/// The HTTP response returned the first time a key was used, replayed for every retry.
+pub struct SavedResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, Vec<u8>)>,
+    pub body: Vec<u8>,
+}
+
+/// Keys are scoped to the user that submitted them.
+#[async_trait]
+pub trait IdempotencyStore {
+    /// Claim a key before processing the request. Returns `false` if the key was claimed before,
+    /// either by a completed request or by one that is still being processed.
+    async fn try_claim(&self, key: &IdempotencyKey, user_id: &str) -> Result<bool, anyhow::Error>;
+
+    /// The response saved for a claimed key. `None` while the request is still being processed.
+    async fn get_saved_response(
+        &self,
+        key: &IdempotencyKey,
+        user_id: &str,
+    ) -> Result<Option<SavedResponse>, anyhow::Error>;
+
+    async fn save_response(
+        &self,
+        key: &IdempotencyKey,
+        user_id: &str,
+        response: &SavedResponse,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Give up a claimed key without saving a response, so the request can be submitted again.
+    async fn release(&self, key: &IdempotencyKey, user_id: &str) -> Result<(), anyhow::Error>;
+}
@@ -0,0 +1,50 @@
+/// Sent with a form that must not be processed twice, such as publishing a newsletter issue.
+/// Every render of the form gets a new key, so a double submit or a retry reuses it.
+#[derive(Debug)]
+pub struct IdempotencyKey(String);
+
+impl IdempotencyKey {
+    const MAX_LENGTH: usize = 50;
+
+    pub fn parse(s: String) -> Result<IdempotencyKey, String> {
+        if s.is_empty() {
+            return Err("The idempotency key cannot be empty".to_string());
+        }
+
+        if s.len() >= Self::MAX_LENGTH {
+            return Err(format!(
+                "The idempotency key must be shorter than {} characters",
+                Self::MAX_LENGTH
+            ));
+        }
+
+        Ok(Self(s))
+    }
+}
+
+impl AsRef<str> for IdempotencyKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdempotencyKey;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn a_uuid_is_a_valid_key() {
+        assert_ok!(IdempotencyKey::parse(uuid::Uuid::new_v4().to_string()));
+    }
+
+    #[test]
+    fn an_empty_key_is_rejected() {
+        assert_err!(IdempotencyKey::parse("".to_string()));
+    }
+
+    #[test]
+    fn a_key_of_50_characters_is_rejected() {
+        assert_err!(IdempotencyKey::parse("a".repeat(50)));
+    }
+}
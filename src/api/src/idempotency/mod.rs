@@ -0,0 +1,7 @@
+mod idempotency_store;
+mod key;
+mod persistence;
+
+pub use idempotency_store::{IdempotencyStore, SavedResponse};
+pub use key::IdempotencyKey;
+pub use persistence::{release_key, save_response, try_processing, NextAction};
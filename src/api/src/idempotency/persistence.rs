@@ -0,0 +1,150 @@
+use crate::idempotency::{IdempotencyKey, IdempotencyStore, SavedResponse};
+use actix_web::body::to_bytes;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use anyhow::Context;
+
+pub enum NextAction {
+    StartProcessing,
+    ReturnSavedResponse(HttpResponse),
+    /// Another request with the same key is still being processed.
+    RequestInProgress,
+}
+
+pub async fn try_processing(
+    store: &(dyn IdempotencyStore + Send + Sync),
+    key: &IdempotencyKey,
+    user_id: &str,
+) -> Result<NextAction, anyhow::Error> {
+    if store.try_claim(key, user_id).await? {
+        return Ok(NextAction::StartProcessing);
+    }
+
+    match store.get_saved_response(key, user_id).await? {
+        None => Ok(NextAction::RequestInProgress),
+        Some(saved_response) => {
+            let status_code = StatusCode::from_u16(saved_response.status_code)
+                .context("The saved response has an invalid status code")?;
+
+            let mut response = HttpResponse::build(status_code);
+            for (name, value) in saved_response.headers {
+                response.append_header((name, value));
+            }
+
+            Ok(NextAction::ReturnSavedResponse(
+                response.body(saved_response.body),
+            ))
+        }
+    }
+}
+
+/// Save the response for a claimed key, returning it to be sent to the client. The key is released
+/// when the response can't be saved, as it would otherwise stay in progress until it expires and
+/// every retry would be rejected.
+pub async fn save_response(
+    store: &(dyn IdempotencyStore + Send + Sync),
+    key: &IdempotencyKey,
+    user_id: &str,
+    http_response: HttpResponse,
+) -> Result<HttpResponse, anyhow::Error> {
+    let (response_head, body) = http_response.into_parts();
+
+    // `MessageBody::Error` is not `Send` + `Sync`, so it can't go into an `anyhow::Error` as is
+    let body = to_bytes(body).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    let saved_response = SavedResponse {
+        status_code: response_head.status().as_u16(),
+        headers: response_head
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value.as_bytes().to_owned()))
+            .collect(),
+        body: body.to_vec(),
+    };
+
+    if let Err(e) = store.save_response(key, user_id, &saved_response).await {
+        if let Err(release_error) = store.release(key, user_id).await {
+            tracing::warn!("Idempotency key can't be released: {:?}", release_error);
+        }
+        return Err(e).context("Failure saving the idempotent response");
+    }
+
+    Ok(response_head.set_body(body).map_into_boxed_body())
+}
+
+/// Release a claimed key after a failure, so a retry is processed rather than rejected.
+pub async fn release_key(
+    store: &(dyn IdempotencyStore + Send + Sync),
+    key: &IdempotencyKey,
+    user_id: &str,
+) -> Result<(), anyhow::Error> {
+    store
+        .release(key, user_id)
+        .await
+        .context("Failure releasing the idempotency key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{save_response, try_processing, NextAction};
+    use crate::idempotency::{IdempotencyKey, IdempotencyStore, SavedResponse};
+    use actix_web::HttpResponse;
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    /// Claims keys in memory, but can't save any response.
+    #[derive(Default)]
+    struct FailingToSave(Mutex<HashSet<String>>);
+
+    #[async_trait]
+    impl IdempotencyStore for FailingToSave {
+        async fn try_claim(
+            &self,
+            key: &IdempotencyKey,
+            _user_id: &str,
+        ) -> Result<bool, anyhow::Error> {
+            Ok(self.0.lock().unwrap().insert(key.as_ref().to_string()))
+        }
+
+        async fn get_saved_response(
+            &self,
+            _key: &IdempotencyKey,
+            _user_id: &str,
+        ) -> Result<Option<SavedResponse>, anyhow::Error> {
+            Ok(None)
+        }
+
+        async fn save_response(
+            &self,
+            _key: &IdempotencyKey,
+            _user_id: &str,
+            _response: &SavedResponse,
+        ) -> Result<(), anyhow::Error> {
+            Err(anyhow::anyhow!("The store is unavailable"))
+        }
+
+        async fn release(&self, key: &IdempotencyKey, _user_id: &str) -> Result<(), anyhow::Error> {
+            self.0.lock().unwrap().remove(key.as_ref());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn a_key_whose_response_cant_be_saved_is_released() {
+        let store = FailingToSave::default();
+        let key = IdempotencyKey::parse(uuid::Uuid::new_v4().to_string()).unwrap();
+        assert!(matches!(
+            try_processing(&store, &key, "ursula").await.unwrap(),
+            NextAction::StartProcessing
+        ));
+
+        let save_res = save_response(&store, &key, "ursula", HttpResponse::Ok().finish()).await;
+
+        assert!(save_res.is_err());
+        assert!(matches!(
+            try_processing(&store, &key, "ursula").await.unwrap(),
+            NextAction::StartProcessing
+        ));
+    }
+}
@@ -1,8 +1,11 @@
 pub mod adapters;
 pub mod authentication;
 pub mod configuration;
+pub mod csrf;
 pub mod domain;
+pub mod idempotency;
 pub mod middleware;
+pub mod migrations;
 pub mod routes;
 pub mod session_state;
 pub mod startup;
@@ -0,0 +1,285 @@
+use anyhow::Context;
+use aws_sdk_dynamodb::types::{AttributeValue, Select};
+use aws_sdk_dynamodb::Client;
+
+/// Subscribers stored before `SubscriptionStatus` was recorded are only confirmed through GSI1.
+/// Record the status on them, so every subscriber has one.
+pub async fn backfill_subscription_status(
+    client: &Client,
+    table_name: &str,
+) -> Result<(), anyhow::Error> {
+    let scan_results: Result<Vec<_>, _> = client
+        .scan()
+        .table_name(table_name)
+        .filter_expression("#type = :type AND attribute_not_exists(SubscriptionStatus)")
+        .expression_attribute_names("#type", "Type")
+        .expression_attribute_values(":type", AttributeValue::S("Subscriber".to_string()))
+        .into_paginator()
+        .items()
+        .send()
+        .collect()
+        .await;
+
+    let subscribers = scan_results.context(format!(
+        "Failure scanning subscribers in DynamoDB. Using table {}",
+        table_name
+    ))?;
+
+    tracing::info!(
+        "Backfilling the subscription status of {} subscribers",
+        subscribers.len()
+    );
+
+    for subscriber in subscribers {
+        let pk = subscriber
+            .get("PK")
+            .cloned()
+            .context("Subscriber has no PK")?;
+
+        let status = match subscriber
+            .get("GSI1PK")
+            .and_then(|gsi1pk| gsi1pk.as_s().ok())
+        {
+            Some(gsi1pk) if gsi1pk == "confirmed" => "confirmed",
+            _ => "pending",
+        };
+
+        let update_res = client
+            .update_item()
+            .table_name(table_name)
+            .key("PK", pk)
+            .update_expression("SET SubscriptionStatus = :status")
+            .condition_expression("attribute_not_exists(SubscriptionStatus)")
+            .expression_attribute_values(":status", AttributeValue::S(status.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => {}
+            // The subscriber changed status since the scan, which recorded it
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) => {}
+            Err(e) => {
+                return Err(e).context(format!(
+                    "Failure backfilling the subscription status in DynamoDB. Using table {}",
+                    table_name
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Users created before roles were introduced were all admins, make them owners.
+pub async fn backfill_user_roles(client: &Client, table_name: &str) -> Result<(), anyhow::Error> {
+    let scan_results: Result<Vec<_>, _> = client
+        .scan()
+        .table_name(table_name)
+        .filter_expression("attribute_exists(password_hash) AND attribute_not_exists(#role)")
+        .expression_attribute_names("#role", "Role")
+        .into_paginator()
+        .items()
+        .send()
+        .collect()
+        .await;
+
+    let users = scan_results.context(format!(
+        "Failure scanning users in DynamoDB. Using table {}",
+        table_name
+    ))?;
+
+    tracing::info!("Backfilling the role of {} users", users.len());
+
+    for user in users {
+        let pk = user.get("PK").cloned().context("User has no PK")?;
+
+        let update_res = client
+            .update_item()
+            .table_name(table_name)
+            .key("PK", pk)
+            .update_expression("SET #role = :role")
+            .condition_expression("attribute_not_exists(#role)")
+            .expression_attribute_names("#role", "Role")
+            .expression_attribute_values(":role", AttributeValue::S("owner".to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => {}
+            // The user was given a role since the scan
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) => {}
+            Err(e) => {
+                return Err(e).context(format!(
+                    "Failure backfilling the user role in DynamoDB. Using table {}",
+                    table_name
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Users are listed from GSI1, index the users created before they were.
+pub async fn backfill_user_index(client: &Client, table_name: &str) -> Result<(), anyhow::Error> {
+    let scan_results: Result<Vec<_>, _> = client
+        .scan()
+        .table_name(table_name)
+        .filter_expression("attribute_exists(password_hash) AND attribute_not_exists(GSI1PK)")
+        .into_paginator()
+        .items()
+        .send()
+        .collect()
+        .await;
+
+    let users = scan_results.context(format!(
+        "Failure scanning users in DynamoDB. Using table {}",
+        table_name
+    ))?;
+
+    tracing::info!("Indexing {} users", users.len());
+
+    for user in users {
+        let pk = user.get("PK").cloned().context("User has no PK")?;
+
+        let update_res = client
+            .update_item()
+            .table_name(table_name)
+            .key("PK", pk.clone())
+            .update_expression("SET GSI1PK = :gsi1pk, GSI1SK = :gsi1sk")
+            .condition_expression("attribute_exists(password_hash)")
+            .expression_attribute_values(":gsi1pk", AttributeValue::S("User".to_string()))
+            .expression_attribute_values(":gsi1sk", pk)
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => {}
+            // The user was removed since the scan
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) => {}
+            Err(e) => {
+                return Err(e).context(format!(
+                    "Failure indexing the user in DynamoDB. Using table {}",
+                    table_name
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Deliveries used to be counted from the delivery log every time the history was listed. Count
+/// them once for the issues published before the backend kept `RecipientCount` on the
+/// `NewsletterIssueStats` item of the issue. Those issues have been sent, so their audience is the
+/// subscribers they were delivered to. The issue items themselves are never updated, as the send
+/// newsletter pipe would send them again.
+pub async fn backfill_recipient_counts(
+    client: &Client,
+    table_name: &str,
+) -> Result<(), anyhow::Error> {
+    let scan_results: Result<Vec<_>, _> = client
+        .scan()
+        .table_name(table_name)
+        .filter_expression("#type = :type")
+        .expression_attribute_names("#type", "Type")
+        .expression_attribute_values(":type", AttributeValue::S("NewsletterIssue".to_string()))
+        .into_paginator()
+        .items()
+        .send()
+        .collect()
+        .await;
+
+    let issues = scan_results.context(format!(
+        "Failure scanning newsletter issues in DynamoDB. Using table {}",
+        table_name
+    ))?;
+
+    tracing::info!("Backfilling the recipient count of {} issues", issues.len());
+
+    for issue in issues {
+        // Issues stored under their title, before issues had an id, were sent before deliveries
+        // were logged and aren't in the history, so there is nothing to count for them
+        let issue_id = match issue
+            .get("IssueId")
+            .and_then(|issue_id| issue_id.as_s().ok())
+        {
+            Some(issue_id) => issue_id,
+            None => continue,
+        };
+
+        // Deliveries are grouped by issue on GSI1
+        let count_results: Result<Vec<_>, _> = client
+            .query()
+            .table_name(table_name)
+            .index_name("GSI1")
+            .key_condition_expression("GSI1PK = :gsi1pk")
+            .expression_attribute_values(
+                ":gsi1pk",
+                AttributeValue::S(format!("NewsletterDelivery#{}", issue_id)),
+            )
+            .select(Select::Count)
+            .into_paginator()
+            .send()
+            .collect()
+            .await;
+
+        let recipient_count: i32 = count_results
+            .context(format!(
+                "Failure counting newsletter deliveries in DynamoDB. Using table {}",
+                table_name
+            ))?
+            .iter()
+            .map(|page| page.count)
+            .sum();
+
+        let update_res = client
+            .update_item()
+            .table_name(table_name)
+            .key(
+                "PK",
+                AttributeValue::S(format!("NewsletterIssueStats#{}", issue_id)),
+            )
+            .update_expression(
+                "SET #type = :stats_type, IssueId = :issue_id, \
+                GSI1PK = :stats_type, GSI1SK = :issue_id, \
+                RecipientCount = :count, AudienceSize = if_not_exists(AudienceSize, :count)",
+            )
+            .condition_expression("attribute_not_exists(RecipientCount) OR RecipientCount < :count")
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(
+                ":stats_type",
+                AttributeValue::S("NewsletterIssueStats".to_string()),
+            )
+            .expression_attribute_values(":issue_id", AttributeValue::S(issue_id.to_string()))
+            .expression_attribute_values(":count", AttributeValue::N(recipient_count.to_string()))
+            .send()
+            .await;
+
+        match update_res {
+            Ok(_) => {}
+            // The backend counted every delivery since the scan itself
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) => {}
+            Err(e) => {
+                return Err(e).context(format!(
+                    "Failure backfilling the recipient count in DynamoDB. Using table {}",
+                    table_name
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+mod backfill;
+mod schema_version;
+mod tables;
+
+use crate::configuration::DatabaseSettings;
+use aws_sdk_dynamodb::Client;
+use backfill::{
+    backfill_recipient_counts, backfill_subscription_status, backfill_user_index,
+    backfill_user_roles,
+};
+use schema_version::{get_schema_version, record_schema_version};
+use tables::{ensure_index, ensure_table, TableDefinition, GSI1, GSI1_ALL, SESSIONS_BY_USER};
+
+/// Every change to the tables or the shape of their items, in the order it is applied. The
+/// version of a migration is its position in the list, so new migrations are only ever appended.
+const MIGRATIONS: [Migration; 9] = [
+    Migration::CreateNewsletterTable,
+    Migration::CreateAuthTable,
+    Migration::BackfillSubscriptionStatus,
+    Migration::BackfillUserRoles,
+    Migration::AddSessionsByUserIndex,
+    Migration::BackfillRecipientCounts,
+    Migration::IndexUsers,
+    Migration::AddGsi1AllIndex,
+    Migration::AddAuthGsi1Index,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Migration {
+    CreateNewsletterTable,
+    /// Sessions and idempotency keys are stored in the auth table as well.
+    CreateAuthTable,
+    BackfillSubscriptionStatus,
+    /// Users created before roles were introduced were all admins.
+    BackfillUserRoles,
+    /// Sessions are listed and revoked per user through the index.
+    AddSessionsByUserIndex,
+    /// The newsletter history reads the recipient count from the stats item of the issue.
+    BackfillRecipientCounts,
+    /// Users are listed from GSI1 instead of a scan of the auth table.
+    IndexUsers,
+    /// Subscribers, issues and drafts are read from GSI1All, as GSI1 is deployed keys only.
+    AddGsi1AllIndex,
+    /// The users indexed by `IndexUsers` are listed from it.
+    AddAuthGsi1Index,
+}
+
+impl Migration {
+    fn description(&self) -> &'static str {
+        match self {
+            Migration::CreateNewsletterTable => "Create the newsletter table and its GSI1 index",
+            Migration::CreateAuthTable => "Create the auth and session table",
+            Migration::BackfillSubscriptionStatus => {
+                "Backfill the subscription status of subscribers stored before it was recorded"
+            }
+            Migration::BackfillUserRoles => "Make the users created before roles owners",
+            Migration::AddSessionsByUserIndex => "Add the SessionsByUser index to the auth table",
+            Migration::BackfillRecipientCounts => {
+                "Count the deliveries of the issues published before they were counted"
+            }
+            Migration::IndexUsers => "Index the users on GSI1 of the auth table",
+            Migration::AddGsi1AllIndex => {
+                "Add GSI1All, projecting every attribute, to the newsletter table"
+            }
+            Migration::AddAuthGsi1Index => "Add GSI1 to the auth table",
+        }
+    }
+
+    async fn apply(
+        &self,
+        client: &Client,
+        settings: &DatabaseSettings,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Migration::CreateNewsletterTable => {
+                ensure_table(
+                    client,
+                    &TableDefinition::newsletter(&settings.database_name),
+                )
+                .await
+            }
+            Migration::CreateAuthTable => {
+                ensure_table(client, &TableDefinition::auth(&settings.auth_database_name)).await
+            }
+            Migration::BackfillSubscriptionStatus => {
+                backfill_subscription_status(client, &settings.database_name).await
+            }
+            Migration::BackfillUserRoles => {
+                backfill_user_roles(client, &settings.auth_database_name).await
+            }
+            Migration::AddSessionsByUserIndex => {
+                ensure_index(client, &settings.auth_database_name, &SESSIONS_BY_USER).await
+            }
+            Migration::BackfillRecipientCounts => {
+                backfill_recipient_counts(client, &settings.database_name).await
+            }
+            Migration::IndexUsers => {
+                backfill_user_index(client, &settings.auth_database_name).await
+            }
+            Migration::AddGsi1AllIndex => {
+                ensure_index(client, &settings.database_name, &GSI1_ALL).await
+            }
+            Migration::AddAuthGsi1Index => {
+                ensure_index(client, &settings.auth_database_name, &GSI1).await
+            }
+        }
+    }
+}
+
+/// The migrations still to be applied to a schema at `current_version`, with their version.
+fn pending_migrations(current_version: u32) -> impl Iterator<Item = (u32, Migration)> {
+    MIGRATIONS
+        .into_iter()
+        .zip(1..)
+        .map(|(migration, version)| (version, migration))
+        .filter(move |(version, _)| *version > current_version)
+}
+
+/// Bring the newsletter and auth tables up to the latest schema version, creating them if they
+/// don't exist. Applying the migrations again is a no-op, so this is safe to run on every start.
+/// Returns the schema version the tables are at.
+#[tracing::instrument(skip(client, settings))]
+pub async fn apply_migrations(
+    client: &Client,
+    settings: &DatabaseSettings,
+) -> Result<u32, anyhow::Error> {
+    let mut current_version = get_schema_version(client, &settings.database_name).await?;
+
+    for (version, migration) in pending_migrations(current_version) {
+        tracing::info!(
+            "Applying migration {}: {}",
+            version,
+            migration.description()
+        );
+
+        migration.apply(client, settings).await?;
+        record_schema_version(
+            client,
+            &settings.database_name,
+            version,
+            migration.description(),
+        )
+        .await?;
+
+        current_version = version;
+    }
+
+    tracing::info!("Schema is at version {}", current_version);
+
+    Ok(current_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pending_migrations, Migration, MIGRATIONS};
+
+    #[test]
+    fn every_migration_is_pending_for_new_tables() {
+        let pending: Vec<_> = pending_migrations(0).collect();
+
+        assert_eq!(pending.len(), MIGRATIONS.len());
+        assert_eq!(pending[0], (1, Migration::CreateNewsletterTable));
+    }
+
+    #[test]
+    fn applied_migrations_are_skipped() {
+        let pending: Vec<_> = pending_migrations(3).collect();
+
+        assert_eq!(
+            pending,
+            vec![
+                (4, Migration::BackfillUserRoles),
+                (5, Migration::AddSessionsByUserIndex),
+                (6, Migration::BackfillRecipientCounts),
+                (7, Migration::IndexUsers),
+                (8, Migration::AddGsi1AllIndex),
+                (9, Migration::AddAuthGsi1Index)
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_pending_at_the_latest_version() {
+        assert_eq!(pending_migrations(MIGRATIONS.len() as u32).count(), 0);
+    }
+}
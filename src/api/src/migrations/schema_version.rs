@@ -0,0 +1,85 @@
+use anyhow::Context;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use chrono::Utc;
+
+/// The version is recorded as a single item in the newsletter table.
+const SCHEMA_VERSION_KEY: &str = "SchemaVersion";
+
+/// The latest migration applied to the tables, 0 if the newsletter table doesn't exist yet.
+pub async fn get_schema_version(client: &Client, table_name: &str) -> Result<u32, anyhow::Error> {
+    let get_res = client
+        .get_item()
+        .table_name(table_name)
+        .key("PK", AttributeValue::S(SCHEMA_VERSION_KEY.to_string()))
+        .consistent_read(true)
+        .send()
+        .await;
+
+    let item = match get_res {
+        Ok(output) => output.item,
+        Err(e)
+            if e.as_service_error()
+                .map(|e| e.is_resource_not_found_exception())
+                .unwrap_or(false) =>
+        {
+            return Ok(0)
+        }
+        Err(e) => {
+            return Err(e).context(format!(
+                "Failure reading the schema version from DynamoDB. Using table {}",
+                table_name
+            ))
+        }
+    };
+
+    match item {
+        None => Ok(0),
+        Some(item) => item
+            .get("Version")
+            .and_then(|version| version.as_n().ok())
+            .and_then(|version| version.parse().ok())
+            .context("The schema version item has no Version"),
+    }
+}
+
+/// Record `version` as applied. A newer version recorded by a migration running at the same time
+/// is kept.
+pub async fn record_schema_version(
+    client: &Client,
+    table_name: &str,
+    version: u32,
+    description: &str,
+) -> Result<(), anyhow::Error> {
+    let put_res = client
+        .put_item()
+        .table_name(table_name)
+        .item("PK", AttributeValue::S(SCHEMA_VERSION_KEY.to_string()))
+        .item("Type", AttributeValue::S(SCHEMA_VERSION_KEY.to_string()))
+        .item("Version", AttributeValue::N(version.to_string()))
+        .item("Description", AttributeValue::S(description.to_string()))
+        .item(
+            "AppliedAt",
+            AttributeValue::N(Utc::now().timestamp().to_string()),
+        )
+        .condition_expression("attribute_not_exists(PK) OR Version < :version")
+        .expression_attribute_values(":version", AttributeValue::N(version.to_string()))
+        .send()
+        .await;
+
+    match put_res {
+        Ok(_) => Ok(()),
+        Err(e)
+            if e.as_service_error()
+                .map(|e| e.is_conditional_check_failed_exception())
+                .unwrap_or(false) =>
+        {
+            tracing::info!("Schema version {} was already recorded", version);
+            Ok(())
+        }
+        Err(e) => Err(e).context(format!(
+            "Failure recording the schema version in DynamoDB. Using table {}",
+            table_name
+        )),
+    }
+}
@@ -0,0 +1,423 @@
+use anyhow::Context;
+use aws_sdk_dynamodb::types::{
+    AttributeDefinition, BillingMode, CreateGlobalSecondaryIndexAction, GlobalSecondaryIndex,
+    GlobalSecondaryIndexUpdate, IndexStatus, KeySchemaElement, KeyType, Projection, ProjectionType,
+    ScalarAttributeType, StreamSpecification, StreamViewType, TableDescription, TableStatus,
+    TimeToLiveSpecification, TimeToLiveStatus,
+};
+use aws_sdk_dynamodb::Client;
+use std::time::Duration;
+
+/// Name of the attribute DynamoDB uses to expire items from every table.
+const TTL_ATTRIBUTE: &str = "ttl";
+
+/// How often, and how many times, to check on a table while DynamoDB creates it or an index.
+/// Backfilling an index on a large table takes a while, DynamoDB Local is immediate.
+const ACTIVE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const ACTIVE_POLL_ATTEMPTS: u32 = 180;
+
+/// A global secondary index on string attributes, projecting every attribute.
+#[derive(Debug, Clone, Copy)]
+pub struct IndexDefinition {
+    name: &'static str,
+    partition_key: &'static str,
+    sort_key: Option<&'static str>,
+}
+
+pub const GSI1: IndexDefinition = IndexDefinition {
+    name: "GSI1",
+    partition_key: "GSI1PK",
+    sort_key: Some("GSI1SK"),
+};
+
+/// The keys of GSI1, projecting every attribute. The deployed newsletter table has GSI1 projecting
+/// its keys only, and the projection of an index can't be changed in place, so queries that read
+/// the indexed items use this one.
+pub const GSI1_ALL: IndexDefinition = IndexDefinition {
+    name: "GSI1All",
+    partition_key: "GSI1PK",
+    sort_key: Some("GSI1SK"),
+};
+
+/// The sessions of a user, see `DynamoDbSessionStore`.
+pub const SESSIONS_BY_USER: IndexDefinition = IndexDefinition {
+    name: "SessionsByUser",
+    partition_key: "SessionUserId",
+    sort_key: None,
+};
+
+/// The shape of a table when it is created. Every table is keyed on a string `PK` and expires
+/// items through the `ttl` attribute. Indexes added later are migrations of their own, see
+/// `ensure_index`, so the table a migration creates never changes once it has been applied.
+#[derive(Debug, Clone)]
+pub struct TableDefinition {
+    name: String,
+    indexes: Vec<IndexDefinition>,
+    /// A stream of new images, read by the EventBridge pipes.
+    with_stream: bool,
+}
+
+impl TableDefinition {
+    pub fn newsletter(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            indexes: vec![GSI1],
+            with_stream: true,
+        }
+    }
+
+    pub fn auth(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            indexes: vec![],
+            with_stream: false,
+        }
+    }
+}
+
+/// Create the table, or bring an existing table in line with its definition. Fails if an
+/// existing table has a different key, which can't be changed in place.
+pub async fn ensure_table(client: &Client, table: &TableDefinition) -> Result<(), anyhow::Error> {
+    match describe_table(client, &table.name).await? {
+        None => create_table(client, table).await?,
+        Some(description) => {
+            verify_key_schema(&description, &table.name)?;
+
+            // DynamoDB adds a single index at a time
+            for index in &table.indexes {
+                if !has_index(&description, index) {
+                    wait_until_active(client, &table.name).await?;
+                    tracing::info!("Adding {} to table {}", index.name, &table.name);
+                    add_index(client, &table.name, index).await?;
+                }
+            }
+
+            if table.with_stream && !has_stream(&description) {
+                wait_until_active(client, &table.name).await?;
+                tracing::info!("Enabling the stream of table {}", &table.name);
+                enable_stream(client, &table.name).await?;
+            }
+        }
+    }
+
+    wait_until_active(client, &table.name).await?;
+    enable_ttl(client, &table.name).await
+}
+
+async fn describe_table(
+    client: &Client,
+    table_name: &str,
+) -> Result<Option<TableDescription>, anyhow::Error> {
+    match client.describe_table().table_name(table_name).send().await {
+        Ok(output) => Ok(output.table),
+        Err(e)
+            if e.as_service_error()
+                .map(|e| e.is_resource_not_found_exception())
+                .unwrap_or(false) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e).context(format!("Failure describing table {}", table_name)),
+    }
+}
+
+async fn create_table(client: &Client, table: &TableDefinition) -> Result<(), anyhow::Error> {
+    tracing::info!("Creating table {}", &table.name);
+
+    let mut create_table = client
+        .create_table()
+        .table_name(&table.name)
+        .billing_mode(BillingMode::PayPerRequest)
+        .attribute_definitions(string_attribute("PK")?)
+        .key_schema(key("PK", KeyType::Hash)?);
+
+    for index in &table.indexes {
+        for attribute in index_attributes(index) {
+            create_table = create_table.attribute_definitions(string_attribute(attribute)?);
+        }
+
+        create_table = create_table.global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name(index.name)
+                .set_key_schema(Some(index_key_schema(index)?))
+                .projection(all_attributes_projection())
+                .build()
+                .context(format!("Failure building {}", index.name))?,
+        );
+    }
+
+    if table.with_stream {
+        create_table = create_table.stream_specification(new_image_stream()?);
+    }
+
+    match create_table.send().await {
+        Ok(_) => Ok(()),
+        // Another migration created the table in the meantime
+        Err(e)
+            if e.as_service_error()
+                .map(|e| e.is_resource_in_use_exception())
+                .unwrap_or(false) =>
+        {
+            Ok(())
+        }
+        Err(e) => Err(e).context(format!("Failure creating table {}", &table.name)),
+    }
+}
+
+async fn add_index(
+    client: &Client,
+    table_name: &str,
+    index: &IndexDefinition,
+) -> Result<(), anyhow::Error> {
+    let create_index = CreateGlobalSecondaryIndexAction::builder()
+        .index_name(index.name)
+        .set_key_schema(Some(index_key_schema(index)?))
+        .projection(all_attributes_projection())
+        .build()
+        .context(format!("Failure building {}", index.name))?;
+
+    let mut update_table = client.update_table().table_name(table_name);
+    for attribute in index_attributes(index) {
+        update_table = update_table.attribute_definitions(string_attribute(attribute)?);
+    }
+
+    update_table
+        .global_secondary_index_updates(
+            GlobalSecondaryIndexUpdate::builder()
+                .create(create_index)
+                .build(),
+        )
+        .send()
+        .await
+        .context(format!(
+            "Failure adding {} to table {}",
+            index.name, table_name
+        ))?;
+
+    Ok(())
+}
+
+async fn enable_stream(client: &Client, table_name: &str) -> Result<(), anyhow::Error> {
+    client
+        .update_table()
+        .table_name(table_name)
+        .stream_specification(new_image_stream()?)
+        .send()
+        .await
+        .context(format!(
+            "Failure enabling the stream of table {}",
+            table_name
+        ))?;
+
+    Ok(())
+}
+
+async fn enable_ttl(client: &Client, table_name: &str) -> Result<(), anyhow::Error> {
+    let ttl_status = client
+        .describe_time_to_live()
+        .table_name(table_name)
+        .send()
+        .await
+        .context(format!(
+            "Failure describing the TTL of table {}",
+            table_name
+        ))?
+        .time_to_live_description
+        .and_then(|description| description.time_to_live_status);
+
+    if matches!(
+        ttl_status,
+        Some(TimeToLiveStatus::Enabled) | Some(TimeToLiveStatus::Enabling)
+    ) {
+        return Ok(());
+    }
+
+    tracing::info!("Enabling TTL on table {}", table_name);
+
+    client
+        .update_time_to_live()
+        .table_name(table_name)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .enabled(true)
+                .attribute_name(TTL_ATTRIBUTE)
+                .build()
+                .context("Failure building the TTL specification")?,
+        )
+        .send()
+        .await
+        .context(format!("Failure enabling TTL on table {}", table_name))?;
+
+    Ok(())
+}
+
+/// Add the index to an existing table, unless it already has an index of the same name.
+pub async fn ensure_index(
+    client: &Client,
+    table_name: &str,
+    index: &IndexDefinition,
+) -> Result<(), anyhow::Error> {
+    let description = describe_table(client, table_name)
+        .await?
+        .context(format!("Table {} does not exist", table_name))?;
+
+    if !has_index(&description, index) {
+        wait_until_active(client, table_name).await?;
+        tracing::info!("Adding {} to table {}", index.name, table_name);
+        add_index(client, table_name, index).await?;
+    }
+
+    wait_until_active(client, table_name).await
+}
+
+/// Tables and indexes can't be changed until DynamoDB has finished creating them.
+async fn wait_until_active(client: &Client, table_name: &str) -> Result<(), anyhow::Error> {
+    for _ in 0..ACTIVE_POLL_ATTEMPTS {
+        let description = describe_table(client, table_name)
+            .await?
+            .context(format!("Table {} does not exist", table_name))?;
+
+        if is_active(&description) {
+            return Ok(());
+        }
+
+        tokio::time::sleep(ACTIVE_POLL_INTERVAL).await;
+    }
+
+    Err(anyhow::anyhow!(
+        "Table {} did not become active in time",
+        table_name
+    ))
+}
+
+fn is_active(description: &TableDescription) -> bool {
+    description.table_status() == Some(&TableStatus::Active)
+        && description
+            .global_secondary_indexes()
+            .iter()
+            .all(|index| index.index_status() == Some(&IndexStatus::Active))
+}
+
+fn verify_key_schema(
+    description: &TableDescription,
+    table_name: &str,
+) -> Result<(), anyhow::Error> {
+    let key_schema: Vec<_> = description
+        .key_schema()
+        .iter()
+        .map(|key| (key.attribute_name(), key.key_type()))
+        .collect();
+
+    if key_schema != [("PK", &KeyType::Hash)] {
+        return Err(anyhow::anyhow!(
+            "Table {} is keyed on {:?}, expected a PK partition key only",
+            table_name,
+            key_schema
+        ));
+    }
+
+    Ok(())
+}
+
+/// Indexes are only compared by name. An index that needs a different projection is added under
+/// a new name, see `GSI1_ALL`.
+fn has_index(description: &TableDescription, index: &IndexDefinition) -> bool {
+    description
+        .global_secondary_indexes()
+        .iter()
+        .any(|existing| existing.index_name() == Some(index.name))
+}
+
+fn has_stream(description: &TableDescription) -> bool {
+    description
+        .stream_specification()
+        .map(|stream| stream.stream_enabled())
+        .unwrap_or(false)
+}
+
+fn string_attribute(name: &str) -> Result<AttributeDefinition, anyhow::Error> {
+    AttributeDefinition::builder()
+        .attribute_name(name)
+        .attribute_type(ScalarAttributeType::S)
+        .build()
+        .context(format!(
+            "Failure building the {} attribute definition",
+            name
+        ))
+}
+
+fn key(name: &str, key_type: KeyType) -> Result<KeySchemaElement, anyhow::Error> {
+    KeySchemaElement::builder()
+        .attribute_name(name)
+        .key_type(key_type)
+        .build()
+        .context(format!("Failure building the {} key", name))
+}
+
+fn index_attributes(index: &IndexDefinition) -> impl Iterator<Item = &'static str> {
+    std::iter::once(index.partition_key).chain(index.sort_key)
+}
+
+fn index_key_schema(index: &IndexDefinition) -> Result<Vec<KeySchemaElement>, anyhow::Error> {
+    let mut key_schema = vec![key(index.partition_key, KeyType::Hash)?];
+    if let Some(sort_key) = index.sort_key {
+        key_schema.push(key(sort_key, KeyType::Range)?);
+    }
+
+    Ok(key_schema)
+}
+
+fn all_attributes_projection() -> Projection {
+    Projection::builder()
+        .projection_type(ProjectionType::All)
+        .build()
+}
+
+fn new_image_stream() -> Result<StreamSpecification, anyhow::Error> {
+    StreamSpecification::builder()
+        .stream_enabled(true)
+        .stream_view_type(StreamViewType::NewImage)
+        .build()
+        .context("Failure building the stream specification")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_index, key, verify_key_schema, GSI1};
+    use aws_sdk_dynamodb::types::{GlobalSecondaryIndexDescription, KeyType, TableDescription};
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn tables_keyed_on_pk_are_accepted() {
+        let description = TableDescription::builder()
+            .key_schema(key("PK", KeyType::Hash).unwrap())
+            .build();
+
+        assert_ok!(verify_key_schema(&description, "newsletter"));
+    }
+
+    #[test]
+    fn tables_with_a_sort_key_are_rejected() {
+        let description = TableDescription::builder()
+            .key_schema(key("PK", KeyType::Hash).unwrap())
+            .key_schema(key("SK", KeyType::Range).unwrap())
+            .build();
+
+        assert_err!(verify_key_schema(&description, "newsletter"));
+    }
+
+    #[test]
+    fn a_missing_index_is_detected() {
+        let without_gsi1 = TableDescription::builder().build();
+        let with_gsi1 = TableDescription::builder()
+            .global_secondary_indexes(
+                GlobalSecondaryIndexDescription::builder()
+                    .index_name("GSI1")
+                    .build(),
+            )
+            .build();
+
+        assert!(!has_index(&without_gsi1, &GSI1));
+        assert!(has_index(&with_gsi1, &GSI1));
+    }
+}
@@ -1,9 +1,15 @@
+use crate::authentication::Role;
+use crate::csrf::csrf_token_input;
 use crate::session_state::TypedSession;
-use crate::utils::e500;
+use crate::utils::{e500, html_escape};
 use actix_web::http::header::LOCATION;
-use actix_web::{http::header::ContentType, HttpResponse};
+use actix_web::{http::header::ContentType, web, HttpResponse};
+use std::fmt::Write;
 
-pub async fn admin_dashboard(session: TypedSession) -> Result<HttpResponse, actix_web::Error> {
+pub async fn admin_dashboard(
+    session: TypedSession,
+    role: web::ReqData<Role>,
+) -> Result<HttpResponse, actix_web::Error> {
     let username = if let Some(user_id) = session.get_user_id().map_err(e500)? {
         user_id
     } else {
@@ -11,6 +17,31 @@ pub async fn admin_dashboard(session: TypedSession) -> Result<HttpResponse, acti
             .insert_header((LOCATION, "/login"))
             .finish());
     };
+    let username = html_escape(&username);
+    let csrf_token_input = csrf_token_input(&session)?;
+    let role = role.into_inner();
+
+    let mut actions_html = String::new();
+    if role >= Role::Editor {
+        writeln!(
+            actions_html,
+            r#"        <li><a href="/admin/newsletters">Publish a newsletter issue</a></li>"#
+        )
+        .unwrap();
+    }
+    writeln!(
+        actions_html,
+        r#"        <li><a href="/admin/newsletters/history">Newsletter history</a></li>"#
+    )
+    .unwrap();
+    if role >= Role::Owner {
+        writeln!(
+            actions_html,
+            r#"        <li><a href="/admin/users">Manage users</a></li>"#
+        )
+        .unwrap();
+    }
+
     Ok(HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(format!(
@@ -21,12 +52,15 @@ pub async fn admin_dashboard(session: TypedSession) -> Result<HttpResponse, acti
     <title>Admin dashboard</title>
 </head>
 <body>
-    <p>Welcome {username}!</p>
+    <p>Welcome {username}! You are an {role}.</p>
     <p>Available actions:</p>
     <ol>
-        <li><a href="/admin/password">Change password</a></li>
+{actions_html}        <li><a href="/admin/password">Change password</a></li>
+        <li><a href="/admin/two-factor">Two-factor authentication</a></li>
+        <li><a href="/admin/sessions">Active sessions</a></li>
         <li>
           <form name="logoutForm" action="/admin/logout" method="post">
+            {csrf_token_input}
             <input type="submit" value="Logout">
           </form>
         </li>
@@ -1,11 +1,15 @@
 mod dashboard;
 mod logout;
-mod migrate;
 mod newsletter;
 mod password;
+mod sessions;
+mod two_factor;
+mod users;
 
 pub use dashboard::admin_dashboard;
 pub use logout::log_out;
-pub use migrate::*;
 pub use newsletter::*;
 pub use password::*;
+pub use sessions::*;
+pub use two_factor::*;
+pub use users::*;
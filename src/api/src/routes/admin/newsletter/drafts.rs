@@ -0,0 +1,226 @@
+use super::post::{parse_send_at, FormData};
+use crate::authentication::{UserId, UserRepository};
+use crate::csrf::csrf_token_input;
+use crate::domain::subscriber_email::SubscriberEmail;
+use crate::domain::{NewsletterIssueId, NewsletterMetadata, NewsletterStore, NewsletterStoreError};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, html_escape, see_other};
+use actix_web::http::header::{ContentType, CONTENT_SECURITY_POLICY};
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::{FlashMessage, IncomingFlashMessages};
+use std::fmt::Write;
+
+#[derive(serde::Deserialize)]
+pub struct DraftParameters {
+    issue_id: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TestSendFormData {
+    issue_id: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PublishDraftFormData {
+    issue_id: String,
+    #[serde(default)]
+    send_at: Option<String>,
+}
+
+#[tracing::instrument(skip(form, newsletter_store))]
+pub async fn save_newsletter_draft(
+    form: web::Form<FormData>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(e) = form.validate_content() {
+        FlashMessage::error(html_escape(&e)).send();
+        return Ok(see_other("/admin/newsletters"));
+    }
+
+    let save_res = newsletter_store
+        .save_draft(NewsletterMetadata::new(
+            &form.title,
+            &form.text_content,
+            &form.html_content,
+            None,
+        ))
+        .await;
+
+    match save_res {
+        Ok(_) => {
+            FlashMessage::info("The newsletter draft has been saved!").send();
+            Ok(see_other("/admin/newsletters/drafts"))
+        }
+        Err(NewsletterStoreError::IssueExists(e)) => {
+            FlashMessage::error(html_escape(&e)).send();
+            Ok(see_other("/admin/newsletters"))
+        }
+        Err(e) => Err(e500(e)),
+    }
+}
+
+#[tracing::instrument(skip(session, flash_messages, newsletter_store))]
+pub async fn newsletter_drafts(
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let csrf_token_input = csrf_token_input(&session)?;
+    let mut msg_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+
+    let drafts = newsletter_store.list_drafts().await.map_err(e500)?;
+
+    let mut drafts_html = String::new();
+    for draft in &drafts {
+        let issue_id = html_escape(draft.issue_id.as_ref());
+        let query =
+            serde_urlencoded::to_string([("issue_id", draft.issue_id.as_ref())]).map_err(e500)?;
+
+        writeln!(
+            drafts_html,
+            r#"        <li>
+            {title} <a href="/admin/newsletters/drafts/preview?{query}">Preview</a>
+            <form action="/admin/newsletters/drafts/test" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="issue_id" value="{issue_id}">
+                <button type="submit">Send a test to me</button>
+            </form>
+            <form action="/admin/newsletters/drafts/publish" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="issue_id" value="{issue_id}">
+                <label>Send at (UTC, leave empty to send now):
+                    <input type="datetime-local" name="send_at">
+                </label>
+                <button type="submit">Publish</button>
+            </form>
+        </li>"#,
+            title = html_escape(&draft.issue_title),
+            query = html_escape(&query),
+        )
+        .unwrap();
+    }
+
+    if drafts.is_empty() {
+        drafts_html.push_str("        <li>There are no drafts</li>");
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Newsletter Drafts</title>
+</head>
+<body>
+    {msg_html}
+    <ul>
+{drafts_html}
+    </ul>
+    <p><a href="/admin/newsletters">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
+
+/// Render the stored HTML content as is, so the admin sees the issue the way it will be sent.
+#[tracing::instrument(skip(parameters, newsletter_store))]
+pub async fn preview_newsletter_draft(
+    parameters: web::Query<DraftParameters>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issue_id =
+        NewsletterIssueId::parse(parameters.0.issue_id).map_err(actix_web::error::ErrorNotFound)?;
+
+    match newsletter_store.get_draft(&issue_id).await {
+        // The draft is served from the admin origin, sandboxing it stops any script it contains
+        // running with the session of whoever previews it
+        Ok(draft) => Ok(HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .insert_header((CONTENT_SECURITY_POLICY, "sandbox"))
+            .body(draft.html_content)),
+        Err(NewsletterStoreError::NotADraft(e)) => Err(actix_web::error::ErrorNotFound(e)),
+        Err(e) => Err(e500(e)),
+    }
+}
+
+/// Send a test of the draft to the email address of the logged in user.
+#[tracing::instrument(skip(form, newsletter_store, user_repo, user_id))]
+pub async fn send_newsletter_test(
+    form: web::Form<TestSendFormData>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+    user_repo: web::Data<dyn UserRepository + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issue_id = match NewsletterIssueId::parse(form.issue_id.clone()) {
+        Ok(issue_id) => issue_id,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/newsletters/drafts"));
+        }
+    };
+
+    let user = user_repo
+        .get_user(&user_id.into_inner().as_string())
+        .await
+        .map_err(e500)?;
+    let recipient = match user.and_then(|user| user.email) {
+        Some(email) => SubscriberEmail::parse(email).map_err(e500)?,
+        None => {
+            FlashMessage::error(
+                "You have no email address to send a test to. An owner can set it on the users page.",
+            )
+            .send();
+            return Ok(see_other("/admin/newsletters/drafts"));
+        }
+    };
+
+    match newsletter_store.send_test(&issue_id, &recipient).await {
+        Ok(_) => FlashMessage::info(format!(
+            "A test of the draft has been sent to {}",
+            html_escape(recipient.as_ref())
+        ))
+        .send(),
+        Err(NewsletterStoreError::NotADraft(e)) => FlashMessage::error(html_escape(&e)).send(),
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/newsletters/drafts"))
+}
+
+#[tracing::instrument(skip(form, newsletter_store))]
+pub async fn publish_newsletter_draft(
+    form: web::Form<PublishDraftFormData>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issue_id = match NewsletterIssueId::parse(form.issue_id.clone()) {
+        Ok(issue_id) => issue_id,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/newsletters/drafts"));
+        }
+    };
+
+    let send_at = match parse_send_at(form.send_at.as_deref()) {
+        Ok(send_at) => send_at,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/newsletters/drafts"));
+        }
+    };
+
+    match newsletter_store.publish_draft(&issue_id, send_at).await {
+        Ok(_) if send_at.is_some() => {
+            FlashMessage::info("The newsletter issue has been scheduled!").send()
+        }
+        Ok(_) => FlashMessage::info("The newsletter issue has been published!").send(),
+        Err(NewsletterStoreError::NotADraft(e)) => FlashMessage::error(html_escape(&e)).send(),
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/newsletters/drafts"))
+}
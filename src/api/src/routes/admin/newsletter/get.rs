@@ -1,11 +1,16 @@
+use crate::csrf::csrf_token_input;
+use crate::session_state::TypedSession;
 use actix_web::http::header::ContentType;
 use actix_web::HttpResponse;
 use actix_web_flash_messages::IncomingFlashMessages;
 use std::fmt::Write;
 
 pub async fn publish_newsletter_form(
+    session: TypedSession,
     flash_messages: IncomingFlashMessages,
 ) -> Result<HttpResponse, actix_web::Error> {
+    let csrf_token_input = csrf_token_input(&session)?;
+    let idempotency_key = uuid::Uuid::new_v4();
     let mut msg_html = String::new();
     for m in flash_messages.iter() {
         writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
@@ -23,6 +28,7 @@ pub async fn publish_newsletter_form(
 <body>
     {msg_html}
     <form action="/admin/newsletters" method="post">
+        {csrf_token_input}
         <label>Title:<br>
             <input
                 type="text"
@@ -49,8 +55,18 @@ pub async fn publish_newsletter_form(
             ></textarea>
         </label>
         <br>
+        <label>Send at (UTC, leave empty to send now):<br>
+            <input type="datetime-local" name="send_at">
+        </label>
+        <br>
+        <input hidden type="text" name="idempotency_key" value="{idempotency_key}">
+        <p>Use {{{{ name }}}}, {{{{ issue_title }}}} and {{{{ unsubscribe_url }}}} to personalise the issue for each subscriber.</p>
         <button type="submit">Publish</button>
+        <button type="submit" formaction="/admin/newsletters/drafts">Save draft</button>
     </form>
+    <p><a href="/admin/newsletters/history">History</a></p>
+    <p><a href="/admin/newsletters/drafts">Drafts</a></p>
+    <p><a href="/admin/newsletters/scheduled">Scheduled issues</a></p>
     <p><a href="/admin/dashboard">&lt;- Back</a></p>
 </body>
 </html>"#,
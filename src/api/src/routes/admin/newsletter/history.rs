@@ -0,0 +1,71 @@
+use crate::domain::NewsletterStore;
+use crate::utils::{e500, html_escape};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use chrono::{TimeZone, Utc};
+use std::fmt::Write;
+
+#[tracing::instrument(skip(newsletter_store))]
+pub async fn newsletter_history(
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issues = newsletter_store
+        .list_published_issues()
+        .await
+        .map_err(e500)?;
+
+    let mut issues_html = String::new();
+    for issue in &issues {
+        let published_at = Utc
+            .timestamp_opt(issue.published_at, 0)
+            .single()
+            .map(|published_at| published_at.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| issue.published_at.to_string());
+
+        // Deliveries are counted as the backend sends the issue. Chunks that failed into the DLQ
+        // leave the issue short of its audience, so it stays at sending until they are redriven.
+        let status = match issue.audience_size {
+            Some(audience_size) if issue.recipient_count >= audience_size => "Delivered",
+            None if issue.recipient_count == 0 => "Queued",
+            _ => "Sending",
+        };
+
+        writeln!(
+            issues_html,
+            r#"        <tr>
+            <td><a href="/newsletters/{slug}">{title}</a></td>
+            <td>{published_at}</td>
+            <td>{recipient_count}</td>
+            <td>{status}</td>
+        </tr>"#,
+            slug = html_escape(issue.issue_id.slug()),
+            title = html_escape(&issue.issue_title),
+            recipient_count = issue.recipient_count,
+        )
+        .unwrap();
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Newsletter History</title>
+</head>
+<body>
+    <table>
+        <tr>
+            <th>Issue</th>
+            <th>Published</th>
+            <th>Recipients</th>
+            <th>Status</th>
+        </tr>
+{issues_html}
+    </table>
+    <p><a href="/admin/newsletters">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
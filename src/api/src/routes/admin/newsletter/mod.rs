@@ -1,5 +1,14 @@
+mod drafts;
 mod get;
+mod history;
 mod post;
+mod scheduled;
 
+pub use drafts::{
+    newsletter_drafts, preview_newsletter_draft, publish_newsletter_draft, save_newsletter_draft,
+    send_newsletter_test,
+};
 pub use get::publish_newsletter_form;
+pub use history::newsletter_history;
 pub use post::publish_newsletter;
+pub use scheduled::{cancel_scheduled_newsletter, scheduled_newsletters};
@@ -1,13 +1,23 @@
-use crate::domain::{NewsletterMetadata, NewsletterStore};
+use crate::authentication::UserId;
+use crate::domain::newsletter_template::NewsletterTemplate;
+use crate::domain::{issue_slug, NewsletterMetadata, NewsletterStore, NewsletterStoreError};
+use crate::idempotency::{
+    release_key, save_response, try_processing, IdempotencyKey, IdempotencyStore, NextAction,
+};
 use crate::utils::error_chain_fmt;
-use crate::utils::see_other;
+use crate::utils::{html_escape, see_other};
 use actix_web::http::StatusCode;
 use actix_web::{web, HttpResponse, ResponseError};
 use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
+use chrono::{NaiveDateTime, Utc};
 
 #[derive(thiserror::Error)]
 pub enum PublishNewsletterError {
+    #[error("{0}")]
+    InvalidIdempotencyKey(String),
+    #[error("The newsletter issue is still being published")]
+    RequestInProgress,
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -21,31 +31,131 @@ impl std::fmt::Debug for PublishNewsletterError {
 impl ResponseError for PublishNewsletterError {
     fn status_code(&self) -> StatusCode {
         match self {
+            Self::InvalidIdempotencyKey(_) => StatusCode::BAD_REQUEST,
+            Self::RequestInProgress => StatusCode::CONFLICT,
             Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 #[derive(serde::Deserialize)]
 pub struct FormData {
-    title: String,
-    text_content: String,
-    html_content: String,
+    pub(super) title: String,
+    pub(super) text_content: String,
+    pub(super) html_content: String,
+    /// Sent by a `datetime-local` input, in UTC. Empty when the issue should be sent at once.
+    #[serde(default)]
+    send_at: Option<String>,
+    /// Only publishing requires a key, drafts are saved from the same form.
+    #[serde(default)]
+    idempotency_key: Option<String>,
 }
 
-#[tracing::instrument(skip(form, newsletter_store))]
+impl FormData {
+    pub(super) fn validate_content(&self) -> Result<(), String> {
+        // The title slug is part of the issue id and its address in the archive
+        if issue_slug(&self.title).is_empty() {
+            return Err("The newsletter issue needs a title with a letter or digit".to_string());
+        }
+
+        NewsletterTemplate::parse(self.text_content.clone())
+            .and_then(|_| NewsletterTemplate::parse(self.html_content.clone()))
+            .map(|_| ())
+            .map_err(|e| format!("The newsletter issue is invalid. {}", e))
+    }
+}
+
+#[tracing::instrument(
+    skip(form, newsletter_store, idempotency_store, user_id),
+    fields(user_id=%*user_id)
+)]
 pub async fn publish_newsletter(
     form: web::Form<FormData>,
     newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+    idempotency_store: web::Data<dyn IdempotencyStore + Send + Sync>,
+    user_id: web::ReqData<UserId>,
 ) -> Result<HttpResponse, PublishNewsletterError> {
-    newsletter_store
+    let user_id = user_id.into_inner().as_string();
+    let idempotency_key = IdempotencyKey::parse(form.idempotency_key.clone().unwrap_or_default())
+        .map_err(PublishNewsletterError::InvalidIdempotencyKey)?;
+
+    if let Err(e) = form.validate_content() {
+        FlashMessage::error(html_escape(&e)).send();
+        return Ok(see_other("/admin/newsletters"));
+    }
+
+    let send_at = match parse_send_at(form.send_at.as_deref()) {
+        Ok(send_at) => send_at,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/newsletters"));
+        }
+    };
+
+    match try_processing(idempotency_store.as_ref(), &idempotency_key, &user_id).await? {
+        NextAction::StartProcessing => {}
+        NextAction::ReturnSavedResponse(saved_response) => {
+            success_message(send_at).send();
+            return Ok(saved_response);
+        }
+        NextAction::RequestInProgress => return Err(PublishNewsletterError::RequestInProgress),
+    }
+
+    let store_res = newsletter_store
         .store_newsletter_metadata(NewsletterMetadata::new(
             &form.title,
             &form.text_content,
             &form.html_content,
+            send_at,
         ))
-        .await
-        .context("Failure storing newsletter data")?;
+        .await;
+
+    if let Err(e) = store_res {
+        // Nothing was published, so the form can be submitted again
+        release_key(idempotency_store.as_ref(), &idempotency_key, &user_id).await?;
+
+        return match e {
+            NewsletterStoreError::IssueExists(e) => {
+                FlashMessage::error(html_escape(&e)).send();
+                Ok(see_other("/admin/newsletters"))
+            }
+            e => Err(e).context("Failure storing newsletter data")?,
+        };
+    }
+
+    success_message(send_at).send();
+    let response = see_other("/admin/newsletters");
+    let response = save_response(
+        idempotency_store.as_ref(),
+        &idempotency_key,
+        &user_id,
+        response,
+    )
+    .await?;
+
+    Ok(response)
+}
+
+fn success_message(send_at: Option<i64>) -> FlashMessage {
+    match send_at {
+        None => FlashMessage::info("The newsletter issue has been published!"),
+        Some(_) => FlashMessage::info("The newsletter issue has been scheduled!"),
+    }
+}
+
+pub(super) fn parse_send_at(send_at: Option<&str>) -> Result<Option<i64>, String> {
+    let send_at = match send_at.map(str::trim) {
+        None | Some("") => return Ok(None),
+        Some(send_at) => send_at,
+    };
+
+    let send_at = NaiveDateTime::parse_from_str(send_at, "%Y-%m-%dT%H:%M")
+        .map_err(|_| format!("{} is not a valid send time", send_at))?
+        .and_utc()
+        .timestamp();
+
+    if send_at <= Utc::now().timestamp() {
+        return Err("The send time must be in the future".to_string());
+    }
 
-    FlashMessage::info("The newsletter issue has been published!").send();
-    Ok(see_other("/admin/newsletters"))
+    Ok(Some(send_at))
 }
@@ -0,0 +1,104 @@
+use crate::csrf::csrf_token_input;
+use crate::domain::{NewsletterIssueId, NewsletterStore, NewsletterStoreError};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, html_escape, see_other};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::{FlashMessage, IncomingFlashMessages};
+use chrono::{TimeZone, Utc};
+use std::fmt::Write;
+
+#[derive(serde::Deserialize)]
+pub struct CancelFormData {
+    issue_id: String,
+}
+
+#[tracing::instrument(skip(session, flash_messages, newsletter_store))]
+pub async fn scheduled_newsletters(
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let csrf_token_input = csrf_token_input(&session)?;
+    let mut msg_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+
+    let scheduled = newsletter_store
+        .list_scheduled_newsletters()
+        .await
+        .map_err(e500)?;
+
+    let mut scheduled_html = String::new();
+    for newsletter in &scheduled {
+        let send_at = Utc
+            .timestamp_opt(newsletter.send_at, 0)
+            .single()
+            .map(|send_at| send_at.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_else(|| newsletter.send_at.to_string());
+
+        writeln!(
+            scheduled_html,
+            r#"        <li>
+            {title} at {send_at}
+            <form action="/admin/newsletters/scheduled/cancel" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="issue_id" value="{issue_id}">
+                <button type="submit">Cancel</button>
+            </form>
+        </li>"#,
+            title = html_escape(&newsletter.issue_title),
+            issue_id = html_escape(newsletter.issue_id.as_ref()),
+        )
+        .unwrap();
+    }
+
+    if scheduled.is_empty() {
+        scheduled_html.push_str("        <li>No issues are scheduled</li>");
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Scheduled Newsletter Issues</title>
+</head>
+<body>
+    {msg_html}
+    <ul>
+{scheduled_html}
+    </ul>
+    <p><a href="/admin/newsletters">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
+
+#[tracing::instrument(skip(form, newsletter_store))]
+pub async fn cancel_scheduled_newsletter(
+    form: web::Form<CancelFormData>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issue_id = match NewsletterIssueId::parse(form.0.issue_id) {
+        Ok(issue_id) => issue_id,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/newsletters/scheduled"));
+        }
+    };
+
+    match newsletter_store
+        .cancel_scheduled_newsletter(&issue_id)
+        .await
+    {
+        Ok(_) => FlashMessage::info("The scheduled newsletter issue has been cancelled").send(),
+        Err(NewsletterStoreError::NotScheduled(e)) => FlashMessage::error(html_escape(&e)).send(),
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/newsletters/scheduled"))
+}
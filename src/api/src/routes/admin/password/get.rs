@@ -1,3 +1,4 @@
+use crate::csrf::csrf_token_input;
 use crate::session_state::TypedSession;
 use crate::utils::{e500, see_other};
 use actix_web::http::header::ContentType;
@@ -12,6 +13,7 @@ pub async fn change_password_form(
     if session.get_user_id().map_err(e500)?.is_none() {
         return Ok(see_other("/login"));
     };
+    let csrf_token_input = csrf_token_input(&session)?;
     let mut msg_html = String::new();
     for m in flash_messages.iter() {
         writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
@@ -28,6 +30,7 @@ pub async fn change_password_form(
 <body>
     {msg_html}
     <form action="/admin/password" method="post">
+        {csrf_token_input}
         <label>Current password
             <input
                 type="password"
@@ -1,4 +1,7 @@
 use crate::authentication::{validate_credentials, AuthError, Credentials, UserId, UserRepository};
+use crate::session_state::{
+    revoke_other_sessions, SessionRegistry, SessionsNotTracked, TypedSession,
+};
 use crate::utils::{e500, see_other};
 use actix_web::{web, HttpResponse};
 use actix_web_flash_messages::FlashMessage;
@@ -14,6 +17,8 @@ pub struct FormData {
 pub async fn change_password(
     form: web::Form<FormData>,
     user_repo: web::Data<dyn UserRepository + Send + Sync>,
+    session_registry: web::Data<dyn SessionRegistry + Send + Sync>,
+    session: TypedSession,
     user_id: web::ReqData<UserId>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let user_id = user_id.into_inner();
@@ -42,6 +47,22 @@ pub async fn change_password(
         .change_password(&user_id.as_string(), form.0.new_password)
         .await
         .map_err(e500)?;
-    FlashMessage::error("Your password has been changed.").send();
+    // Anyone else who knew the old password is logged out
+    let current_session_id = session.get_session_id().map_err(e500)?;
+    match revoke_other_sessions(
+        session_registry.get_ref(),
+        &user_id.as_string(),
+        current_session_id.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => FlashMessage::error("Your password has been changed.").send(),
+        Err(e) if e.is::<SessionsNotTracked>() => FlashMessage::error(format!(
+            "Your password has been changed, but you are still logged in elsewhere. {}",
+            e
+        ))
+        .send(),
+        Err(e) => return Err(e500(e)),
+    }
     Ok(see_other("/admin/password"))
 }
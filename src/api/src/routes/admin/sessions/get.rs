@@ -0,0 +1,93 @@
+use crate::authentication::UserId;
+use crate::csrf::csrf_token_input;
+use crate::session_state::{SessionRegistry, SessionsNotTracked, TypedSession};
+use crate::utils::{e500, html_escape};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use chrono::DateTime;
+use std::fmt::Write;
+
+#[tracing::instrument(skip(flash_messages, session, session_registry, user_id))]
+pub async fn active_sessions(
+    flash_messages: IncomingFlashMessages,
+    session: TypedSession,
+    session_registry: web::Data<dyn SessionRegistry + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner().as_string();
+    let current_session_id = session.get_session_id().map_err(e500)?;
+    let csrf_token_input = csrf_token_input(&session)?;
+
+    let mut msg_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+
+    let sessions = match session_registry.list_sessions(&user_id).await {
+        Ok(sessions) => sessions,
+        Err(e) if e.is::<SessionsNotTracked>() => {
+            writeln!(msg_html, "<p><i>{}</i></p>", e).unwrap();
+            vec![]
+        }
+        Err(e) => return Err(e500(e)),
+    };
+
+    let mut sessions_html = String::new();
+    for active_session in &sessions {
+        let created_at = DateTime::from_timestamp(active_session.created_at, 0)
+            .map(|created_at| created_at.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+        let user_agent = active_session
+            .user_agent()
+            .unwrap_or_else(|| "Unknown browser".to_string());
+
+        let is_current_session =
+            current_session_id.is_some() && active_session.session_id() == current_session_id;
+        let action = if is_current_session {
+            "this session".to_string()
+        } else {
+            format!(
+                r#"<form action="/admin/sessions/revoke" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="revocation_id" value="{revocation_id}">
+                <button type="submit">Log out</button>
+            </form>"#,
+                revocation_id = html_escape(&active_session.revocation_id),
+            )
+        };
+
+        writeln!(
+            sessions_html,
+            r#"        <li>
+            Logged in at {created_at} from {user_agent}
+            {action}
+        </li>"#,
+            user_agent = html_escape(&user_agent),
+        )
+        .unwrap();
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Sessions</title>
+</head>
+<body>
+    {msg_html}
+    <ul>
+{sessions_html}
+    </ul>
+    <form action="/admin/sessions/revoke-others" method="post">
+        {csrf_token_input}
+        <button type="submit">Log out everywhere else</button>
+    </form>
+    <p><a href="/admin/dashboard">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
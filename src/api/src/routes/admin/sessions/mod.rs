@@ -0,0 +1,5 @@
+mod get;
+mod post;
+
+pub use get::active_sessions;
+pub use post::{log_out_other_sessions, revoke_session};
@@ -0,0 +1,56 @@
+use crate::authentication::UserId;
+use crate::session_state::{
+    revoke_other_sessions, SessionRegistry, SessionsNotTracked, TypedSession,
+};
+use crate::utils::{e500, see_other};
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+
+#[derive(serde::Deserialize)]
+pub struct RevokeSessionFormData {
+    revocation_id: String,
+}
+
+#[tracing::instrument(skip(form, session_registry, user_id))]
+pub async fn revoke_session(
+    form: web::Form<RevokeSessionFormData>,
+    session_registry: web::Data<dyn SessionRegistry + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner().as_string();
+
+    match session_registry
+        .revoke_session(&user_id, &form.revocation_id)
+        .await
+    {
+        Ok(true) => FlashMessage::info("The session has been logged out.").send(),
+        Ok(false) => FlashMessage::error("The session has already been logged out.").send(),
+        Err(e) if e.is::<SessionsNotTracked>() => FlashMessage::error(e.to_string()).send(),
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/sessions"))
+}
+
+#[tracing::instrument(skip(session, session_registry, user_id))]
+pub async fn log_out_other_sessions(
+    session: TypedSession,
+    session_registry: web::Data<dyn SessionRegistry + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = user_id.into_inner().as_string();
+    let current_session_id = session.get_session_id().map_err(e500)?;
+
+    match revoke_other_sessions(
+        session_registry.get_ref(),
+        &user_id,
+        current_session_id.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => FlashMessage::info("You have been logged out everywhere else.").send(),
+        Err(e) if e.is::<SessionsNotTracked>() => FlashMessage::error(e.to_string()).send(),
+        Err(e) => return Err(e500(e)),
+    }
+    Ok(see_other("/admin/sessions"))
+}
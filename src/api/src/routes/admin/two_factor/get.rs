@@ -0,0 +1,105 @@
+use crate::authentication::{TotpSecret, TwoFactorEncryptionKey, TwoFactorStore, UserId};
+use crate::csrf::csrf_token_input;
+use crate::session_state::TypedSession;
+use crate::utils::{e500, html_escape};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use std::fmt::Write;
+
+/// Shows the QR code to scan with an authenticator app, or the number of recovery codes left once
+/// two-factor authentication is enabled. The secret of the QR code is kept until enrollment is
+/// confirmed, so reloading the page doesn't invalidate a scanned code.
+pub async fn two_factor_form(
+    user_id: web::ReqData<UserId>,
+    two_factor_store: web::Data<dyn TwoFactorStore + Send + Sync>,
+    encryption_key: web::Data<TwoFactorEncryptionKey>,
+    flash_messages: IncomingFlashMessages,
+    session: TypedSession,
+) -> Result<HttpResponse, actix_web::Error> {
+    let username = user_id.into_inner().as_string();
+    let csrf_token_input = csrf_token_input(&session)?;
+    let two_factor = two_factor_store
+        .get_two_factor(&username)
+        .await
+        .map_err(e500)?;
+
+    let mut msg_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+
+    let content_html = if two_factor.is_enabled() {
+        format!(
+            r#"<p>Two-factor authentication is enabled. You have {} recovery codes left.</p>"#,
+            two_factor.recovery_codes_remaining
+        )
+    } else {
+        let secret = match two_factor.encrypted_pending_secret {
+            Some(encrypted_secret) => TotpSecret::from_bytes(
+                encryption_key
+                    .decrypt(&encrypted_secret, &username)
+                    .map_err(e500)?,
+            ),
+            None => {
+                let secret = TotpSecret::generate();
+                let encrypted_secret = encryption_key
+                    .encrypt(secret.expose_bytes(), &username)
+                    .map_err(e500)?;
+                two_factor_store
+                    .store_pending_secret(&username, &encrypted_secret)
+                    .await
+                    .map_err(e500)?;
+                secret
+            }
+        };
+
+        let provisioning_uri = secret.provisioning_uri(&username);
+        let qr_code = QrCode::new(provisioning_uri.as_bytes())
+            .map_err(e500)?
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+
+        format!(
+            r#"<p>Scan the QR code with your authenticator app, or enter the key <code>{secret}</code>.</p>
+    {qr_code}
+    <p><a href="{provisioning_uri}">Open in an authenticator app</a></p>
+    <form action="/admin/two-factor" method="post">
+        {csrf_token_input}
+        <label>Authentication code
+            <input
+                type="text"
+                inputmode="numeric"
+                autocomplete="one-time-code"
+                placeholder="Enter the code from your app"
+                name="code"
+            >
+        </label>
+        <br>
+        <button type="submit">Enable two-factor authentication</button>
+    </form>"#,
+            secret = secret.to_base32(),
+            provisioning_uri = html_escape(&provisioning_uri),
+        )
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Two-factor authentication</title>
+</head>
+<body>
+    {msg_html}
+    {content_html}
+    <p><a href="/admin/dashboard">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
@@ -0,0 +1,5 @@
+mod get;
+mod post;
+
+pub use get::two_factor_form;
+pub use post::enable_two_factor;
@@ -0,0 +1,105 @@
+use crate::authentication::{
+    generate_recovery_codes, hash_recovery_code, TotpSecret, TwoFactorEncryptionKey,
+    TwoFactorStore, UserId,
+};
+use crate::utils::{e500, see_other};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use secrecy::{ExposeSecret, Secret};
+use std::fmt::Write;
+
+#[derive(serde::Deserialize)]
+pub struct FormData {
+    code: Secret<String>,
+}
+
+/// Enable two-factor authentication once the user has proven their app generates the right
+/// codes. The recovery codes are shown once, only their hashes are stored.
+#[tracing::instrument(skip(form, two_factor_store, encryption_key), fields(username = %user_id.as_string()))]
+pub async fn enable_two_factor(
+    form: web::Form<FormData>,
+    user_id: web::ReqData<UserId>,
+    two_factor_store: web::Data<dyn TwoFactorStore + Send + Sync>,
+    encryption_key: web::Data<TwoFactorEncryptionKey>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let username = user_id.into_inner().as_string();
+    let two_factor = two_factor_store
+        .get_two_factor(&username)
+        .await
+        .map_err(e500)?;
+
+    if two_factor.is_enabled() {
+        FlashMessage::error("Two-factor authentication is already enabled.").send();
+        return Ok(see_other("/admin/two-factor"));
+    }
+
+    let encrypted_secret = match two_factor.encrypted_pending_secret {
+        Some(encrypted_secret) => encrypted_secret,
+        None => return Ok(see_other("/admin/two-factor")),
+    };
+    let secret = TotpSecret::from_bytes(
+        encryption_key
+            .decrypt(&encrypted_secret, &username)
+            .map_err(e500)?,
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let time_step = match secret.verify(form.code.expose_secret(), now) {
+        Some(time_step) => time_step,
+        None => {
+            FlashMessage::error("The authentication code is incorrect.").send();
+            return Ok(see_other("/admin/two-factor"));
+        }
+    };
+
+    let recovery_codes = generate_recovery_codes();
+    let recovery_code_hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| hash_recovery_code(code))
+        .collect();
+
+    if !two_factor_store
+        .enable_two_factor(
+            &username,
+            &encrypted_secret,
+            &recovery_code_hashes,
+            time_step,
+        )
+        .await
+        .map_err(e500)?
+    {
+        FlashMessage::error("The set up was restarted in another window, scan the new QR code.")
+            .send();
+        return Ok(see_other("/admin/two-factor"));
+    }
+
+    let mut recovery_codes_html = String::new();
+    for code in &recovery_codes {
+        writeln!(
+            recovery_codes_html,
+            "        <li><code>{}</code></li>",
+            code
+        )
+        .unwrap();
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Two-factor authentication</title>
+</head>
+<body>
+    <p>Two-factor authentication is enabled.</p>
+    <p>Keep these recovery codes somewhere safe. Each of them logs you in once if you lose your authenticator app, and they won't be shown again.</p>
+    <ul>
+{recovery_codes_html}    </ul>
+    <p><a href="/admin/dashboard">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
@@ -0,0 +1,112 @@
+use crate::authentication::{Role, UserId, UserRepository};
+use crate::csrf::csrf_token_input;
+use crate::session_state::TypedSession;
+use crate::utils::{e500, html_escape};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use std::fmt::Write;
+
+#[tracing::instrument(skip(session, flash_messages, user_repo, user_id))]
+pub async fn manage_users(
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    user_repo: web::Data<dyn UserRepository + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let current_user = user_id.into_inner().as_string();
+    let csrf_token_input = csrf_token_input(&session)?;
+
+    let mut msg_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+
+    let users = user_repo.list_users().await.map_err(e500)?;
+
+    let mut users_html = String::new();
+    for user in &users {
+        let action = if user.disabled {
+            "disabled".to_string()
+        } else if user.username == current_user {
+            "you".to_string()
+        } else {
+            format!(
+                r#"<form action="/admin/users/disable" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="username" value="{username}">
+                <button type="submit">Disable</button>
+            </form>"#,
+                username = html_escape(&user.username),
+            )
+        };
+
+        writeln!(
+            users_html,
+            r#"        <li>
+            {username} ({role}) {email}
+            {action}
+            <form action="/admin/users/email" method="post">
+                {csrf_token_input}
+                <input type="hidden" name="username" value="{username}">
+                <input type="email" name="email" placeholder="Set their email address">
+                <button type="submit">Set email</button>
+            </form>
+        </li>"#,
+            username = html_escape(&user.username),
+            role = user.role,
+            email = html_escape(user.email.as_deref().unwrap_or("no email address")),
+        )
+        .unwrap();
+    }
+
+    let mut role_options = String::new();
+    for role in Role::ALL {
+        writeln!(
+            role_options,
+            r#"                <option value="{role}">{role}</option>"#
+        )
+        .unwrap();
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Users</title>
+</head>
+<body>
+    {msg_html}
+    <ul>
+{users_html}
+    </ul>
+    <form action="/admin/users" method="post">
+        {csrf_token_input}
+        <label>Username
+            <input type="text" placeholder="Enter the username" name="username">
+        </label>
+        <br>
+        <label>Email address
+            <input type="email" placeholder="Enter their email address" name="email">
+        </label>
+        <br>
+        <label>Password
+            <input type="password" placeholder="Enter their password" name="password">
+        </label>
+        <br>
+        <label>Role
+            <select name="role">
+{role_options}
+            </select>
+        </label>
+        <br>
+        <button type="submit">Add user</button>
+    </form>
+    <p><a href="/admin/dashboard">&lt;- Back</a></p>
+</body>
+</html>"#,
+        )))
+}
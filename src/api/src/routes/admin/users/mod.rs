@@ -0,0 +1,5 @@
+mod get;
+mod post;
+
+pub use get::manage_users;
+pub use post::{add_user, disable_user, set_user_email};
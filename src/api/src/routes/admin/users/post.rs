@@ -0,0 +1,124 @@
+use crate::authentication::{
+    validate_new_password, Role, UserAuthenticationError, UserCreationError, UserId, UserRepository,
+};
+use crate::domain::subscriber_email::SubscriberEmail;
+use crate::utils::{e500, html_escape, see_other};
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use secrecy::Secret;
+
+#[derive(serde::Deserialize)]
+pub struct AddUserFormData {
+    username: String,
+    email: String,
+    password: Secret<String>,
+    role: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct DisableUserFormData {
+    username: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetUserEmailFormData {
+    username: String,
+    email: String,
+}
+
+#[tracing::instrument(skip(form, user_repo), fields(username = %form.username))]
+pub async fn add_user(
+    form: web::Form<AddUserFormData>,
+    user_repo: web::Data<dyn UserRepository + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    let username = form.username.trim().to_string();
+
+    let validation = if username.is_empty() {
+        Err("The username can't be empty".to_string())
+    } else {
+        SubscriberEmail::parse(form.email.trim().to_string()).and_then(|email| {
+            validate_new_password(&form.password)?;
+            Ok((email, Role::parse(&form.role)?))
+        })
+    };
+
+    let (email, role) = match validation {
+        Ok(validated) => validated,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/users"));
+        }
+    };
+
+    match user_repo
+        .create_user(&username, &email, form.password, role)
+        .await
+    {
+        Ok(_) => FlashMessage::info(format!(
+            "{} has been added as an {}",
+            html_escape(&username),
+            role
+        ))
+        .send(),
+        Err(UserCreationError::UserExists(e)) => FlashMessage::error(html_escape(&e)).send(),
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/users"))
+}
+
+/// Set the address tests of draft issues are sent to when the user sends one.
+#[tracing::instrument(skip(form, user_repo), fields(username = %form.username))]
+pub async fn set_user_email(
+    form: web::Form<SetUserEmailFormData>,
+    user_repo: web::Data<dyn UserRepository + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let email = match SubscriberEmail::parse(form.email.trim().to_string()) {
+        Ok(email) => email,
+        Err(e) => {
+            FlashMessage::error(html_escape(&e)).send();
+            return Ok(see_other("/admin/users"));
+        }
+    };
+
+    match user_repo.set_email(&form.username, &email).await {
+        Ok(_) => FlashMessage::info(format!(
+            "The email address of {} has been set to {}",
+            html_escape(&form.username),
+            html_escape(email.as_ref())
+        ))
+        .send(),
+        Err(UserAuthenticationError::UserNotFoundError(e)) => {
+            FlashMessage::error(html_escape(&e)).send()
+        }
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/users"))
+}
+
+#[tracing::instrument(skip(form, user_repo, user_id), fields(username = %form.username))]
+pub async fn disable_user(
+    form: web::Form<DisableUserFormData>,
+    user_repo: web::Data<dyn UserRepository + Send + Sync>,
+    user_id: web::ReqData<UserId>,
+) -> Result<HttpResponse, actix_web::Error> {
+    // There would be nobody left to enable the owner again
+    if form.username == user_id.into_inner().as_string() {
+        FlashMessage::error("You can't disable yourself").send();
+        return Ok(see_other("/admin/users"));
+    }
+
+    match user_repo.disable_user(&form.username).await {
+        Ok(_) => {
+            FlashMessage::info(format!("{} has been disabled", html_escape(&form.username))).send()
+        }
+        Err(UserAuthenticationError::UserNotFoundError(e)) => {
+            FlashMessage::error(html_escape(&e)).send()
+        }
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/admin/users"))
+}
@@ -1,13 +1,19 @@
+use crate::csrf::csrf_token_input;
+use crate::session_state::TypedSession;
 use actix_web::{http::header::ContentType, HttpResponse};
 use actix_web_flash_messages::IncomingFlashMessages;
 use std::fmt::Write;
 
-pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
+pub async fn login_form(
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+) -> Result<HttpResponse, actix_web::Error> {
+    let csrf_token_input = csrf_token_input(&session)?;
     let mut error_html = String::new();
     for m in flash_messages.iter() {
         writeln!(error_html, "<p><i>{}</i></p>", m.content()).unwrap();
     }
-    HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(format!(
             r#"<!DOCTYPE html>
@@ -22,6 +28,7 @@ pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
     {error_html}
     <div class="row">
     <form action="/login" method="post" class="col s12">
+    {csrf_token_input}
     <div class="row">
     <div class="input-field col s12">
           <input placeholder="Username" id="username" name="username" type="text" class="validate">
@@ -42,5 +49,5 @@ pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
     <script src="https://cdnjs.cloudflare.com/ajax/libs/materialize/1.0.0/js/materialize.min.js"></script>
 </body>
 </html>"#,
-        ))
+        )))
 }
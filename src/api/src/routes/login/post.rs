@@ -1,11 +1,14 @@
-use crate::authentication::{validate_credentials, Credentials};
+use crate::authentication::{
+    is_locked_out, login_attempt_sources, record_failed_login, validate_credentials, Credentials,
+    LoginAttemptStore, TrustedProxies, TwoFactorStore,
+};
 use crate::authentication::{AuthError, UserRepository};
 use crate::session_state::TypedSession;
 use crate::utils::error_chain_fmt;
 use actix_web::error::InternalError;
 use actix_web::http::header::LOCATION;
 use actix_web::web;
-use actix_web::HttpResponse;
+use actix_web::{HttpRequest, HttpResponse};
 use actix_web_flash_messages::FlashMessage;
 use secrecy::Secret;
 
@@ -16,13 +19,17 @@ pub struct FormData {
 }
 
 #[tracing::instrument(
-skip(form, user_repo, session),
+skip(form, request, trusted_proxies, user_repo, login_attempt_store, two_factor_store, session),
 fields(username=tracing::field::Empty, user_id=tracing::field::Empty)
 )]
 // We are now injecting `PgPool` to retrieve stored credentials from the database
 pub async fn login(
     form: web::Form<FormData>,
+    request: HttpRequest,
+    trusted_proxies: web::Data<TrustedProxies>,
     user_repo: web::Data<dyn UserRepository + Send + Sync>,
+    login_attempt_store: web::Data<dyn LoginAttemptStore + Send + Sync>,
+    two_factor_store: web::Data<dyn TwoFactorStore + Send + Sync>,
     session: TypedSession,
 ) -> Result<HttpResponse, InternalError<LoginError>> {
     let credentials = Credentials {
@@ -31,12 +38,44 @@ pub async fn login(
     };
     tracing::Span::current().record("username", &tracing::field::display(&credentials.username));
 
+    let login_attempt_sources = login_attempt_sources(&credentials.username, &request, &trusted_proxies);
+
+    // Locked out attempts never reach the password hash, so they are cheap to turn away
+    if is_locked_out(login_attempt_store.get_ref(), &login_attempt_sources)
+        .await
+        .map_err(|e| login_redirect(LoginError::UnexpectedError(e)))?
+    {
+        return Err(login_redirect(LoginError::TooManyAttempts));
+    }
+
     match validate_credentials(credentials, user_repo.get_ref()).await {
         Ok(user_id) => {
             tracing::Span::current().record("user_id", &tracing::field::display(&user_id));
+            let two_factor = two_factor_store
+                .get_two_factor(&user_id)
+                .await
+                .map_err(|e| login_redirect(LoginError::UnexpectedError(e)))?;
             session.renew();
+
+            // The failed attempts are kept until the second factor is checked too, so logging in
+            // again with the password doesn't allow more guesses of the code
+            if two_factor.is_enabled() {
+                session
+                    .insert_pending_two_factor_user_id(user_id)
+                    .map_err(|e| login_redirect(LoginError::UnexpectedError(e.into())))?;
+                return Ok(HttpResponse::SeeOther()
+                    .insert_header((LOCATION, "/login/2fa"))
+                    .finish());
+            }
+
+            // Only the username is cleared, so one valid account can't reset the count of an
+            // IP address guessing the passwords of others
+            login_attempt_store
+                .clear_failed_attempts(&login_attempt_sources[0])
+                .await
+                .map_err(|e| login_redirect(LoginError::UnexpectedError(e)))?;
             session
-                .insert_user_id(user_id)
+                .log_in(user_id, &request)
                 .map_err(|e| login_redirect(LoginError::UnexpectedError(e.into())))?;
             Ok(HttpResponse::SeeOther()
                 .insert_header((LOCATION, "/admin/dashboard"))
@@ -44,7 +83,12 @@ pub async fn login(
         }
         Err(e) => {
             let e = match e {
-                AuthError::InvalidCredentials(_) => LoginError::AuthError(e.into()),
+                AuthError::InvalidCredentials(_) => {
+                    record_failed_login(login_attempt_store.get_ref(), &login_attempt_sources)
+                        .await
+                        .map_err(|e| login_redirect(LoginError::UnexpectedError(e)))?;
+                    LoginError::AuthError(e.into())
+                }
                 AuthError::UnexpectedError(_) => LoginError::UnexpectedError(e.into()),
             };
             Err(login_redirect(e))
@@ -67,6 +111,8 @@ fn login_redirect(e: LoginError) -> InternalError<LoginError> {
 pub enum LoginError {
     #[error("Authentication failed")]
     AuthError(#[source] anyhow::Error),
+    #[error("Too many failed login attempts. Try again in a few minutes")]
+    TooManyAttempts,
     #[error("Something went wrong")]
     UnexpectedError(#[from] anyhow::Error),
 }
@@ -0,0 +1,52 @@
+use crate::csrf::csrf_token_input;
+use crate::session_state::TypedSession;
+use crate::utils::{e500, see_other};
+use actix_web::{http::header::ContentType, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use std::fmt::Write;
+
+pub async fn two_factor_login_form(
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+) -> Result<HttpResponse, actix_web::Error> {
+    if session
+        .get_pending_two_factor_user_id()
+        .map_err(e500)?
+        .is_none()
+    {
+        return Ok(see_other("/login"));
+    }
+    let csrf_token_input = csrf_token_input(&session)?;
+
+    let mut error_html = String::new();
+    for m in flash_messages.iter() {
+        writeln!(error_html, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Login</title>
+</head>
+<body>
+    {error_html}
+    <form action="/login/2fa" method="post">
+        {csrf_token_input}
+        <label>Authentication code
+            <input
+                type="text"
+                autocomplete="one-time-code"
+                placeholder="Code from your app, or a recovery code"
+                name="code"
+            >
+        </label>
+        <br>
+        <button type="submit">Login</button>
+    </form>
+</body>
+</html>"#,
+        )))
+}
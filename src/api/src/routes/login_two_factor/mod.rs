@@ -0,0 +1,5 @@
+mod get;
+mod post;
+
+pub use get::two_factor_login_form;
+pub use post::two_factor_login;
@@ -0,0 +1,105 @@
+use crate::authentication::{
+    is_locked_out, login_attempt_sources, record_failed_login, verify_two_factor_code,
+    LoginAttemptStore, TrustedProxies, TwoFactorEncryptionKey, TwoFactorStore,
+};
+use crate::session_state::TypedSession;
+use crate::utils::{error_chain_fmt, see_other};
+use actix_web::error::InternalError;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use secrecy::{ExposeSecret, Secret};
+
+#[derive(serde::Deserialize)]
+pub struct FormData {
+    code: Secret<String>,
+}
+
+/// The second step of logging in, for users with two-factor authentication. The session only
+/// holds the user id once the code is accepted.
+#[tracing::instrument(
+    skip(
+        form,
+        request,
+        trusted_proxies,
+        session,
+        two_factor_store,
+        encryption_key,
+        login_attempt_store
+    ),
+    fields(user_id=tracing::field::Empty)
+)]
+pub async fn two_factor_login(
+    form: web::Form<FormData>,
+    request: HttpRequest,
+    trusted_proxies: web::Data<TrustedProxies>,
+    session: TypedSession,
+    two_factor_store: web::Data<dyn TwoFactorStore + Send + Sync>,
+    encryption_key: web::Data<TwoFactorEncryptionKey>,
+    login_attempt_store: web::Data<dyn LoginAttemptStore + Send + Sync>,
+) -> Result<HttpResponse, InternalError<TwoFactorLoginError>> {
+    let user_id = match session
+        .get_pending_two_factor_user_id()
+        .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e.into())))?
+    {
+        Some(user_id) => user_id,
+        None => return Ok(see_other("/login")),
+    };
+    tracing::Span::current().record("user_id", tracing::field::display(&user_id));
+
+    let login_attempt_sources = login_attempt_sources(&user_id, &request, &trusted_proxies);
+    if is_locked_out(login_attempt_store.get_ref(), &login_attempt_sources)
+        .await
+        .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e)))?
+    {
+        return Err(two_factor_redirect(TwoFactorLoginError::TooManyAttempts));
+    }
+
+    let is_valid = verify_two_factor_code(
+        two_factor_store.get_ref(),
+        &encryption_key,
+        &user_id,
+        form.code.expose_secret(),
+    )
+    .await
+    .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e)))?;
+
+    if !is_valid {
+        record_failed_login(login_attempt_store.get_ref(), &login_attempt_sources)
+            .await
+            .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e)))?;
+        return Err(two_factor_redirect(TwoFactorLoginError::InvalidCode));
+    }
+
+    login_attempt_store
+        .clear_failed_attempts(&login_attempt_sources[0])
+        .await
+        .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e)))?;
+    session.renew();
+    session.remove_pending_two_factor_user_id();
+    session
+        .log_in(user_id, &request)
+        .map_err(|e| two_factor_redirect(TwoFactorLoginError::UnexpectedError(e.into())))?;
+
+    Ok(see_other("/admin/dashboard"))
+}
+
+fn two_factor_redirect(e: TwoFactorLoginError) -> InternalError<TwoFactorLoginError> {
+    FlashMessage::error(e.to_string()).send();
+    InternalError::from_response(e, see_other("/login/2fa"))
+}
+
+#[derive(thiserror::Error)]
+pub enum TwoFactorLoginError {
+    #[error("The authentication code is incorrect")]
+    InvalidCode,
+    #[error("Too many failed login attempts. Try again in a few minutes")]
+    TooManyAttempts,
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for TwoFactorLoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
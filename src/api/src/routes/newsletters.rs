@@ -0,0 +1,28 @@
+use crate::domain::newsletter_template::render_for_archive;
+use crate::domain::NewsletterStore;
+use crate::utils::e500;
+use actix_web::http::header::{ContentType, CONTENT_SECURITY_POLICY};
+use actix_web::{web, HttpResponse};
+
+/// The public archive of published issues, rendered from the content stored when the issue was
+/// published.
+#[tracing::instrument(skip(newsletter_store))]
+pub async fn newsletter_issue(
+    slug: web::Path<String>,
+    newsletter_store: web::Data<dyn NewsletterStore + Send + Sync>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let issue = newsletter_store
+        .get_published_issue(&slug)
+        .await
+        .map_err(e500)?;
+
+    match issue {
+        // Issues are written by editors, sandboxing them stops any script they contain running
+        // on the site's origin
+        Some(issue) => Ok(HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .insert_header((CONTENT_SECURITY_POLICY, "sandbox"))
+            .body(render_for_archive(&issue.html_content, &issue.issue_title))),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
@@ -1,7 +1,9 @@
 use crate::domain::new_subscriber::NewSubscriber;
 use crate::domain::subscriber_email::SubscriberEmail;
 use crate::domain::subscriber_name::SubscriberName;
-use crate::domain::subscriber_repository::SubscriberRepository;
+use crate::domain::subscriber_repository::{
+    DatabaseError, SubscriberRepository, SubscriptionStatus,
+};
 use actix_web::http::StatusCode;
 use actix_web::{web, HttpResponse, ResponseError};
 use anyhow::Context;
@@ -68,16 +70,89 @@ pub async fn subscribe(
     let new_subscriber: NewSubscriber =
         form.0.try_into().map_err(SubscribeError::ValidationError)?;
 
-    let subscriber_id = repo
-        .insert_subscriber(&new_subscriber)
+    let subscription_token = generate_subscription_token();
+
+    match repo
+        .insert_subscriber_with_token(&new_subscriber, &subscription_token)
         .await
-        .context("Failed to insert new subscriber in the database.")?;
+    {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(DatabaseError::UserExists(_)) => resubscribe(repo.get_ref(), &new_subscriber).await,
+        Err(e) => Err(anyhow::Error::new(e)
+            .context("Failed to insert new subscriber in the database.")
+            .into()),
+    }
+}
 
-    let subscription_token = generate_subscription_token();
+/// A repeated subscription gets a fresh confirmation email unless the subscriber is already
+/// confirmed. Every case responds the same way so the response doesn't reveal who is on the list.
+async fn resubscribe(
+    repo: &(dyn SubscriberRepository + Send + Sync),
+    new_subscriber: &NewSubscriber,
+) -> Result<HttpResponse, SubscribeError> {
+    let status = repo
+        .get_subscription_status(new_subscriber.email.as_ref())
+        .await
+        .context("Failed to retrieve subscription status")?;
+
+    match status {
+        Some(SubscriptionStatus::Confirmed) => {
+            info!("Subscriber is already confirmed, no confirmation email sent");
+        }
+        _ => {
+            let token_stored = repo
+                .store_token(
+                    new_subscriber.email.to_string(),
+                    &generate_subscription_token(),
+                )
+                .await
+                .context("Failed to store token in the database")?;
+
+            if !token_stored {
+                info!("A confirmation token was issued recently, no confirmation email sent");
+            }
+        }
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
 
-    repo.store_token(subscriber_id, &subscription_token)
+#[derive(serde::Deserialize)]
+pub struct ResendFormData {
+    pub email: String,
+}
+
+/// Issue a new confirmation token for a pending subscriber, the token stream sends the email.
+/// Tokens are issued at most once per cooldown period per subscriber. Always responds with a 200
+/// so the response doesn't reveal who is on the list, or whether the email was resent.
+#[tracing::instrument(
+    name = "resending_confirmation",
+    skip(form, repo),
+    fields(subscriber_email = %form.email)
+)]
+pub async fn resend_confirmation(
+    form: web::Form<ResendFormData>,
+    repo: web::Data<dyn SubscriberRepository + Send + Sync>,
+) -> Result<HttpResponse, SubscribeError> {
+    let email = SubscriberEmail::parse(form.0.email).map_err(SubscribeError::ValidationError)?;
+
+    let status = repo
+        .get_subscription_status(email.as_ref())
         .await
-        .context("Failed to store token in the database")?;
+        .context("Failed to retrieve subscription status")?;
+
+    if status == Some(SubscriptionStatus::Pending) {
+        let token_stored = repo
+            .store_token(email.to_string(), &generate_subscription_token())
+            .await
+            .context("Failed to store token in the database")?;
+
+        if !token_stored {
+            info!("A confirmation token was issued recently, confirmation email not resent");
+        }
+    } else {
+        info!("No pending subscription, confirmation email not resent");
+    }
 
     Ok(HttpResponse::Ok().finish())
 }
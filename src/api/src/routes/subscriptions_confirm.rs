@@ -1,4 +1,4 @@
-use crate::domain::subscriber_repository::SubscriberRepository;
+use crate::domain::subscriber_repository::{DatabaseError, SubscriberRepository};
 use crate::utils::error_chain_fmt;
 use actix_web::{web, HttpResponse, ResponseError};
 use anyhow::Context;
@@ -15,6 +15,10 @@ pub enum ConfirmationError {
     UnexpectedError(#[from] anyhow::Error),
     #[error("There is no subscriber associated with the provided token.")]
     UnknownToken,
+    #[error("The provided token has expired. Request a new confirmation email.")]
+    ExpiredToken,
+    #[error("You have unsubscribed since this token was issued. Subscribe again to receive the newsletter.")]
+    Unsubscribed,
 }
 
 impl std::fmt::Debug for ConfirmationError {
@@ -27,6 +31,7 @@ impl ResponseError for ConfirmationError {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::UnknownToken => StatusCode::UNAUTHORIZED,
+            Self::ExpiredToken | Self::Unsubscribed => StatusCode::GONE,
             Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -37,19 +42,25 @@ pub async fn confirm(
     parameters: web::Query<Parameters>,
     repo: web::Data<dyn SubscriberRepository + Send + Sync>,
 ) -> Result<HttpResponse, ConfirmationError> {
-    let id = repo
+    let subscriber_id = repo
         .get_subscriber_id_from_token(&parameters.subscription_token)
         .await
-        .context("Failed to retrieve subscription token")?;
+        .map_err(|e| match e {
+            DatabaseError::TokenNotFoundError(_) => ConfirmationError::UnknownToken,
+            DatabaseError::TokenExpired(_) => ConfirmationError::ExpiredToken,
+            e => ConfirmationError::UnexpectedError(
+                anyhow::Error::new(e).context("Failed to retrieve subscription token"),
+            ),
+        })?;
 
-    match id {
-        None => Err(ConfirmationError::UnknownToken),
-        Some(subscriber_id) => {
-            repo.confirm_subscriber(subscriber_id)
-                .await
-                .context("Failed to confirm subscriber")?;
+    let confirmed = repo
+        .confirm_subscriber(subscriber_id)
+        .await
+        .context("Failed to confirm subscriber")?;
 
-            Ok(HttpResponse::Ok().finish())
-        }
+    if !confirmed {
+        return Err(ConfirmationError::Unsubscribed);
     }
+
+    Ok(HttpResponse::Ok().finish())
 }
@@ -0,0 +1,97 @@
+use crate::domain::subscriber_repository::SubscriberRepository;
+use crate::domain::unsubscribe_token::UnsubscribeToken;
+use crate::startup::HmacSecret;
+use crate::utils::{error_chain_fmt, html_escape};
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+
+#[derive(serde::Deserialize)]
+pub struct UnsubscribeParameters {
+    token: String,
+}
+
+#[derive(thiserror::Error)]
+pub enum UnsubscribeError {
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error("{0}")]
+    InvalidToken(String),
+}
+
+impl std::fmt::Debug for UnsubscribeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for UnsubscribeError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidToken(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Ask the subscriber to confirm, so link scanners following the email link don't unsubscribe.
+#[tracing::instrument(name = "unsubscribe_form", skip(parameters, hmac_secret))]
+pub async fn unsubscribe_form(
+    parameters: web::Query<UnsubscribeParameters>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let email = UnsubscribeToken::parse(&parameters.token, &hmac_secret.0)
+        .map_err(UnsubscribeError::InvalidToken)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Unsubscribe</title>
+</head>
+<body>
+    <p>Stop sending newsletters to {email}?</p>
+    <form action="/subscriptions/unsubscribe?token={token}" method="post">
+        <button type="submit">Unsubscribe</button>
+    </form>
+</body>
+</html>"#,
+            email = html_escape(email.as_ref()),
+            token = html_escape(&parameters.token),
+        )))
+}
+
+/// Handles both the confirmation form and one-click unsubscribes from the `List-Unsubscribe`
+/// header, which post `List-Unsubscribe=One-Click` to the same URL.
+#[tracing::instrument(name = "unsubscribe", skip(parameters, repo, hmac_secret))]
+pub async fn unsubscribe(
+    parameters: web::Query<UnsubscribeParameters>,
+    repo: web::Data<dyn SubscriberRepository + Send + Sync>,
+    hmac_secret: web::Data<HmacSecret>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let email = UnsubscribeToken::parse(&parameters.token, &hmac_secret.0)
+        .map_err(UnsubscribeError::InvalidToken)?;
+
+    repo.unsubscribe(email.to_string())
+        .await
+        .context("Failed to unsubscribe subscriber")?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <title>Unsubscribe</title>
+</head>
+<body>
+    <p>You have been unsubscribed.</p>
+</body>
+</html>"#,
+        ))
+}
@@ -1,31 +1,98 @@
 use actix_session::SessionExt;
 use actix_session::{Session, SessionGetError, SessionInsertError};
 use actix_web::dev::Payload;
+use actix_web::http::header::USER_AGENT;
 use actix_web::{FromRequest, HttpRequest};
+use async_trait::async_trait;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::collections::HashMap;
 use std::future::{ready, Ready};
+use uuid::Uuid;
 
 pub struct TypedSession(Session);
 
 impl TypedSession {
     const USER_ID_KEY: &'static str = "user_id";
+    /// Identifies the session among the sessions of the user, see `SessionRegistry`.
+    const SESSION_ID_KEY: &'static str = "session_id";
+    const USER_AGENT_KEY: &'static str = "user_agent";
+    /// Set between a correct password and a correct second factor, instead of the user id.
+    const PENDING_TWO_FACTOR_USER_ID_KEY: &'static str = "pending_two_factor_user_id";
+    /// Forms are submitted with it, see `csrf_protection`.
+    const CSRF_TOKEN_KEY: &'static str = "csrf_token";
 
     pub fn renew(&self) {
         self.0.renew();
     }
 
-    pub fn insert_user_id(&self, user_id: String) -> Result<(), SessionInsertError> {
-        self.0.insert(Self::USER_ID_KEY, user_id)
+    /// Log the user in, recording the browser they logged in with from the request.
+    pub fn log_in(&self, user_id: String, request: &HttpRequest) -> Result<(), SessionInsertError> {
+        let user_agent = request
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok());
+
+        self.0.insert(Self::USER_ID_KEY, user_id)?;
+        self.0
+            .insert(Self::SESSION_ID_KEY, Uuid::new_v4().to_string())?;
+        // Forms rendered before logging in can't be submitted as the user
+        self.0.remove(Self::CSRF_TOKEN_KEY);
+        self.0.insert(Self::USER_AGENT_KEY, user_agent)
     }
 
     pub fn get_user_id(&self) -> Result<Option<String>, SessionGetError> {
         self.0.get(Self::USER_ID_KEY)
     }
 
+    /// `None` for sessions started before sessions were identified.
+    pub fn get_session_id(&self) -> Result<Option<String>, SessionGetError> {
+        self.0.get(Self::SESSION_ID_KEY)
+    }
+
+    pub fn insert_pending_two_factor_user_id(
+        &self,
+        user_id: String,
+    ) -> Result<(), SessionInsertError> {
+        self.0.insert(Self::PENDING_TWO_FACTOR_USER_ID_KEY, user_id)
+    }
+
+    pub fn get_pending_two_factor_user_id(&self) -> Result<Option<String>, SessionGetError> {
+        self.0.get(Self::PENDING_TWO_FACTOR_USER_ID_KEY)
+    }
+
+    pub fn remove_pending_two_factor_user_id(&self) {
+        self.0.remove(Self::PENDING_TWO_FACTOR_USER_ID_KEY);
+    }
+
+    /// The CSRF token of the session, issued the first time a form is rendered.
+    pub fn get_or_issue_csrf_token(&self) -> Result<String, anyhow::Error> {
+        if let Some(csrf_token) = self.get_csrf_token()? {
+            return Ok(csrf_token);
+        }
+
+        let csrf_token = generate_csrf_token();
+        self.0.insert(Self::CSRF_TOKEN_KEY, &csrf_token)?;
+        Ok(csrf_token)
+    }
+
+    pub fn get_csrf_token(&self) -> Result<Option<String>, SessionGetError> {
+        self.0.get(Self::CSRF_TOKEN_KEY)
+    }
+
     pub fn log_out(self) {
         self.0.purge()
     }
 }
 
+fn generate_csrf_token() -> String {
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(32)
+        .collect()
+}
+
 impl FromRequest for TypedSession {
     type Error = <Session as FromRequest>::Error;
     type Future = Ready<Result<TypedSession, Self::Error>>;
@@ -34,3 +101,71 @@ impl FromRequest for TypedSession {
         ready(Ok(TypedSession(req.get_session())))
     }
 }
+
+/// A session of a user, as stored by the session store.
+pub struct ActiveSession {
+    /// Identifies the session to the store without revealing its key, which is enough to take
+    /// the session over.
+    pub revocation_id: String,
+    /// Unix timestamp of when the session was first stored.
+    pub created_at: i64,
+    /// The session state, each value serialised as JSON.
+    pub state: HashMap<String, String>,
+}
+
+impl ActiveSession {
+    pub fn session_id(&self) -> Option<String> {
+        self.get(TypedSession::SESSION_ID_KEY)
+    }
+
+    pub fn user_agent(&self) -> Option<String> {
+        self.get(TypedSession::USER_AGENT_KEY)
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.state
+            .get(key)
+            .and_then(|value| serde_json::from_str::<Option<String>>(value).ok())
+            .flatten()
+    }
+}
+
+/// Returned by a `SessionRegistry` that doesn't store sessions on the server, rather than
+/// reporting that there is nothing to list or log out.
+#[derive(Debug, thiserror::Error)]
+#[error("Sessions are stored in the browser's cookie, so they can't be listed or logged out from another browser.")]
+pub struct SessionsNotTracked;
+
+/// Lists and revokes the sessions of a user, so they can be logged out of other browsers.
+#[async_trait]
+pub trait SessionRegistry {
+    /// The sessions of the user that haven't expired, oldest first.
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<ActiveSession>, anyhow::Error>;
+
+    /// Returns `false` if the user has no session with that revocation id.
+    async fn revoke_session(
+        &self,
+        user_id: &str,
+        revocation_id: &str,
+    ) -> Result<bool, anyhow::Error>;
+}
+
+/// Revoke every session of the user but the one identified by `current_session_id`. Sessions
+/// started before sessions were identified can't be told apart, so they are all revoked.
+pub async fn revoke_other_sessions(
+    registry: &(dyn SessionRegistry + Send + Sync),
+    user_id: &str,
+    current_session_id: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    for session in registry.list_sessions(user_id).await? {
+        if current_session_id.is_some() && session.session_id().as_deref() == current_session_id {
+            continue;
+        }
+
+        registry
+            .revoke_session(user_id, &session.revocation_id)
+            .await?;
+    }
+
+    Ok(())
+}
@@ -1,13 +1,27 @@
-use crate::adapters::dynamo_db_session_store::DynamoDbSessionStore;
+use crate::adapters::configured_session_store::ConfiguredSessionStore;
+use crate::adapters::dynamodb_idempotency_store::DynamoDbIdempotencyStore;
+use crate::adapters::dynamodb_login_attempt_store::DynamoDbLoginAttemptStore;
 use crate::adapters::dynamodb_subscriber_repository::DynamoDbSubscriberRepository;
+use crate::adapters::dynamodb_two_factor_store::DynamoDbTwoFactorStore;
 use crate::adapters::dynamodb_user_repository::DynamoDbUserRepository;
-use crate::authentication::{reject_anonymous_users, UserRepository};
-use crate::configuration::{DatabaseSettings, Settings};
+use crate::authentication::{
+    reject_anonymous_users, require_editor, require_owner, LoginAttemptStore, TrustedProxies,
+    TwoFactorEncryptionKey, TwoFactorStore, UserRepository,
+};
+use crate::configuration::{ApplicationSettings, DatabaseSettings, SessionSettings, Settings};
+use crate::csrf::csrf_protection;
 use crate::domain::subscriber_repository::SubscriberRepository;
+use crate::idempotency::IdempotencyStore;
 use crate::routes::{
-    admin_dashboard, change_password, change_password_form, confirm, health_check, home, log_out,
-    login, login_form, migrate_db, publish_newsletter, publish_newsletter_form, subscribe,
+    active_sessions, add_user, admin_dashboard, cancel_scheduled_newsletter, change_password,
+    change_password_form, confirm, disable_user, enable_two_factor, health_check, home, log_out,
+    log_out_other_sessions, login, login_form, manage_users, newsletter_drafts,
+    newsletter_history, newsletter_issue, preview_newsletter_draft, publish_newsletter,
+    publish_newsletter_draft, publish_newsletter_form, resend_confirmation, revoke_session,
+    save_newsletter_draft, scheduled_newsletters, send_newsletter_test, set_user_email, subscribe,
+    two_factor_form, two_factor_login, two_factor_login_form, unsubscribe, unsubscribe_form,
 };
+use crate::session_state::SessionRegistry;
 use actix_session::SessionMiddleware;
 use actix_web::cookie::Key;
 use actix_web::dev::{Server, Service};
@@ -30,6 +44,7 @@ use std::sync::Arc;
 use crate::adapters::S3NewsletterMetadataStorage;
 use crate::domain::NewsletterStore;
 use crate::middleware::TraceData;
+use crate::migrations::apply_migrations;
 use opentelemetry_sdk::trace::TracerProvider;
 use tokio::sync::mpsc::UnboundedSender;
 use tracing_actix_web::{RequestId, TracingLogger};
@@ -50,11 +65,17 @@ impl Application {
         ))?;
 
         let port = listener.local_addr().unwrap().port();
+
+        if configuration.database.run_migrations {
+            let dynamodb_client = configure_dynamodb_client(&configuration.database).await;
+            apply_migrations(&dynamodb_client, &configuration.database).await?;
+        }
+
         let server = run(
             listener,
             configuration.database,
-            configuration.application.base_url,
-            configuration.application.hmac_secret,
+            configuration.application,
+            configuration.session,
             tracer_provider,
             request_done_sender
         )
@@ -76,23 +97,23 @@ impl Application {
 async fn run(
     listener: TcpListener,
     db_settings: DatabaseSettings,
-    base_url: String,
-    hmac_secret: Secret<String>,
+    application_settings: ApplicationSettings,
+    session_settings: SessionSettings,
     tracer: TracerProvider,
     request_done_sender: UnboundedSender<()>
 ) -> Result<Server, anyhow::Error> {
+    let two_factor_encryption_key =
+        TwoFactorEncryptionKey::parse(&application_settings.two_factor_encryption_key)?;
+    let trusted_proxies = TrustedProxies::new(application_settings.trusted_proxies);
+    let hmac_secret = application_settings.hmac_secret;
+
     let secret_key = Key::from(hmac_secret.clone().expose_secret().as_bytes());
     let message_store = CookieMessageStore::builder(secret_key.clone()).build();
     let message_framework = FlashMessagesFramework::builder(message_store).build();
 
-    let dynamo_db_store = DynamoDbSessionStore::builder()
-        .table_name(db_settings.auth_database_name.clone())
-        .key_name("PK".to_string())
-        .use_dynamo_db_local(db_settings.use_local)
-        .build()
-        .await?;
+    let session_store = ConfiguredSessionStore::build(&session_settings, &db_settings).await?;
 
-    let base_url = Data::new(ApplicationBaseUrl(base_url));
+    let base_url = Data::new(ApplicationBaseUrl(application_settings.base_url));
     
     let (s3_client, dynamodb_client) = configure_aws(&db_settings).await;
 
@@ -125,6 +146,37 @@ async fn run(
     let newsletter_store_data: Data<dyn NewsletterStore + Send + Sync> =
         Data::from(newsletter_store_arc);
 
+    let idempotency_store_arc: Arc<dyn IdempotencyStore + Send + Sync> =
+        Arc::new(DynamoDbIdempotencyStore::new(
+            dynamodb_client.clone(),
+            db_settings.auth_database_name.clone(),
+        ));
+    let idempotency_store_data: Data<dyn IdempotencyStore + Send + Sync> =
+        Data::from(idempotency_store_arc);
+
+    let login_attempt_store_arc: Arc<dyn LoginAttemptStore + Send + Sync> =
+        Arc::new(DynamoDbLoginAttemptStore::new(
+            dynamodb_client.clone(),
+            db_settings.auth_database_name.clone(),
+        ));
+    let login_attempt_store_data: Data<dyn LoginAttemptStore + Send + Sync> =
+        Data::from(login_attempt_store_arc);
+
+    let two_factor_store_arc: Arc<dyn TwoFactorStore + Send + Sync> =
+        Arc::new(DynamoDbTwoFactorStore::new(
+            dynamodb_client.clone(),
+            db_settings.auth_database_name.clone(),
+        ));
+    let two_factor_store_data: Data<dyn TwoFactorStore + Send + Sync> =
+        Data::from(two_factor_store_arc);
+    let two_factor_encryption_key = Data::new(two_factor_encryption_key);
+    let trusted_proxies = Data::new(trusted_proxies);
+
+    let session_registry_arc: Arc<dyn SessionRegistry + Send + Sync> =
+        Arc::new(session_store.clone());
+    let session_registry_data: Data<dyn SessionRegistry + Send + Sync> =
+        Data::from(session_registry_arc);
+
     let server = HttpServer::new(move || {
         let arc_tracer = Arc::new(tracer.clone());
         let tracer_data = Data::from(arc_tracer);
@@ -132,16 +184,80 @@ async fn run(
         App::new()
             .wrap(message_framework.clone())
             .wrap(SessionMiddleware::new(
-                dynamo_db_store.clone(),
+                session_store.clone(),
                 secret_key.clone(),
             ))
             .route("/", web::get().to(home))
             .service(
                 web::scope("/admin")
+                    // Runs after reject_anonymous_users, so logged out users are sent to log in
+                    .wrap(from_fn(csrf_protection))
                     .wrap(from_fn(reject_anonymous_users))
                     .route("/dashboard", web::get().to(admin_dashboard))
-                    .route("/newsletters", web::get().to(publish_newsletter_form))
-                    .route("/newsletters", web::post().to(publish_newsletter))
+                    .route(
+                        "/newsletters",
+                        web::get()
+                            .to(publish_newsletter_form)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route(
+                        "/newsletters",
+                        web::post()
+                            .to(publish_newsletter)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route("/newsletters/drafts", web::get().to(newsletter_drafts))
+                    .route(
+                        "/newsletters/drafts",
+                        web::post()
+                            .to(save_newsletter_draft)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route("/newsletters/drafts/preview", web::get().to(preview_newsletter_draft))
+                    .route(
+                        "/newsletters/drafts/test",
+                        web::post()
+                            .to(send_newsletter_test)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route(
+                        "/newsletters/drafts/publish",
+                        web::post()
+                            .to(publish_newsletter_draft)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route("/newsletters/history", web::get().to(newsletter_history))
+                    .route("/newsletters/scheduled", web::get().to(scheduled_newsletters))
+                    .route(
+                        "/newsletters/scheduled/cancel",
+                        web::post()
+                            .to(cancel_scheduled_newsletter)
+                            .wrap(from_fn(require_editor)),
+                    )
+                    .route(
+                        "/users",
+                        web::get().to(manage_users).wrap(from_fn(require_owner)),
+                    )
+                    .route(
+                        "/users",
+                        web::post().to(add_user).wrap(from_fn(require_owner)),
+                    )
+                    .route(
+                        "/users/disable",
+                        web::post().to(disable_user).wrap(from_fn(require_owner)),
+                    )
+                    .route(
+                        "/users/email",
+                        web::post().to(set_user_email).wrap(from_fn(require_owner)),
+                    )
+                    .route("/two-factor", web::get().to(two_factor_form))
+                    .route("/two-factor", web::post().to(enable_two_factor))
+                    .route("/sessions", web::get().to(active_sessions))
+                    .route("/sessions/revoke", web::post().to(revoke_session))
+                    .route(
+                        "/sessions/revoke-others",
+                        web::post().to(log_out_other_sessions),
+                    )
                     .route("/password", web::get().to(change_password_form))
                     .route("/password", web::post().to(change_password))
                     .route("/logout", web::post().to(log_out)),
@@ -167,15 +283,33 @@ async fn run(
             })
             .wrap(TraceData)
             .route("/login", web::get().to(login_form))
-            .route("/login", web::post().to(login))
+            .route(
+                "/login",
+                web::post().to(login).wrap(from_fn(csrf_protection)),
+            )
+            .route("/login/2fa", web::get().to(two_factor_login_form))
+            .route(
+                "/login/2fa",
+                web::post()
+                    .to(two_factor_login)
+                    .wrap(from_fn(csrf_protection)),
+            )
             .route("/health_check", web::get().to(health_check))
             .route("/subscriptions", web::post().to(subscribe))
             .route("/subscriptions/confirm", web::get().to(confirm))
-            .route("/newsletters", web::post().to(publish_newsletter))
-            .route("/util/_migrate", web::get().to(migrate_db))
+            .route("/subscriptions/resend", web::post().to(resend_confirmation))
+            .route("/subscriptions/unsubscribe", web::get().to(unsubscribe_form))
+            .route("/subscriptions/unsubscribe", web::post().to(unsubscribe))
+            .route("/newsletters/{slug}", web::get().to(newsletter_issue))
             .app_data(store_data.clone())
             .app_data(user_repo_data.clone())
             .app_data(newsletter_store_data.clone())
+            .app_data(idempotency_store_data.clone())
+            .app_data(login_attempt_store_data.clone())
+            .app_data(two_factor_store_data.clone())
+            .app_data(two_factor_encryption_key.clone())
+            .app_data(trusted_proxies.clone())
+            .app_data(session_registry_data.clone())
             .app_data(base_url.clone())
             .app_data(Data::new(HmacSecret(hmac_secret.clone())))
             .app_data(tracer_data.clone())
@@ -187,16 +321,28 @@ async fn run(
     Ok(server)
 }
 
-async fn configure_aws(
-    db_settings: &DatabaseSettings,
-) -> (aws_sdk_s3::Client, aws_sdk_dynamodb::Client) {
+/// A DynamoDB client for the configured tables, used by the `migrate` binary.
+pub async fn configure_dynamodb_client(db_settings: &DatabaseSettings) -> aws_sdk_dynamodb::Client {
+    let dynamo_config = configure_dynamo(&https_client(), db_settings).await;
+
+    aws_sdk_dynamodb::Client::from_conf(dynamo_config)
+}
+
+fn https_client() -> SharedHttpClient {
     let https_connector = hyper_rustls::HttpsConnectorBuilder::new()
         .with_webpki_roots()
         .https_or_http()
         .enable_http1()
         .enable_http2()
         .build();
-    let hyper_client = HyperClientBuilder::new().build(https_connector);
+
+    HyperClientBuilder::new().build(https_connector)
+}
+
+async fn configure_aws(
+    db_settings: &DatabaseSettings,
+) -> (aws_sdk_s3::Client, aws_sdk_dynamodb::Client) {
+    let hyper_client = https_client();
 
     let s3_config = configure_s3(&hyper_client, db_settings).await;
     let dynamo_config = configure_dynamo(&hyper_client, db_settings).await;
@@ -14,6 +14,16 @@ pub fn see_other(location: &str) -> HttpResponse {
         .finish()
 }
 
+/// Escape user provided values before interpolating them into an HTML page.
+pub fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
 pub fn error_chain_fmt(
     e: &impl std::error::Error,
     f: &mut std::fmt::Formatter<'_>,
@@ -0,0 +1,82 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use secrecy::Secret;
+use uuid::Uuid;
+use zero2prod::adapters::dynamodb_user_repository::DynamoDbUserRepository;
+use zero2prod::authentication::{Role, UserCreationError, UserRepository};
+use zero2prod::domain::subscriber_email::SubscriberEmail;
+
+#[tokio::test]
+async fn the_seeding_endpoint_is_not_exposed() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/util/_migrate", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn a_bootstrapped_admin_can_log_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_repo =
+        DynamoDbUserRepository::new(app.dynamo_db_client.clone(), app.auth_table_name.clone());
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+    let email = SubscriberEmail::parse(format!("{}@test.com", username)).unwrap();
+
+    // Act
+    user_repo
+        .create_user(
+            &username,
+            &email,
+            Secret::new(password.clone()),
+            Role::Owner,
+        )
+        .await
+        .unwrap();
+
+    // Assert
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &username,
+            "password": &password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn bootstrapping_an_existing_user_is_refused() {
+    // Arrange
+    let app = spawn_app().await;
+    let user_repo =
+        DynamoDbUserRepository::new(app.dynamo_db_client.clone(), app.auth_table_name.clone());
+
+    // Act
+    let result = user_repo
+        .create_user(
+            &app.test_user.username,
+            &SubscriberEmail::parse("someone-else@test.com".to_string()).unwrap(),
+            Secret::new(Uuid::new_v4().to_string()),
+            Role::Owner,
+        )
+        .await;
+
+    // Assert - The existing password still works
+    assert!(matches!(result, Err(UserCreationError::UserExists(_))));
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
@@ -0,0 +1,107 @@
+use crate::helpers::{assert_is_redirect_to, extract_csrf_token, get_csrf_token, spawn_app};
+
+#[tokio::test]
+async fn logins_without_a_csrf_token_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/login", &app.address))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Reload the page and try again"));
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn forms_with_a_mismatched_csrf_token_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", &app.address))
+        .form(&serde_json::json!({ "csrf_token": "not-the-token" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn csrf_tokens_of_another_session_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let other_browser = app
+        .login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+    let other_csrf_token = get_csrf_token(&other_browser, &app.address).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", &app.address))
+        .form(&serde_json::json!({ "csrf_token": other_csrf_token }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn csrf_tokens_can_be_sent_in_a_header() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let csrf_token = get_csrf_token(&app.api_client, &app.address).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", &app.address))
+        .header("X-CSRF-Token", csrf_token)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn the_csrf_token_changes_when_logging_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let csrf_token_before_login = get_csrf_token(&app.api_client, &app.address).await;
+
+    // Act
+    app.test_user.login(&app).await;
+
+    // Assert
+    let html_page = app.get_admin_dashboard_html().await;
+    assert_ne!(extract_csrf_token(&html_page), csrf_token_before_login);
+}
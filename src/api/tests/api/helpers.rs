@@ -3,20 +3,22 @@ use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
 use async_trait::async_trait;
 use aws_config::environment::EnvironmentVariableCredentialsProvider;
 use aws_config::{BehaviorVersion, Region};
-use aws_sdk_dynamodb::types::ScalarAttributeType::S;
-use aws_sdk_dynamodb::types::{
-    AttributeDefinition, AttributeValue, BillingMode, GlobalSecondaryIndex, KeySchemaElement,
-    KeyType, Projection, ProjectionType,
-};
+use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 use opentelemetry::trace::TracerProvider;
 use tokio::sync::mpsc::unbounded_channel;
 
 use tracing::log::info;
+use secrecy::Secret;
 use uuid::Uuid;
 use wiremock::MockServer;
-use zero2prod::configuration::{get_configuration, DatabaseSettings};
+use zero2prod::configuration::{
+    get_configuration, DatabaseSettings, SessionBackend, SessionEncryptionKeySettings,
+};
+use std::collections::HashMap;
+use zero2prod::domain::issue_slug;
 use zero2prod::domain::subscriber_email::SubscriberEmail;
+use zero2prod::migrations::apply_migrations;
 use zero2prod::startup::Application;
 use telemetry::{get_subscriber, init_subscriber, init_tracer};
 
@@ -34,6 +36,8 @@ pub struct TestApp {
     pub api_client: reqwest::Client,
     pub dynamo_db_client: aws_sdk_dynamodb::Client,
     pub table_name: String,
+    pub auth_table_name: String,
+    pub hmac_secret: Secret<String>,
 }
 
 impl TestApp {
@@ -47,6 +51,49 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn post_resend_confirmation(&self, body: String) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/subscriptions/resend", &self.address))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn count_tokens_for_email(&self, email_address: &str) -> usize {
+        let scan_results: Result<Vec<_>, _> = self
+            .dynamo_db_client
+            .scan()
+            .table_name(&self.table_name)
+            .into_paginator()
+            .items()
+            .send()
+            .collect()
+            .await;
+
+        scan_results
+            .unwrap()
+            .iter()
+            .filter(|item| item["Type"].as_s().unwrap() == "SubscriberToken")
+            .filter(|item| item["EmailAddress"].as_s().unwrap() == email_address)
+            .count()
+    }
+
+    /// Move the last confirmation token of a subscriber back past the cooldown, so another one
+    /// can be issued straight away.
+    pub async fn end_confirmation_cooldown(&self, email_address: &str) {
+        self.dynamo_db_client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(email_address.to_string()))
+            .update_expression("SET LastTokenCreatedAt = :created_at")
+            .expression_attribute_values(":created_at", AttributeValue::N("0".to_string()))
+            .send()
+            .await
+            .expect("Failed to end the confirmation cooldown");
+    }
+
     pub async fn confirm_subscription(&self, token: String) -> reqwest::Response {
         reqwest::Client::new()
             .get(&format!(
@@ -59,13 +106,33 @@ impl TestApp {
             .expect("Failed to execute request")
     }
 
+    pub async fn get_unsubscribe(&self, token: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .get(format!("{}/subscriptions/unsubscribe", &self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_unsubscribe(&self, token: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/subscriptions/unsubscribe", &self.address))
+            .query(&[("token", token)])
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn post_login<Body>(&self, body: &Body) -> reqwest::Response
     where
         Body: serde::Serialize,
     {
         self.api_client
             .post(&format!("{}/login", &self.address))
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -99,21 +166,35 @@ impl TestApp {
     }
 
     pub async fn validate_newsletter_storage(&self, title: &str) -> Result<(), ()> {
-        let get_res = self
-            .dynamo_db_client
+        match self.get_newsletter_item(title).await {
+            None => Err(()),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// The id of the issue stored with `title`, found through the slug the issue reserved.
+    pub async fn get_newsletter_issue_id(&self, title: &str) -> Option<String> {
+        self.get_item(&format!("NewsletterSlug#{}", issue_slug(title)))
+            .await
+            .and_then(|item| item.get("IssueId").and_then(|id| id.as_s().ok()).cloned())
+    }
+
+    async fn get_newsletter_item(&self, title: &str) -> Option<HashMap<String, AttributeValue>> {
+        let issue_id = self.get_newsletter_issue_id(title).await?;
+
+        self.get_item(&format!("NewsletterIssue#{}", issue_id))
+            .await
+    }
+
+    async fn get_item(&self, pk: &str) -> Option<HashMap<String, AttributeValue>> {
+        self.dynamo_db_client
             .get_item()
             .table_name(&self.table_name)
-            .key("PK", AttributeValue::S(title.to_string()))
+            .key("PK", AttributeValue::S(pk.to_string()))
             .send()
-            .await;
-
-        match get_res {
-            Ok(res) => match res.item {
-                None => Err(()),
-                Some(_) => Ok(()),
-            },
-            Err(_) => Err(()),
-        }
+            .await
+            .expect("Failed to read newsletter item")
+            .item
     }
 
     pub async fn get_login_html(&self) -> String {
@@ -154,6 +235,7 @@ impl TestApp {
     pub async fn post_logout(&self) -> reqwest::Response {
         self.api_client
             .post(&format!("{}/admin/logout", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({})).await)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -165,7 +247,7 @@ impl TestApp {
     {
         self.api_client
             .post(&format!("{}/admin/password", &self.address))
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -189,12 +271,148 @@ impl TestApp {
     {
         self.api_client
             .post(&format!("{}/admin/newsletters", &self.address))
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_save_newsletter_draft<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/newsletters/drafts", &self.address))
+            .form(&self.with_csrf_token(body).await)
             .send()
             .await
             .expect("Failed to execute request.")
     }
 
+    pub async fn get_newsletter_drafts_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/newsletters/drafts", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get_newsletter_draft_preview(&self, issue_id: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/newsletters/drafts/preview", &self.address))
+            .query(&[("issue_id", issue_id)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_newsletter_test_send(&self, issue_id: &str) -> reqwest::Response {
+        let form = serde_json::json!({ "issue_id": issue_id });
+        self.api_client
+            .post(format!("{}/admin/newsletters/drafts/test", &self.address))
+            .form(&self.with_csrf_token(&form).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_publish_newsletter_draft(&self, issue_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/newsletters/drafts/publish", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({ "issue_id": issue_id })).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_newsletter_history(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/newsletters/history", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_newsletter_history_html(&self) -> String {
+        self.get_newsletter_history().await.text().await.unwrap()
+    }
+
+    pub async fn get_newsletter_issue(&self, slug: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/newsletters/{}", &self.address, slug))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_scheduled_newsletters_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/newsletters/scheduled", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_cancel_scheduled_newsletter(&self, issue_id: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/newsletters/scheduled/cancel", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({ "issue_id": issue_id })).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_newsletter_type(&self, issue_id: &str) -> Option<String> {
+        self.get_item(&format!("NewsletterIssue#{}", issue_id))
+            .await
+            .and_then(|item| item.get("Type").and_then(|t| t.as_s().ok()).cloned())
+    }
+
+    /// Record delivery progress on the stats item of an issue, the way the backend does as it sends
+    /// the issue.
+    pub async fn record_delivery_progress(
+        &self,
+        issue_id: &str,
+        recipient_count: usize,
+        audience_size: Option<usize>,
+    ) {
+        let mut update = self
+            .dynamo_db_client
+            .update_item()
+            .table_name(&self.table_name)
+            .key(
+                "PK",
+                AttributeValue::S(format!("NewsletterIssueStats#{}", issue_id)),
+            )
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(
+                ":stats_type",
+                AttributeValue::S("NewsletterIssueStats".to_string()),
+            )
+            .expression_attribute_values(":issue_id", AttributeValue::S(issue_id.to_string()))
+            .expression_attribute_values(":count", AttributeValue::N(recipient_count.to_string()));
+
+        let stats_item = "#type = :stats_type, IssueId = :issue_id, \
+            GSI1PK = :stats_type, GSI1SK = :issue_id, RecipientCount = :count";
+        update = match audience_size {
+            Some(audience_size) => update
+                .update_expression(format!("SET {}, AudienceSize = :audience", stats_item))
+                .expression_attribute_values(
+                    ":audience",
+                    AttributeValue::N(audience_size.to_string()),
+                ),
+            None => update.update_expression(format!("SET {}", stats_item)),
+        };
+
+        update.send().await.expect("Failed to record delivery progress");
+    }
+
     /// Extract the confirmation links embedded in the request to the email API.
     pub fn get_confirmation_links(&self, email_request: &wiremock::Request) -> ConfirmationLinks {
         let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
@@ -219,6 +437,181 @@ impl TestApp {
         ConfirmationLinks { html, plain_text }
     }
 
+    /// Store another user with the given role, who can log in alongside the test user.
+    pub async fn create_user(&self, role: &'static str) -> TestUser {
+        let user = TestUser::generate_with_role(role);
+        user.store(&self.dynamo_db_client, &self.auth_table_name)
+            .await;
+        user
+    }
+
+    pub async fn get_user_item(&self, username: &str) -> HashMap<String, AttributeValue> {
+        self.dynamo_db_client
+            .get_item()
+            .table_name(&self.auth_table_name)
+            .key("PK", AttributeValue::S(username.to_string()))
+            .send()
+            .await
+            .unwrap()
+            .item
+            .unwrap()
+    }
+
+    pub async fn get_manage_users(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/admin/users", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_manage_users_html(&self) -> String {
+        self.get_manage_users().await.text().await.unwrap()
+    }
+
+    pub async fn post_add_user<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/users", &self.address))
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_set_user_email<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/users/email", &self.address))
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_disable_user(&self, username: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/users/disable", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({ "username": username })).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_two_factor_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/two-factor", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_enable_two_factor(&self, code: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/two-factor", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({ "code": code })).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_two_factor_login_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/login/2fa", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_two_factor_login(&self, code: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/login/2fa", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({ "code": code })).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_sessions_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/sessions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_revoke_session(&self, revocation_id: &str) -> reqwest::Response {
+        let form = serde_json::json!({ "revocation_id": revocation_id });
+        self.api_client
+            .post(format!("{}/admin/sessions/revoke", &self.address))
+            .form(&self.with_csrf_token(&form).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_log_out_other_sessions(&self) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/sessions/revoke-others", &self.address))
+            .form(&self.with_csrf_token(&serde_json::json!({})).await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Log the user in from a separate client, with its own cookies, as if from another browser.
+    pub async fn login_from_another_browser(
+        &self,
+        user: &TestUser,
+        user_agent: &str,
+    ) -> reqwest::Client {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .cookie_store(true)
+            .user_agent(user_agent)
+            .build()
+            .unwrap();
+
+        let csrf_token = get_csrf_token(&client, &self.address).await;
+        client
+            .post(format!("{}/login", &self.address))
+            .form(&serde_json::json!({
+                "username": &user.username,
+                "password": &user.password,
+                "csrf_token": csrf_token
+            }))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        client
+    }
+
+    /// Add the CSRF token of the session to a form, as the browser submits it.
+    pub async fn with_csrf_token<Body>(&self, body: &Body) -> serde_json::Value
+    where
+        Body: serde::Serialize,
+    {
+        let mut body = serde_json::to_value(body).unwrap();
+        body["csrf_token"] = get_csrf_token(&self.api_client, &self.address)
+            .await
+            .into();
+        body
+    }
+
     pub async fn post_newsletters(&self, body: serde_json::Value) -> reqwest::Response {
         reqwest::Client::new()
             .post(&format!("{}/newsletters", &self.address))
@@ -229,7 +622,32 @@ impl TestApp {
     }
 }
 
+/// The CSRF token of the session of the client, read from the login form.
+pub async fn get_csrf_token(client: &reqwest::Client, address: &str) -> String {
+    let html_page = client
+        .get(format!("{}/login", address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .text()
+        .await
+        .unwrap();
+
+    extract_csrf_token(&html_page)
+}
+
+pub fn extract_csrf_token(html_page: &str) -> String {
+    let marker = r#"name="csrf_token" value=""#;
+    let start = html_page.find(marker).expect("No CSRF token in the page") + marker.len();
+    let end = start + html_page[start..].find('"').unwrap();
+    html_page[start..end].to_string()
+}
+
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with_session_backend(SessionBackend::DynamoDb).await
+}
+
+pub async fn spawn_app_with_session_backend(session_backend: SessionBackend) -> TestApp {
     // Launch a mock server to stand in for Postmark's API
     let email_server = MockServer::start().await;
 
@@ -244,6 +662,12 @@ pub async fn spawn_app() -> TestApp {
         c.database.use_local = true;
         // Use a random OS port
         c.application.application_port = 0;
+        c.session.backend = session_backend;
+        // Sessions are stored encrypted, as they are when deployed
+        c.session.encryption_keys = vec![SessionEncryptionKeySettings {
+            id: "test".to_string(),
+            key: Secret::new("6VfjAXpNxNVBiJmlYaVdWNtgoJVxQvl/eDk1G3QoUcs=".to_string()),
+        }];
         c.telemetry.otlp_endpoint = "http://localhost:4318".to_string();
         c.telemetry.dataset_name = "test-zero2prod".to_string();
         c
@@ -285,6 +709,8 @@ pub async fn spawn_app() -> TestApp {
         api_client: client,
         dynamo_db_client: dynamo_db_client.clone(),
         table_name: configuration.database.database_name.clone(),
+        auth_table_name: configuration.database.auth_database_name.clone(),
+        hmac_secret: configuration.application.hmac_secret.clone(),
     };
 
     test_app
@@ -308,102 +734,33 @@ pub async fn configure_database(config: &DatabaseSettings) -> Client {
 
     let dynamodb_client = aws_sdk_dynamodb::Client::from_conf(conf);
 
-    let _create_table = dynamodb_client
-        .create_table()
-        .table_name(&config.database_name)
-        .billing_mode(BillingMode::PayPerRequest)
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name("PK")
-                .attribute_type(S)
-                .build()
-                .unwrap(),
-        )
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name("GSI1PK")
-                .attribute_type(S)
-                .build()
-                .unwrap(),
-        )
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name("GSI1SK")
-                .attribute_type(S)
-                .build()
-                .unwrap(),
-        )
-        .key_schema(
-            KeySchemaElement::builder()
-                .attribute_name("PK")
-                .key_type(KeyType::Hash)
-                .build()
-                .unwrap(),
-        )
-        .global_secondary_indexes(
-            GlobalSecondaryIndex::builder()
-                .index_name("GSI1")
-                .key_schema(
-                    KeySchemaElement::builder()
-                        .attribute_name("GSI1PK")
-                        .key_type(KeyType::Hash)
-                        .build()
-                        .unwrap(),
-                )
-                .key_schema(
-                    KeySchemaElement::builder()
-                        .attribute_name("GSI1SK")
-                        .key_type(KeyType::Range)
-                        .build()
-                        .unwrap(),
-                )
-                .projection(
-                    Projection::builder()
-                        .projection_type(ProjectionType::All)
-                        .build(),
-                )
-                .build()
-                .unwrap(),
-        )
-        .send()
-        .await
-        .unwrap();
-
-    let _create_table = dynamodb_client
-        .create_table()
-        .table_name(&config.auth_database_name)
-        .billing_mode(BillingMode::PayPerRequest)
-        .attribute_definitions(
-            AttributeDefinition::builder()
-                .attribute_name("PK")
-                .attribute_type(S)
-                .build()
-                .unwrap(),
-        )
-        .key_schema(
-            KeySchemaElement::builder()
-                .attribute_name("PK")
-                .key_type(KeyType::Hash)
-                .build()
-                .unwrap(),
-        )
-        .send()
+    apply_migrations(&dynamodb_client, config)
         .await
-        .unwrap();
+        .expect("Failed to migrate the database");
 
     dynamodb_client
 }
 
 pub struct TestUser {
     pub username: String,
+    pub email: String,
     pub password: String,
+    pub role: &'static str,
 }
 
 impl TestUser {
+    /// The test user of every `TestApp` is an owner, so it can do everything.
     pub fn generate() -> Self {
+        Self::generate_with_role("owner")
+    }
+
+    pub fn generate_with_role(role: &'static str) -> Self {
+        let username = Uuid::new_v4().to_string();
         Self {
-            username: Uuid::new_v4().to_string(),
+            email: format!("{}@test.com", username),
+            username,
             password: Uuid::new_v4().to_string(),
+            role,
         }
     }
 
@@ -433,6 +790,10 @@ impl TestUser {
             .item("PK", AttributeValue::S(self.username.to_string()))
             .item("SK", AttributeValue::S("CREDENTIALS".to_string()))
             .item("password_hash", AttributeValue::S(password_hash))
+            .item("EmailAddress", AttributeValue::S(self.email.to_string()))
+            .item("Role", AttributeValue::S(self.role.to_string()))
+            .item("GSI1PK", AttributeValue::S("User".to_string()))
+            .item("GSI1SK", AttributeValue::S(self.username.to_string()))
             .send()
             .await
             .expect("Failure creating test user");
@@ -23,3 +23,59 @@ async fn an_error_flash_message_is_set_on_failure() {
     let html_page = app.get_login_html().await;
     assert!(!html_page.contains("Authentication failed"));
 }
+
+#[tokio::test]
+async fn logins_are_locked_out_after_too_many_failed_attempts() {
+    // Arrange
+    let app = spawn_app().await;
+    for _ in 0..5 {
+        app.post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": "wrong-password"
+        }))
+        .await;
+    }
+
+    // Act - Part 1 - Log in with the right password
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_login_html().await;
+    assert!(html_page.contains("<p><i>Too many failed login attempts."));
+}
+
+#[tokio::test]
+async fn a_successful_login_clears_the_failed_attempts() {
+    // Arrange
+    let app = spawn_app().await;
+    let wrong_login_body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": "wrong-password"
+    });
+    let login_body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password
+    });
+
+    for _ in 0..4 {
+        app.post_login(&wrong_login_body).await;
+    }
+    app.post_login(&login_body).await;
+
+    // Act
+    for _ in 0..4 {
+        app.post_login(&wrong_login_body).await;
+    }
+    let response = app.post_login(&login_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
@@ -1,8 +1,15 @@
 mod admin_dashboard;
+mod bootstrap_admin;
 mod change_password;
+mod csrf;
 mod health_check;
 mod helpers;
 mod login;
+mod migrations;
 mod newsletter;
+mod sessions;
 mod subscriptions;
 mod subscriptions_confirm;
+mod subscriptions_unsubscribe;
+mod two_factor;
+mod users;
@@ -0,0 +1,214 @@
+use crate::helpers::configure_database;
+use aws_sdk_dynamodb::types::AttributeValue;
+use uuid::Uuid;
+use zero2prod::configuration::{get_configuration, DatabaseSettings};
+use zero2prod::migrations::apply_migrations;
+
+async fn database_settings() -> DatabaseSettings {
+    let mut settings = get_configuration()
+        .await
+        .expect("Failed to read configuration.")
+        .database;
+    settings.database_name = Uuid::new_v4().to_string();
+    settings.auth_database_name = Uuid::new_v4().to_string();
+    settings.use_local = true;
+    settings
+}
+
+#[tokio::test]
+async fn migrations_can_be_applied_again() {
+    // Arrange
+    let settings = database_settings().await;
+    let client = configure_database(&settings).await;
+
+    // Act
+    let first_version = apply_migrations(&client, &settings).await.unwrap();
+    let second_version = apply_migrations(&client, &settings).await.unwrap();
+
+    // Assert
+    assert_eq!(first_version, second_version);
+    let tables = client.list_tables().send().await.unwrap();
+    assert!(tables.table_names().contains(&settings.database_name));
+    assert!(tables.table_names().contains(&settings.auth_database_name));
+}
+
+#[tokio::test]
+async fn subscribers_without_a_status_are_backfilled() {
+    // Arrange
+    let settings = database_settings().await;
+    let client = configure_database(&settings).await;
+    let latest_version = apply_migrations(&client, &settings).await.unwrap();
+
+    client
+        .put_item()
+        .table_name(&settings.database_name)
+        .item("PK", AttributeValue::S("james@test.com".to_string()))
+        .item("Type", AttributeValue::S("Subscriber".to_string()))
+        .item("GSI1PK", AttributeValue::S("confirmed".to_string()))
+        .item("GSI1SK", AttributeValue::S("james@test.com".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Forget the applied migrations, so the backfill runs again
+    client
+        .delete_item()
+        .table_name(&settings.database_name)
+        .key("PK", AttributeValue::S("SchemaVersion".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let version = apply_migrations(&client, &settings).await.unwrap();
+
+    // Assert
+    assert_eq!(version, latest_version);
+    let subscriber = client
+        .get_item()
+        .table_name(&settings.database_name)
+        .key("PK", AttributeValue::S("james@test.com".to_string()))
+        .send()
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+    assert_eq!(
+        subscriber["SubscriptionStatus"].as_s().unwrap(),
+        &"confirmed".to_string()
+    );
+}
+
+#[tokio::test]
+async fn users_are_backfilled_into_the_user_index() {
+    // Arrange
+    let settings = database_settings().await;
+    let client = configure_database(&settings).await;
+    let latest_version = apply_migrations(&client, &settings).await.unwrap();
+
+    client
+        .put_item()
+        .table_name(&settings.auth_database_name)
+        .item("PK", AttributeValue::S("admin".to_string()))
+        .item("password_hash", AttributeValue::S("hash".to_string()))
+        .item("Role", AttributeValue::S("owner".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Forget the applied migrations, so the backfill runs again
+    client
+        .delete_item()
+        .table_name(&settings.database_name)
+        .key("PK", AttributeValue::S("SchemaVersion".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let version = apply_migrations(&client, &settings).await.unwrap();
+
+    // Assert
+    assert_eq!(version, latest_version);
+    let user = client
+        .get_item()
+        .table_name(&settings.auth_database_name)
+        .key("PK", AttributeValue::S("admin".to_string()))
+        .send()
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+    assert_eq!(user["GSI1PK"].as_s().unwrap(), &"User".to_string());
+    assert_eq!(user["GSI1SK"].as_s().unwrap(), &"admin".to_string());
+}
+
+#[tokio::test]
+async fn recipient_counts_are_backfilled_on_the_stats_item() {
+    // Arrange
+    let settings = database_settings().await;
+    let client = configure_database(&settings).await;
+    let latest_version = apply_migrations(&client, &settings).await.unwrap();
+    let issue_id = Uuid::new_v4().to_string();
+
+    // An issue stored under its title, before issues had an id
+    client
+        .put_item()
+        .table_name(&settings.database_name)
+        .item("PK", AttributeValue::S("Legacy issue".to_string()))
+        .item("Type", AttributeValue::S("NewsletterIssue".to_string()))
+        .item("IssueTitle", AttributeValue::S("Legacy issue".to_string()))
+        .send()
+        .await
+        .unwrap();
+    client
+        .put_item()
+        .table_name(&settings.database_name)
+        .item(
+            "PK",
+            AttributeValue::S(format!("NewsletterIssue#{}", issue_id)),
+        )
+        .item("Type", AttributeValue::S("NewsletterIssue".to_string()))
+        .item("IssueId", AttributeValue::S(issue_id.clone()))
+        .send()
+        .await
+        .unwrap();
+    client
+        .put_item()
+        .table_name(&settings.database_name)
+        .item(
+            "PK",
+            AttributeValue::S(format!("NewsletterDelivery#{}#james@test.com", issue_id)),
+        )
+        .item("Type", AttributeValue::S("NewsletterDelivery".to_string()))
+        .item(
+            "GSI1PK",
+            AttributeValue::S(format!("NewsletterDelivery#{}", issue_id)),
+        )
+        .item("GSI1SK", AttributeValue::S("james@test.com".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Forget the applied migrations, so the backfill runs again
+    client
+        .delete_item()
+        .table_name(&settings.database_name)
+        .key("PK", AttributeValue::S("SchemaVersion".to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let version = apply_migrations(&client, &settings).await.unwrap();
+
+    // Assert
+    assert_eq!(version, latest_version);
+    let get_item = |pk: String| {
+        client
+            .get_item()
+            .table_name(&settings.database_name)
+            .key("PK", AttributeValue::S(pk))
+            .send()
+    };
+    let stats = get_item(format!("NewsletterIssueStats#{}", issue_id))
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+    assert_eq!(stats["RecipientCount"].as_n().unwrap(), "1");
+    assert_eq!(stats["AudienceSize"].as_n().unwrap(), "1");
+    // The issue items are left alone, so the send newsletter pipe doesn't send them again
+    let issue = get_item(format!("NewsletterIssue#{}", issue_id))
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+    assert!(!issue.contains_key("RecipientCount"));
+    let legacy_issue = get_item("Legacy issue".to_string())
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+    assert!(!legacy_issue.contains_key("RecipientCount"));
+}
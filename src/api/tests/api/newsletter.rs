@@ -17,6 +17,13 @@ async fn create_unconfirmed_subscriber(app: &TestApp) {
         .unwrap();
 }
 
+/// A send time for the `datetime-local` input of the publish form.
+fn tomorrow() -> String {
+    (chrono::Utc::now() + chrono::TimeDelta::try_days(1).unwrap())
+        .format("%Y-%m-%dT%H:%M")
+        .to_string()
+}
+
 async fn create_confirmed_subscriber(app: &TestApp) {
     let _ = create_unconfirmed_subscriber(app).await;
     let token = app.get_token_for_email("james@test.com").await;
@@ -41,6 +48,7 @@ async fn newsletter_is_stored_ready_for_send() {
         "title": "Newsletter title",
         "text_content": "Newsletter body as plain text",
         "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
     });
     let response = app.post_publish_newsletter(&newsletter_request_body).await;
     assert_is_redirect_to(&response, "/admin/newsletters");
@@ -78,9 +86,495 @@ async fn you_must_be_logged_in_to_publish_a_newsletter() {
         "title": "Newsletter title",
         "text_content": "Newsletter body as plain text",
         "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
     });
     let response = app.post_publish_newsletter(&newsletter_request_body).await;
 
     // Assert
     assert_is_redirect_to(&response, "/login");
 }
+
+#[tokio::test]
+async fn newsletters_with_unknown_placeholders_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Submit newsletter form
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter with a typo",
+        "text_content": "Hi {{ first_name }}",
+        "html_content": "<p>Hi {{ name }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("{{ first_name }} is not a supported placeholder"));
+
+    // Assert
+    let res = app
+        .validate_newsletter_storage("Newsletter with a typo")
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn unknown_placeholders_are_escaped_in_the_error_message() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Submit newsletter form
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter with markup in a placeholder",
+        "text_content": "Hi {{ <b>name</b> }}",
+        "html_content": "<p>Hi {{ name }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+
+    // Assert
+    assert!(html_page.contains("{{ &lt;b&gt;name&lt;/b&gt; }} is not a supported placeholder"));
+    assert!(!html_page.contains("<b>name</b>"));
+}
+
+#[tokio::test]
+async fn newsletters_with_a_send_time_are_scheduled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let send_at = tomorrow();
+
+    // Act - Part 1 - Submit newsletter form
+    let newsletter_request_body = serde_json::json!({
+        "title": "Scheduled newsletter",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": send_at,
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been scheduled!</i></p>"));
+
+    // Assert
+    let issue_id = app
+        .get_newsletter_issue_id("Scheduled newsletter")
+        .await
+        .unwrap();
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("ScheduledNewsletterIssue".to_string())
+    );
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("Scheduled newsletter"));
+}
+
+#[tokio::test]
+async fn send_times_in_the_past_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter from the past",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": "2020-01-01T09:00",
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("The send time must be in the future"));
+    assert!(app
+        .validate_newsletter_storage("Newsletter from the past")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn invalid_send_times_are_escaped_in_the_error_message() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter with markup as its send time",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": "<b>tomorrow</b>",
+    });
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Assert
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("&lt;b&gt;tomorrow&lt;/b&gt; is not a valid send time"));
+    assert!(!html_page.contains("<b>tomorrow</b>"));
+}
+
+#[tokio::test]
+async fn scheduled_newsletters_can_be_cancelled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let send_at = tomorrow();
+    let newsletter_request_body = serde_json::json!({
+        "title": "Cancelled newsletter",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": send_at,
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    let issue_id = app
+        .get_newsletter_issue_id("Cancelled newsletter")
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.post_cancel_scheduled_newsletter(&issue_id).await;
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+
+    // Assert
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("The scheduled newsletter issue has been cancelled"));
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("CancelledNewsletterIssue".to_string())
+    );
+
+    // Cancelling again is reported rather than failing
+    app.post_cancel_scheduled_newsletter(&issue_id).await;
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("is not scheduled"));
+
+    // The title of a cancelled issue can be used again
+    let newsletter_request_body = serde_json::json!({
+        "title": "Cancelled newsletter",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "send_at": send_at,
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been scheduled!</i></p>"));
+}
+
+#[tokio::test]
+async fn drafts_are_not_sent_until_they_are_published() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Draft newsletter",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Draft body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+
+    // Act - Part 1 - Save the draft
+    let response = app
+        .post_save_newsletter_draft(&newsletter_request_body)
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters/drafts");
+
+    let html_page = app.get_newsletter_drafts_html().await;
+    assert!(html_page.contains("<p><i>The newsletter draft has been saved!</i></p>"));
+    assert!(html_page.contains("Draft newsletter"));
+    let issue_id = app
+        .get_newsletter_issue_id("Draft newsletter")
+        .await
+        .unwrap();
+    assert!(html_page.contains(&issue_id));
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("DraftNewsletterIssue".to_string())
+    );
+
+    // Act - Part 2 - Preview it
+    let preview = app.get_newsletter_draft_preview(&issue_id).await;
+    assert_eq!(preview.status().as_u16(), 200);
+    assert_eq!(
+        preview.headers().get("Content-Security-Policy").unwrap(),
+        "sandbox"
+    );
+    assert_eq!(preview.text().await.unwrap(), "<p>Draft body as HTML</p>");
+
+    // Act - Part 3 - Send a test
+    let response = app.post_newsletter_test_send(&issue_id).await;
+    assert_is_redirect_to(&response, "/admin/newsletters/drafts");
+    let html_page = app.get_newsletter_drafts_html().await;
+    assert!(html_page.contains(&format!(
+        "A test of the draft has been sent to {}",
+        app.test_user.email
+    )));
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("DraftNewsletterIssue".to_string())
+    );
+
+    // Act - Part 4 - Publish it
+    let response = app.post_publish_newsletter_draft(&issue_id).await;
+    assert_is_redirect_to(&response, "/admin/newsletters/drafts");
+
+    // Assert
+    let html_page = app.get_newsletter_drafts_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been published!</i></p>"));
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("NewsletterIssue".to_string())
+    );
+}
+
+#[tokio::test]
+async fn only_drafts_can_be_previewed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let preview = app
+        .get_newsletter_draft_preview("01HQ3Z5K8M2N4P6R8T0V2X4Z6B-unknown-newsletter")
+        .await;
+
+    // Assert
+    assert_eq!(preview.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn published_issues_are_listed_in_the_history_and_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Archived Issue #1",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Hi {{ name }}, welcome to {{ issue_title }}</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Act - Part 1 - The admin history
+    let html_page = app.get_newsletter_history_html().await;
+    assert!(html_page.contains(r#"<a href="/newsletters/archived-issue-1">Archived Issue #1</a>"#));
+
+    // Act - Part 2 - The public archive
+    app.post_logout().await;
+    let response = app.get_newsletter_issue("archived-issue-1").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers().get("Content-Security-Policy").unwrap(),
+        "sandbox"
+    );
+    assert_eq!(
+        response.text().await.unwrap(),
+        "<p>Hi subscriber, welcome to Archived Issue #1</p>"
+    );
+}
+
+#[tokio::test]
+async fn the_history_shows_an_issue_as_delivered_once_its_whole_audience_has_it() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Issue being sent",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    app.post_publish_newsletter(&newsletter_request_body).await;
+    let issue_id = app
+        .get_newsletter_issue_id("Issue being sent")
+        .await
+        .unwrap();
+
+    // Act - Part 1 - Nothing sent yet
+    let html_page = app.get_newsletter_history_html().await;
+    assert!(html_page.contains("<td>Queued</td>"));
+
+    // Act - Part 2 - Some chunks delivered, others still queued or in the DLQ
+    app.record_delivery_progress(&issue_id, 1, Some(3)).await;
+    let html_page = app.get_newsletter_history_html().await;
+    assert!(html_page.contains("<td>Sending</td>"));
+
+    // Act - Part 3 - Every recipient has it
+    app.record_delivery_progress(&issue_id, 3, Some(3)).await;
+    let html_page = app.get_newsletter_history_html().await;
+
+    // Assert
+    assert!(html_page.contains("<td>3</td>"));
+    assert!(html_page.contains("<td>Delivered</td>"));
+}
+
+#[tokio::test]
+async fn drafts_are_not_in_the_public_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Unpublished draft",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    app.post_save_newsletter_draft(&newsletter_request_body)
+        .await;
+
+    // Act
+    let response = app.get_newsletter_issue("unpublished-draft").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[tokio::test]
+async fn issues_with_a_duplicate_title_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Issue #1",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+    app.post_save_newsletter_draft(&newsletter_request_body)
+        .await;
+
+    // Act - Part 1 - Publish an issue with the same title
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("An issue titled Issue #1 already exists"));
+
+    // Assert - The draft is untouched
+    let issue_id = app.get_newsletter_issue_id("Issue #1").await.unwrap();
+    assert_eq!(
+        app.get_newsletter_type(&issue_id).await,
+        Some("DraftNewsletterIssue".to_string())
+    );
+}
+
+#[tokio::test]
+async fn titles_with_slashes_are_stored_under_their_issue_id() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Rust/WebAssembly news",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+
+    // Act
+    app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Assert
+    let issue_id = app
+        .get_newsletter_issue_id("Rust/WebAssembly news")
+        .await
+        .unwrap();
+    assert!(issue_id.ends_with("-rust-webassembly-news"));
+    let response = app.get_newsletter_issue("rust-webassembly-news").await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn newsletter_creation_is_idempotent() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+
+    // Act - Part 1 - Submit newsletter form
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been published!</i></p>"));
+
+    // Act - Part 3 - Submit newsletter form **again**
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+
+    // Act - Part 4 - Follow the redirect
+    let html_page = app.get_publish_newsletter_html().await;
+    assert!(html_page.contains("<p><i>The newsletter issue has been published!</i></p>"));
+    assert!(!html_page.contains("already exists"));
+}
+
+#[tokio::test]
+async fn concurrent_form_submission_is_handled_gracefully() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+    });
+
+    // Act - Submit two newsletter forms concurrently
+    let response1 = app.post_publish_newsletter(&newsletter_request_body);
+    let response2 = app.post_publish_newsletter(&newsletter_request_body);
+    let (response1, response2) = tokio::join!(response1, response2);
+
+    // Assert - One publishes the issue, the other replays it or is told it is in progress
+    let mut statuses = [response1.status().as_u16(), response2.status().as_u16()];
+    statuses.sort();
+    assert_eq!(statuses[0], 303);
+    assert!(statuses[1] == 303 || statuses[1] == 409);
+    assert!(app
+        .get_newsletter_issue_id("Newsletter title")
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn publishing_without_an_idempotency_key_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    });
+
+    // Act
+    let response = app.post_publish_newsletter(&newsletter_request_body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,303 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, spawn_app_with_session_backend, TestApp};
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::collections::HashMap;
+use zero2prod::configuration::SessionBackend;
+
+async fn get_admin_dashboard_from(app: &TestApp, client: &reqwest::Client) -> reqwest::Response {
+    client
+        .get(format!("{}/admin/dashboard", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn get_session_items(app: &TestApp, user_id: &str) -> Vec<HashMap<String, AttributeValue>> {
+    app.dynamo_db_client
+        .query()
+        .table_name(&app.auth_table_name)
+        .index_name("SessionsByUser")
+        .key_condition_expression("SessionUserId = :user_id")
+        .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+        .send()
+        .await
+        .expect("Failed to query sessions")
+        .items
+        .unwrap_or_default()
+}
+
+/// The revocation id of the first session on the page that can be logged out.
+fn first_revocation_id(html_page: &str) -> String {
+    let marker = r#"name="revocation_id" value=""#;
+    let start = html_page.find(marker).expect("No session to log out") + marker.len();
+    let end = start + html_page[start..].find('"').unwrap();
+    html_page[start..end].to_string()
+}
+
+#[tokio::test]
+async fn active_sessions_are_listed_with_their_browser() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+
+    // Act
+    let html_page = app.get_sessions_html().await;
+
+    // Assert
+    assert!(html_page.contains("Other browser/1.0"));
+    assert!(html_page.contains("this session"));
+    assert_eq!(html_page.matches(r#"name="revocation_id""#).count(), 1);
+}
+
+#[tokio::test]
+async fn another_session_can_be_logged_out() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let other_browser = app
+        .login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+    let revocation_id = first_revocation_id(&app.get_sessions_html().await);
+
+    // Act - Part 1 - Log the other session out
+    let response = app.post_revoke_session(&revocation_id).await;
+    assert_is_redirect_to(&response, "/admin/sessions");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_sessions_html().await;
+    assert!(html_page.contains("The session has been logged out."));
+    assert!(!html_page.contains("Other browser/1.0"));
+
+    // Assert
+    let response = get_admin_dashboard_from(&app, &other_browser).await;
+    assert_is_redirect_to(&response, "/login");
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn sessions_of_other_users_cannot_be_logged_out() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    let other_browser = app
+        .login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+    app.test_user.login(&app).await;
+    let revocation_id = first_revocation_id(&app.get_sessions_html().await);
+    app.post_logout().await;
+    editor.login(&app).await;
+
+    // Act
+    app.post_revoke_session(&revocation_id).await;
+
+    // Assert
+    let html_page = app.get_sessions_html().await;
+    assert!(html_page.contains("The session has already been logged out."));
+    let response = get_admin_dashboard_from(&app, &other_browser).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn logging_out_everywhere_else_keeps_the_current_session() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let first_browser = app
+        .login_from_another_browser(&app.test_user, "First browser/1.0")
+        .await;
+    let second_browser = app
+        .login_from_another_browser(&app.test_user, "Second browser/1.0")
+        .await;
+
+    // Act - Part 1 - Log out everywhere else
+    let response = app.post_log_out_other_sessions().await;
+    assert_is_redirect_to(&response, "/admin/sessions");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_sessions_html().await;
+    assert!(html_page.contains("You have been logged out everywhere else."));
+
+    // Assert
+    for browser in [&first_browser, &second_browser] {
+        let response = get_admin_dashboard_from(&app, browser).await;
+        assert_is_redirect_to(&response, "/login");
+    }
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn changing_the_password_logs_out_other_sessions() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let other_browser = app
+        .login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+    let new_password = uuid::Uuid::new_v4().to_string();
+
+    // Act
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": &new_password,
+            "new_password_check": &new_password,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/password");
+
+    // Assert
+    let response = get_admin_dashboard_from(&app, &other_browser).await;
+    assert_is_redirect_to(&response, "/login");
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn session_ttls_are_stored_in_seconds() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.test_user.login(&app).await;
+
+    // Assert
+    let sessions = get_session_items(&app, &app.test_user.username).await;
+    assert_eq!(sessions.len(), 1);
+    let ttl: i64 = sessions[0]["ttl"].as_n().unwrap().parse().unwrap();
+    let now = chrono::Utc::now().timestamp();
+    assert!(ttl > now);
+    assert!(ttl <= now + 24 * 60 * 60);
+}
+
+#[tokio::test]
+async fn expired_sessions_are_rejected_before_they_are_removed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let sessions = get_session_items(&app, &app.test_user.username).await;
+    let expired_at = chrono::Utc::now().timestamp() - 60;
+
+    // Act - Expire the session, which DynamoDB only removes later
+    app.dynamo_db_client
+        .update_item()
+        .table_name(&app.auth_table_name)
+        .key("PK", sessions[0]["PK"].clone())
+        .update_expression("SET #ttl = :ttl")
+        .expression_attribute_names("#ttl", "ttl")
+        .expression_attribute_values(":ttl", AttributeValue::N(expired_at.to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn session_data_is_stored_encrypted() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    app.test_user.login(&app).await;
+
+    // Assert
+    let sessions = get_session_items(&app, &app.test_user.username).await;
+    let session_data = sessions[0]["session_data"].as_s().unwrap();
+    assert!(session_data.starts_with("enc:v1:test:"));
+    assert!(!session_data.contains(&app.test_user.username));
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn in_memory_sessions_can_be_listed_and_logged_out() {
+    // Arrange
+    let app = spawn_app_with_session_backend(SessionBackend::Memory).await;
+    app.test_user.login(&app).await;
+    let other_browser = app
+        .login_from_another_browser(&app.test_user, "Other browser/1.0")
+        .await;
+
+    // Act
+    app.post_log_out_other_sessions().await;
+
+    // Assert
+    let response = get_admin_dashboard_from(&app, &other_browser).await;
+    assert_is_redirect_to(&response, "/login");
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(get_session_items(&app, &app.test_user.username)
+        .await
+        .is_empty());
+}
+
+#[tokio::test]
+async fn cookie_sessions_keep_the_user_logged_in() {
+    // Arrange
+    let app = spawn_app_with_session_backend(SessionBackend::Cookie).await;
+
+    // Act - Part 1 - Log in
+    app.test_user.login(&app).await;
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act - Part 2 - Log out
+    let response = app.post_logout().await;
+    assert_is_redirect_to(&response, "/login");
+
+    // Assert
+    assert!(get_session_items(&app, &app.test_user.username)
+        .await
+        .is_empty());
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn cookie_sessions_cannot_be_listed_or_logged_out_everywhere_else() {
+    // Arrange
+    let app = spawn_app_with_session_backend(SessionBackend::Cookie).await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Load the sessions page
+    let html_page = app.get_sessions_html().await;
+    assert!(html_page.contains("they can't be listed or logged out from another browser"));
+
+    // Act - Part 2 - Log out everywhere else
+    let response = app.post_log_out_other_sessions().await;
+    assert_is_redirect_to(&response, "/admin/sessions");
+
+    // Assert
+    let html_page = app.get_sessions_html().await;
+    assert!(!html_page.contains("You have been logged out everywhere else."));
+    assert!(html_page.contains("they can't be listed or logged out from another browser"));
+}
+
+#[tokio::test]
+async fn changing_the_password_with_cookie_sessions_warns_about_other_sessions() {
+    // Arrange
+    let app = spawn_app_with_session_backend(SessionBackend::Cookie).await;
+    app.test_user.login(&app).await;
+    let new_password = uuid::Uuid::new_v4().to_string();
+
+    // Act - Part 1 - Change password
+    let response = app
+        .post_change_password(&serde_json::json!({
+            "current_password": &app.test_user.password,
+            "new_password": &new_password,
+            "new_password_check": &new_password,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/password");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_change_password_html().await;
+
+    // Assert
+    assert!(html_page.contains("you are still logged in elsewhere"));
+}
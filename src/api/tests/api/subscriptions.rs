@@ -44,6 +44,23 @@ async fn subscribe_persists_the_new_subscriber() {
         .unwrap();
 
     assert_eq!(saved["PK"].as_s().unwrap(), &"james@test.com".to_string());
+    assert_eq!(saved["Name"].as_s().unwrap(), &"james".to_string());
+    assert_eq!(saved["SubscriptionStatus"].as_s().unwrap(), &"pending".to_string());
+    assert!(saved.contains_key("SubscribedAt"));
+}
+
+#[tokio::test]
+async fn subscribe_stores_a_confirmation_token_with_the_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=james&email=james@test.com";
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(app.count_tokens_for_email("james@test.com").await, 1);
 }
 
 #[tokio::test]
@@ -89,3 +106,88 @@ async fn subscribe_should_return_400_when_data_is_missing() {
         );
     }
 }
+
+#[tokio::test]
+async fn resending_a_confirmation_issues_a_new_token_for_a_pending_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_subscriptions("name=james&email=james@test.com".into())
+        .await;
+    app.end_confirmation_cooldown("james@test.com").await;
+
+    // Act
+    let response = app
+        .post_resend_confirmation("email=james@test.com".into())
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(app.count_tokens_for_email("james@test.com").await, 2);
+}
+
+#[tokio::test]
+async fn resending_a_confirmation_during_the_cooldown_returns_a_200_without_a_new_token() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_subscriptions("name=james&email=james@test.com".into())
+        .await;
+
+    // Act
+    for _ in 0..3 {
+        let response = app
+            .post_resend_confirmation("email=james@test.com".into())
+            .await;
+        assert_eq!(200, response.status().as_u16());
+    }
+
+    // Assert
+    assert_eq!(app.count_tokens_for_email("james@test.com").await, 1);
+}
+
+#[tokio::test]
+async fn resending_a_confirmation_for_an_unknown_address_returns_a_200() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_resend_confirmation("email=nobody@test.com".into())
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(app.count_tokens_for_email("nobody@test.com").await, 0);
+}
+
+#[tokio::test]
+async fn subscribing_twice_while_pending_reissues_the_confirmation_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=james&email=james@test.com";
+    app.post_subscriptions(body.into()).await;
+    app.end_confirmation_cooldown("james@test.com").await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(app.count_tokens_for_email("james@test.com").await, 2);
+}
+
+#[tokio::test]
+async fn subscribing_again_once_confirmed_returns_a_200_without_a_new_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=james&email=james@test.com";
+    app.post_subscriptions(body.into()).await;
+    let token = app.get_token_for_email("james@test.com").await;
+    app.confirm_subscription(token).await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(app.count_tokens_for_email("james@test.com").await, 1);
+}
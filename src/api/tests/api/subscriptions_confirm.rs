@@ -50,4 +50,44 @@ async fn clicking_on_the_confirmation_link_confirms_a_subscriber() {
 
     assert_eq!(saved["PK"].as_s().unwrap(), &"james@test.com".to_string());
     assert_eq!(saved["GSI1PK"].as_s().unwrap(), &"confirmed".to_string());
+    assert_eq!(saved["Name"].as_s().unwrap(), &"james".to_string());
+    assert_eq!(saved["SubscriptionStatus"].as_s().unwrap(), &"confirmed".to_string());
+    assert!(saved.contains_key("SubscribedAt"));
+    assert!(saved.contains_key("ConfirmedAt"));
+}
+
+#[tokio::test]
+async fn confirmations_with_an_expired_token_are_rejected_with_a_410() {
+    // Arrange
+    let app = spawn_app().await;
+    let expired_at = chrono::Utc::now().timestamp() - 60;
+
+    app.dynamo_db_client
+        .put_item()
+        .table_name(&app.table_name)
+        .item("PK", AttributeValue::S("expired-token".to_string()))
+        .item("Type", AttributeValue::S("SubscriberToken".to_string()))
+        .item("EmailAddress", AttributeValue::S("james@test.com".to_string()))
+        .item("ttl", AttributeValue::N(expired_at.to_string()))
+        .send()
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.confirm_subscription("expired-token".to_string()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+}
+
+#[tokio::test]
+async fn confirmations_with_an_unknown_token_are_rejected_with_a_401() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.confirm_subscription("unknown-token".to_string()).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
 }
@@ -0,0 +1,122 @@
+use crate::helpers::spawn_app;
+use aws_sdk_dynamodb::types::AttributeValue;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod::domain::subscriber_email::SubscriberEmail;
+use zero2prod::domain::unsubscribe_token::UnsubscribeToken;
+
+#[tokio::test]
+async fn unsubscribing_with_an_invalid_token_is_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let get_response = app.get_unsubscribe("not-a-valid-token").await;
+    let post_response = app.post_unsubscribe("not-a-valid-token").await;
+
+    // Assert
+    assert_eq!(get_response.status().as_u16(), 400);
+    assert_eq!(post_response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn unsubscribe_link_asks_for_confirmation() {
+    // Arrange
+    let app = spawn_app().await;
+    let email = SubscriberEmail::parse("james@test.com".to_string()).unwrap();
+    let token = UnsubscribeToken::generate(&email, &app.hmac_secret);
+
+    // Act
+    let response = app.get_unsubscribe(&token).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.text().await.unwrap().contains("james@test.com"));
+}
+
+#[tokio::test]
+async fn unsubscribing_removes_a_confirmed_subscriber_from_the_audience() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=james&email=james@test.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(body.into()).await;
+    let token = app.get_token_for_email("james@test.com").await;
+    app.confirm_subscription(token).await;
+
+    let email = SubscriberEmail::parse("james@test.com".to_string()).unwrap();
+    let unsubscribe_token = UnsubscribeToken::generate(&email, &app.hmac_secret);
+
+    // Act
+    let response = app.post_unsubscribe(&unsubscribe_token).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+
+    let saved = app
+        .dynamo_db_client
+        .get_item()
+        .table_name(app.table_name)
+        .key("PK", AttributeValue::S("james@test.com".to_string()))
+        .send()
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+
+    assert!(!saved.contains_key("GSI1PK"));
+    assert_eq!(
+        saved["SubscriptionStatus"].as_s().unwrap(),
+        &"unsubscribed".to_string()
+    );
+}
+
+#[tokio::test]
+async fn an_old_confirmation_link_does_not_confirm_an_unsubscribed_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=james&email=james@test.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    app.post_subscriptions(body.into()).await;
+    let token = app.get_token_for_email("james@test.com").await;
+    app.confirm_subscription(token.clone()).await;
+
+    let email = SubscriberEmail::parse("james@test.com".to_string()).unwrap();
+    let unsubscribe_token = UnsubscribeToken::generate(&email, &app.hmac_secret);
+    app.post_unsubscribe(&unsubscribe_token).await;
+
+    // Act
+    let response = app.confirm_subscription(token).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 410);
+
+    let saved = app
+        .dynamo_db_client
+        .get_item()
+        .table_name(app.table_name)
+        .key("PK", AttributeValue::S("james@test.com".to_string()))
+        .send()
+        .await
+        .unwrap()
+        .item
+        .unwrap();
+
+    assert!(!saved.contains_key("GSI1PK"));
+    assert_eq!(
+        saved["SubscriptionStatus"].as_s().unwrap(),
+        &"unsubscribed".to_string()
+    );
+}
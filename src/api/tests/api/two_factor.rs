@@ -0,0 +1,212 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use data_encoding::BASE32_NOPAD;
+use zero2prod::authentication::TotpSecret;
+
+/// Enable two-factor authentication for the logged in user, returning their secret and recovery
+/// codes.
+async fn enable_two_factor(app: &TestApp) -> (TotpSecret, Vec<String>) {
+    let html_page = app.get_two_factor_html().await;
+    let secret = html_page
+        .split("<code>")
+        .nth(1)
+        .and_then(|rest| rest.split("</code>").next())
+        .unwrap();
+    let secret = TotpSecret::from_bytes(BASE32_NOPAD.decode(secret.as_bytes()).unwrap());
+
+    let response = app
+        .post_enable_two_factor(&secret.generate_code(now()))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let html_page = response.text().await.unwrap();
+    let recovery_codes = html_page
+        .split("<li><code>")
+        .skip(1)
+        .map(|rest| rest.split("</code>").next().unwrap().to_string())
+        .collect();
+
+    (secret, recovery_codes)
+}
+
+/// A code for the next time step, as the code of the current one was used to enable two-factor
+/// authentication and can't be used again.
+fn next_code(secret: &TotpSecret) -> String {
+    secret.generate_code(now() + 30)
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[tokio::test]
+async fn two_factor_authentication_can_be_enabled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Show the QR code
+    let html_page = app.get_two_factor_html().await;
+    assert!(html_page.contains("<svg"));
+    assert!(html_page.contains("otpauth://totp/"));
+
+    // Act - Part 2 - Confirm with a code
+    let (_, recovery_codes) = enable_two_factor(&app).await;
+
+    // Assert
+    assert_eq!(recovery_codes.len(), 10);
+    let html_page = app.get_two_factor_html().await;
+    assert!(html_page.contains("You have 10 recovery codes left"));
+    let user = app.get_user_item(&app.test_user.username).await;
+    assert!(user.contains_key("TotpSecret"));
+    assert!(!user.contains_key("PendingTotpSecret"));
+}
+
+#[tokio::test]
+async fn the_qr_code_is_kept_until_enrollment_is_confirmed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let first_page = app.get_two_factor_html().await;
+    let second_page = app.get_two_factor_html().await;
+
+    // Assert
+    assert_eq!(first_page, second_page);
+}
+
+#[tokio::test]
+async fn a_wrong_code_does_not_enable_two_factor_authentication() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.get_two_factor_html().await;
+
+    // Act
+    let response = app.post_enable_two_factor("000000").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/two-factor");
+    let html_page = app.get_two_factor_html().await;
+    assert!(html_page.contains("The authentication code is incorrect."));
+    let user = app.get_user_item(&app.test_user.username).await;
+    assert!(!user.contains_key("TotpSecret"));
+}
+
+#[tokio::test]
+async fn users_with_two_factor_authentication_must_enter_a_code() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let (secret, _) = enable_two_factor(&app).await;
+    app.post_logout().await;
+
+    // Act - Part 1 - Log in with the password
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/login/2fa");
+
+    // Act - Part 2 - The password alone doesn't log in
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+
+    // Act - Part 3 - Enter the code
+    let response = app.post_two_factor_login(&next_code(&secret)).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+
+    // Assert
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn a_wrong_code_is_rejected_at_login() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    enable_two_factor(&app).await;
+    app.post_logout().await;
+    app.post_login(&serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password
+    }))
+    .await;
+
+    // Act
+    let response = app.post_two_factor_login("000000").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/2fa");
+    let html_page = app.get_two_factor_login_html().await;
+    assert!(html_page.contains("<p><i>The authentication code is incorrect</i></p>"));
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn codes_cannot_be_used_twice() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let (secret, _) = enable_two_factor(&app).await;
+    let code = next_code(&secret);
+    let login_body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password
+    });
+    app.post_logout().await;
+    app.post_login(&login_body).await;
+    app.post_two_factor_login(&code).await;
+    app.post_logout().await;
+    app.post_login(&login_body).await;
+
+    // Act
+    let response = app.post_two_factor_login(&code).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/2fa");
+}
+
+#[tokio::test]
+async fn recovery_codes_can_be_used_once() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let (_, recovery_codes) = enable_two_factor(&app).await;
+    let login_body = serde_json::json!({
+        "username": &app.test_user.username,
+        "password": &app.test_user.password
+    });
+
+    // Act - Part 1 - Log in with a recovery code
+    app.post_logout().await;
+    app.post_login(&login_body).await;
+    let response = app.post_two_factor_login(&recovery_codes[0]).await;
+    assert_is_redirect_to(&response, "/admin/dashboard");
+    let html_page = app.get_two_factor_html().await;
+    assert!(html_page.contains("You have 9 recovery codes left"));
+
+    // Act - Part 2 - Use the same recovery code again
+    app.post_logout().await;
+    app.post_login(&login_body).await;
+    let response = app.post_two_factor_login(&recovery_codes[0]).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login/2fa");
+}
+
+#[tokio::test]
+async fn the_code_can_only_be_entered_after_the_password() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_two_factor_login("000000").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
@@ -0,0 +1,250 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use aws_sdk_dynamodb::types::AttributeValue;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn viewers_cannot_publish_newsletters() {
+    // Arrange
+    let app = spawn_app().await;
+    let viewer = app.create_user("viewer").await;
+    viewer.login(&app).await;
+
+    // Act
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(app
+        .get_newsletter_issue_id("Newsletter title")
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn viewers_can_read_the_newsletter_history() {
+    // Arrange
+    let app = spawn_app().await;
+    let viewer = app.create_user("viewer").await;
+    viewer.login(&app).await;
+
+    // Act
+    let response = app.get_newsletter_history().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn editors_can_publish_newsletters() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    editor.login(&app).await;
+
+    // Act
+    let response = app
+        .post_publish_newsletter(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": Uuid::new_v4().to_string(),
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    assert!(app
+        .get_newsletter_issue_id("Newsletter title")
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn editors_cannot_manage_users() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    editor.login(&app).await;
+
+    // Act
+    let response = app.get_manage_users().await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn owners_can_add_users_who_can_log_in() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let username = Uuid::new_v4().to_string();
+    let password = Uuid::new_v4().to_string();
+
+    // Act - Part 1 - Add the user
+    let response = app
+        .post_add_user(&serde_json::json!({
+            "username": &username,
+            "email": format!("{}@test.com", username),
+            "password": &password,
+            "role": "editor",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/users");
+
+    // Act - Part 2 - Follow the redirect
+    let html_page = app.get_manage_users_html().await;
+    assert!(html_page.contains(&format!("{} has been added as an editor", username)));
+    assert!(html_page.contains(&format!("{} (editor) {}@test.com", username, username)));
+
+    // Act - Part 3 - Log in as the new user
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &username,
+            "password": &password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn existing_users_are_not_overwritten() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    app.test_user.login(&app).await;
+
+    // Act
+    app.post_add_user(&serde_json::json!({
+        "username": &editor.username,
+        "email": "someone-else@test.com",
+        "password": Uuid::new_v4().to_string(),
+        "role": "owner",
+    }))
+    .await;
+
+    // Assert
+    let html_page = app.get_manage_users_html().await;
+    assert!(html_page.contains("already exists"));
+    let user = app.get_user_item(&editor.username).await;
+    assert_eq!(user["Role"].as_s().unwrap(), "editor");
+}
+
+#[tokio::test]
+async fn users_with_a_short_password_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    app.post_add_user(&serde_json::json!({
+        "username": "short-password",
+        "email": "short-password@test.com",
+        "password": "too-short",
+        "role": "viewer",
+    }))
+    .await;
+
+    // Assert
+    let html_page = app.get_manage_users_html().await;
+    assert!(html_page.contains("The password needs between 12 and 128 characters"));
+    assert!(!html_page.contains("short-password (viewer)"));
+}
+
+#[tokio::test]
+async fn owners_can_set_the_email_address_of_a_user() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_set_user_email(&serde_json::json!({
+            "username": &editor.username,
+            "email": "new-address@test.com",
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/users");
+
+    // Assert
+    let html_page = app.get_manage_users_html().await;
+    assert!(html_page.contains(&format!(
+        "The email address of {} has been set to new-address@test.com",
+        editor.username
+    )));
+    let user = app.get_user_item(&editor.username).await;
+    assert_eq!(user["EmailAddress"].as_s().unwrap(), "new-address@test.com");
+}
+
+#[tokio::test]
+async fn disabled_users_cannot_log_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    app.test_user.login(&app).await;
+
+    // Act - Part 1 - Disable the user
+    let response = app.post_disable_user(&editor.username).await;
+    assert_is_redirect_to(&response, "/admin/users");
+
+    // Act - Part 2 - Try to log in as the disabled user
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &editor.username,
+            "password": &editor.password
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn disabled_users_are_logged_out() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = app.create_user("editor").await;
+    editor.login(&app).await;
+
+    // Act - Disable the user while they are logged in
+    app.dynamo_db_client
+        .update_item()
+        .table_name(&app.auth_table_name)
+        .key("PK", AttributeValue::S(editor.username.clone()))
+        .update_expression("SET #disabled = :disabled")
+        .expression_attribute_names("#disabled", "Disabled")
+        .expression_attribute_values(":disabled", AttributeValue::Bool(true))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    let response = app.get_admin_dashboard().await;
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn owners_cannot_disable_themselves() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    app.post_disable_user(&app.test_user.username).await;
+
+    // Assert
+    let html_page = app.get_manage_users_html().await;
+    assert!(html_page.contains("You can't disable yourself"));
+    let response = app.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
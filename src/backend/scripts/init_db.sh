@@ -39,6 +39,20 @@ aws dynamodb create-table \
                         \"ReadCapacityUnits\": 5,
                         \"WriteCapacityUnits\": 5
                     }
+                },
+                {
+                    \"IndexName\": \"GSI1All\",
+                    \"KeySchema\": [
+                        {\"AttributeName\":\"GSI1PK\",\"KeyType\":\"HASH\"},
+                        {\"AttributeName\":\"GSI1SK\",\"KeyType\":\"RANGE\"}
+                    ],
+                    \"Projection\": {
+                        \"ProjectionType\":\"ALL\"
+                    },
+                    \"ProvisionedThroughput\": {
+                        \"ReadCapacityUnits\": 5,
+                        \"WriteCapacityUnits\": 5
+                    }
                 }
             ]" > create-result.json
 
@@ -0,0 +1,182 @@
+use crate::domain::newsletter_delivery_log::NewsletterDeliveryLog;
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use crate::domain::subscriber_email::SubscriberEmail;
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::error::SdkError;
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
+use aws_sdk_dynamodb::types::{AttributeValue, Put, TransactWriteItem, Update};
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+/// Sets the type and GSI1 keys of the stats item of an issue, so the api can list the stats of
+/// every issue with a single query.
+const STATS_ITEM_ATTRIBUTES: &str =
+    "#type = :stats_type, IssueId = :issue_id, GSI1PK = :stats_type, GSI1SK = :issue_id";
+
+/// Stores one `NewsletterDelivery` item per (issue, subscriber) pair in the newsletter table, and
+/// counts them in the `RecipientCount` of a `NewsletterIssueStats` item for the newsletter
+/// history. The counters aren't kept on the issue item, as the send newsletter pipe would send the
+/// issue again on every update of it.
+#[derive(Debug, Clone)]
+pub struct DynamoDbNewsletterDeliveryLog {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbNewsletterDeliveryLog {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl NewsletterDeliveryLog for DynamoDbNewsletterDeliveryLog {
+    #[tracing::instrument(skip(self, recipient))]
+    async fn has_been_delivered(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<bool, anyhow::Error> {
+        let get_res = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(delivery_key(issue_id, recipient)))
+            .consistent_read(true)
+            .send()
+            .await
+            .context(format!(
+                "Failure reading delivery log from DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(get_res.item.is_some())
+    }
+
+    /// The delivery and the recipient count of the issue are written in one transaction, so the
+    /// count stays right when a redelivered chunk records a recipient a second time.
+    #[tracing::instrument(skip(self, recipient))]
+    async fn record_delivery(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<(), anyhow::Error> {
+        let put_delivery = Put::builder()
+            .table_name(&self.table_name)
+            .item("PK", AttributeValue::S(delivery_key(issue_id, recipient)))
+            .item("Type", AttributeValue::S("NewsletterDelivery".to_string()))
+            .item("IssueId", AttributeValue::S(issue_id.to_string()))
+            .item("EmailAddress", AttributeValue::S(recipient.to_string()))
+            .item(
+                "DeliveredAt",
+                AttributeValue::N(chrono::Utc::now().timestamp().to_string()),
+            )
+            .item(
+                "GSI1PK",
+                AttributeValue::S(format!("NewsletterDelivery#{}", issue_id)),
+            )
+            .item("GSI1SK", AttributeValue::S(recipient.to_string()))
+            .condition_expression("attribute_not_exists(PK)")
+            .build()
+            .context("Failure building the delivery item")?;
+
+        let count_recipient = Update::builder()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(stats_key(issue_id)))
+            .update_expression(format!(
+                "SET {} ADD RecipientCount :one",
+                STATS_ITEM_ATTRIBUTES
+            ))
+            .expression_attribute_names("#type", "Type")
+            .set_expression_attribute_values(Some(stats_item_values(issue_id)))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .build()
+            .context("Failure building the recipient count update")?;
+
+        let transact_res = self
+            .client
+            .transact_write_items()
+            .transact_items(TransactWriteItem::builder().put(put_delivery).build())
+            .transact_items(TransactWriteItem::builder().update(count_recipient).build())
+            .send()
+            .await;
+
+        match transact_res {
+            Ok(_) => Ok(()),
+            Err(e) if is_already_delivered_failure(&e) => {
+                tracing::info!("The delivery was already recorded");
+                Ok(())
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure inserting record to DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn record_audience_size(
+        &self,
+        issue_id: &NewsletterIssueId,
+        audience_size: usize,
+    ) -> Result<(), anyhow::Error> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("PK", AttributeValue::S(stats_key(issue_id)))
+            .update_expression(format!(
+                "SET {}, AudienceSize = :audience_size",
+                STATS_ITEM_ATTRIBUTES
+            ))
+            .expression_attribute_names("#type", "Type")
+            .set_expression_attribute_values(Some(stats_item_values(issue_id)))
+            .expression_attribute_values(
+                ":audience_size",
+                AttributeValue::N(audience_size.to_string()),
+            )
+            .send()
+            .await
+            .context(format!(
+                "Failure recording the audience size in DynamoDB. Using table {}",
+                &self.table_name
+            ))?;
+
+        Ok(())
+    }
+}
+
+fn delivery_key(issue_id: &NewsletterIssueId, recipient: &SubscriberEmail) -> String {
+    format!("NewsletterDelivery#{}#{}", issue_id, recipient)
+}
+
+/// The counters of the issue, read by the api for the newsletter history.
+fn stats_key(issue_id: &NewsletterIssueId) -> String {
+    format!("NewsletterIssueStats#{}", issue_id)
+}
+
+fn stats_item_values(issue_id: &NewsletterIssueId) -> HashMap<String, AttributeValue> {
+    HashMap::from([
+        (
+            ":stats_type".to_string(),
+            AttributeValue::S("NewsletterIssueStats".to_string()),
+        ),
+        (
+            ":issue_id".to_string(),
+            AttributeValue::S(issue_id.to_string()),
+        ),
+    ])
+}
+
+/// The delivery is the first item of the transaction, so its cancellation reason is the first.
+fn is_already_delivered_failure(e: &SdkError<TransactWriteItemsError>) -> bool {
+    match e.as_service_error() {
+        Some(TransactWriteItemsError::TransactionCanceledException(e)) => {
+            e.cancellation_reasons()
+                .first()
+                .and_then(|reason| reason.code())
+                == Some("ConditionalCheckFailed")
+        }
+        _ => false,
+    }
+}
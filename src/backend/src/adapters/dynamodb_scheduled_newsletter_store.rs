@@ -0,0 +1,148 @@
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use crate::domain::scheduled_newsletter_store::{ScheduledNewsletter, ScheduledNewsletterStore};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+
+/// Scheduled issues are stored by the api with `GSI1PK` set to their type and `GSI1SK` set to
+/// their zero padded send time, so due issues are a range query on GSI1All, which has the keys of
+/// GSI1 and every attribute of the issue.
+const SCHEDULED_ISSUE_TYPE: &str = "ScheduledNewsletterIssue";
+
+/// Released issues join the published issues the api lists, ordered by publish time.
+const PUBLISHED_ISSUE_INDEX: &str = "PublishedNewsletterIssue";
+
+#[derive(Debug, Clone)]
+pub struct DynamoDbScheduledNewsletterStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoDbScheduledNewsletterStore {
+    pub fn new(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl ScheduledNewsletterStore for DynamoDbScheduledNewsletterStore {
+    #[tracing::instrument(skip(self))]
+    async fn get_due_newsletters(
+        &self,
+        now: i64,
+    ) -> Result<Vec<ScheduledNewsletter>, anyhow::Error> {
+        let mut due = Vec::new();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let query_res = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name("GSI1All")
+                .key_condition_expression("GSI1PK = :gsi1pk AND GSI1SK <= :now")
+                .expression_attribute_values(
+                    ":gsi1pk",
+                    AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+                )
+                .expression_attribute_values(":now", AttributeValue::S(format!("{:010}", now)))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .context(format!(
+                    "Failure querying scheduled newsletters from DynamoDB. Using table {}",
+                    &self.table_name
+                ))?;
+
+            for item in query_res.items() {
+                due.push(parse_scheduled_newsletter(item)?);
+            }
+
+            exclusive_start_key = query_res.last_evaluated_key;
+
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(due)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn release_newsletter(
+        &self,
+        newsletter: &ScheduledNewsletter,
+    ) -> Result<bool, anyhow::Error> {
+        let now = chrono::Utc::now().timestamp();
+
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key(
+                "PK",
+                AttributeValue::S(format!("NewsletterIssue#{}", newsletter.issue_id)),
+            )
+            .condition_expression("#type = :scheduled")
+            .expression_attribute_names("#type", "Type")
+            .expression_attribute_values(
+                ":scheduled",
+                AttributeValue::S(SCHEDULED_ISSUE_TYPE.to_string()),
+            )
+            .expression_attribute_values(":issue", AttributeValue::S("NewsletterIssue".to_string()))
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .expression_attribute_values(
+                ":gsi1pk",
+                AttributeValue::S(PUBLISHED_ISSUE_INDEX.to_string()),
+            )
+            .expression_attribute_values(":gsi1sk", AttributeValue::S(format!("{:010}", now)));
+
+        // The send continues the trace of this release, not the one that scheduled the issue
+        update = match telemetry::get_trace_and_span_id() {
+            None => update.update_expression(
+                "SET #type = :issue, PublishedAt = :now, GSI1PK = :gsi1pk, GSI1SK = :gsi1sk",
+            ),
+            Some((trace_id, span_id)) => update
+                .update_expression(
+                    "SET #type = :issue, PublishedAt = :now, GSI1PK = :gsi1pk, GSI1SK = :gsi1sk, \
+                     TraceParent = :trace_parent, ParentSpan = :parent_span",
+                )
+                .expression_attribute_values(":trace_parent", AttributeValue::S(trace_id))
+                .expression_attribute_values(":parent_span", AttributeValue::S(span_id)),
+        };
+
+        match update.send().await {
+            Ok(_) => Ok(true),
+            Err(e)
+                if e.as_service_error()
+                    .map(|e| e.is_conditional_check_failed_exception())
+                    .unwrap_or(false) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e).context(format!(
+                "Failure releasing scheduled newsletter in DynamoDB. Using table {}",
+                &self.table_name
+            )),
+        }
+    }
+}
+
+fn parse_scheduled_newsletter(
+    item: &HashMap<String, AttributeValue>,
+) -> Result<ScheduledNewsletter, anyhow::Error> {
+    let issue_id = item
+        .get("IssueId")
+        .and_then(|issue_id| issue_id.as_s().ok())
+        .ok_or_else(|| anyhow::anyhow!("Scheduled newsletter item has no IssueId"))?;
+    let issue_id = NewsletterIssueId::parse(issue_id.clone()).map_err(anyhow::Error::msg)?;
+    let send_at = item
+        .get("SendAt")
+        .and_then(|send_at| send_at.as_n().ok())
+        .and_then(|send_at| send_at.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Scheduled newsletter item has no SendAt"))?;
+
+    Ok(ScheduledNewsletter { issue_id, send_at })
+}
@@ -1,10 +1,13 @@
 use crate::domain::confirmed_subscriber::ConfirmedSubscriber;
 use crate::domain::subscriber_email::SubscriberEmail;
-use crate::domain::subscriber_repository::{DatabaseError, SubscriberRepository};
-use anyhow::Result;
+use crate::domain::subscriber_repository::{
+    ConfirmedSubscriberPage, DatabaseError, SubscriberRepository,
+};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct DynamoDbSubscriberRepository {
@@ -20,33 +23,69 @@ impl DynamoDbSubscriberRepository {
 
 #[async_trait]
 impl SubscriberRepository for DynamoDbSubscriberRepository {
-    #[tracing::instrument(skip())]
-    async fn get_confirmed_subscribers(
+    #[tracing::instrument(skip(self))]
+    async fn get_confirmed_subscribers_page(
         &self,
-    ) -> Result<Vec<Result<ConfirmedSubscriber, anyhow::Error>>, anyhow::Error> {
-        let query_res = self
+        cursor: Option<String>,
+        page_size: usize,
+    ) -> Result<ConfirmedSubscriberPage, anyhow::Error> {
+        let mut query = self
             .client
             .query()
             .table_name(&self.table_name)
-            .index_name("GSI1".to_string())
+            // GSI1 projects its keys only, GSI1All has the name of the subscriber as well
+            .index_name("GSI1All".to_string())
             .key_condition_expression("#gsi1pk = :gsi1pk")
             .expression_attribute_names("#gsi1pk", "GSI1PK")
             .expression_attribute_values(":gsi1pk", AttributeValue::S("confirmed".to_string()))
-            .send()
-            .await?;
-
-        if let Some(items) = query_res.items {
-            let subscribers = items
-                .iter()
-                .map(|v| {
-                    Ok(ConfirmedSubscriber {
-                        email: SubscriberEmail::parse(v["PK"].as_s().unwrap().clone()).unwrap(),
-                    })
-                })
-                .collect();
-            Ok(subscribers)
-        } else {
-            Err(DatabaseError::DatabaseReadError("Error reading from database".to_string()).into())
+            .limit(i32::try_from(page_size).unwrap_or(i32::MAX));
+
+        // Confirmed subscribers are keyed by their email address on both the table and the index, so
+        // the cursor is enough to rebuild the last evaluated key.
+        if let Some(cursor) = cursor {
+            query = query
+                .exclusive_start_key("PK", AttributeValue::S(cursor.clone()))
+                .exclusive_start_key("GSI1PK", AttributeValue::S("confirmed".to_string()))
+                .exclusive_start_key("GSI1SK", AttributeValue::S(cursor));
         }
+
+        let query_res = query.send().await.context(format!(
+            "Failure querying confirmed subscribers from DynamoDB. Using table {}",
+            &self.table_name
+        ))?;
+
+        let items = query_res.items.ok_or_else(|| {
+            DatabaseError::DatabaseReadError("Error reading from database".to_string())
+        })?;
+
+        let subscribers = items.iter().map(parse_confirmed_subscriber).collect();
+
+        let next_cursor = query_res
+            .last_evaluated_key
+            .and_then(|key| key.get("PK").and_then(|pk| pk.as_s().ok()).cloned());
+
+        Ok(ConfirmedSubscriberPage {
+            subscribers,
+            next_cursor,
+        })
     }
 }
+
+fn parse_confirmed_subscriber(
+    item: &HashMap<String, AttributeValue>,
+) -> Result<ConfirmedSubscriber, anyhow::Error> {
+    let email = item
+        .get("PK")
+        .and_then(|pk| pk.as_s().ok())
+        .ok_or_else(|| anyhow::anyhow!("Confirmed subscriber item has no PK"))?;
+
+    let name = item
+        .get("Name")
+        .and_then(|name| name.as_s().ok())
+        .cloned();
+
+    Ok(ConfirmedSubscriber {
+        email: SubscriberEmail::parse(email.clone()).map_err(|e| anyhow::anyhow!(e))?,
+        name,
+    })
+}
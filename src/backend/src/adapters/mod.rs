@@ -1,3 +1,6 @@
+pub mod dynamodb_newsletter_delivery_log;
+pub mod dynamodb_scheduled_newsletter_store;
 pub mod dynamodb_subscriber_repository;
 pub mod postmark_email_client;
 pub mod s3_newsletter_service;
+pub mod sqs_newsletter_work_queue;
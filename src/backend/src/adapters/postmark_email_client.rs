@@ -29,6 +29,32 @@ impl PostmarkEmailClient {
     }
 }
 
+impl PostmarkEmailClient {
+    async fn send(&self, request_body: &SendEmailRequest<'_>) -> Result<(), anyhow::Error> {
+        let url = format!("{}/email", self.base_url);
+        self.http_client
+            .post(&url)
+            .header(
+                "X-Postmark-Server-Token",
+                self.authorization_token.expose_secret(),
+            )
+            .json(request_body)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to send email: {:?}", e);
+                e
+            })?
+            .error_for_status()
+            .map_err(|e| {
+                tracing::error!("Failed to send email: {:?}", e);
+                e
+            })?;
+
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl EmailClient for PostmarkEmailClient {
     #[tracing::instrument(
@@ -44,34 +70,52 @@ impl EmailClient for PostmarkEmailClient {
         html_content: &str,
         text_content: &str,
     ) -> Result<(), anyhow::Error> {
-        let url = format!("{}/email", self.base_url);
         let request_body = SendEmailRequest {
             from: self.sender.as_ref(),
             to: recipient.as_ref(),
             subject,
             html_body: html_content,
             text_body: text_content,
+            headers: vec![],
         };
-        self.http_client
-            .post(&url)
-            .header(
-                "X-Postmark-Server-Token",
-                self.authorization_token.expose_secret(),
-            )
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|e| {
-                tracing::error!("Failed to send email: {:?}", e);
-                e
-            })?
-            .error_for_status()
-            .map_err(|e| {
-                tracing::error!("Failed to send email: {:?}", e);
-                e
-            })?;
 
-        Ok(())
+        self.send(&request_body).await
+    }
+
+    #[tracing::instrument(
+    name = "send_newsletter",
+    skip(recipient, subject, html_content, text_content, unsubscribe_url),
+    fields(
+    subscriber_email = %recipient.as_ref(),)
+    )]
+    async fn send_newsletter_to(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        unsubscribe_url: &str,
+    ) -> Result<(), anyhow::Error> {
+        let list_unsubscribe = format!("<{}>", unsubscribe_url);
+        let request_body = SendEmailRequest {
+            from: self.sender.as_ref(),
+            to: recipient.as_ref(),
+            subject,
+            html_body: html_content,
+            text_body: text_content,
+            headers: vec![
+                EmailHeader {
+                    name: "List-Unsubscribe",
+                    value: &list_unsubscribe,
+                },
+                EmailHeader {
+                    name: "List-Unsubscribe-Post",
+                    value: "List-Unsubscribe=One-Click",
+                },
+            ],
+        };
+
+        self.send(&request_body).await
     }
 }
 
@@ -83,6 +127,15 @@ struct SendEmailRequest<'a> {
     subject: &'a str,
     html_body: &'a str,
     text_body: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<EmailHeader<'a>>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct EmailHeader<'a> {
+    name: &'a str,
+    value: &'a str,
 }
 
 #[cfg(test)]
@@ -186,6 +239,66 @@ mod tests {
         assert_err!(outcome);
     }
 
+    #[tokio::test]
+    async fn send_newsletter_adds_list_unsubscribe_headers() {
+        let mock_server = MockServer::start().await;
+        let sender = SubscriberEmail::parse(SafeEmail().fake()).unwrap();
+        let email_client = PostmarkEmailClient::new(
+            mock_server.uri(),
+            sender,
+            Secret::new(String::from("")),
+            Duration::from_millis(10000),
+        );
+
+        Mock::given(path("/email"))
+            .and(SendEmailBodyMatcher)
+            .and(ListUnsubscribeHeadersMatcher)
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let subscriber_email = SubscriberEmail::parse(SafeEmail().fake()).unwrap();
+        let subject: String = Sentence(1..2).fake();
+        let content: String = Paragraph(1..10).fake();
+
+        let outcome = email_client
+            .send_newsletter_to(
+                &subscriber_email,
+                &subject,
+                &content,
+                &content,
+                "https://my.newsletter/subscriptions/unsubscribe?token=abc",
+            )
+            .await;
+
+        assert_ok!(outcome);
+    }
+
+    struct ListUnsubscribeHeadersMatcher;
+
+    impl wiremock::Match for ListUnsubscribeHeadersMatcher {
+        fn matches(&self, request: &Request) -> bool {
+            let result: Result<serde_json::Value, _> = serde_json::from_slice(&request.body);
+
+            if let Ok(body) = result {
+                body["Headers"]
+                    == serde_json::json!([
+                        {
+                            "Name": "List-Unsubscribe",
+                            "Value": "<https://my.newsletter/subscriptions/unsubscribe?token=abc>"
+                        },
+                        {
+                            "Name": "List-Unsubscribe-Post",
+                            "Value": "List-Unsubscribe=One-Click"
+                        }
+                    ])
+            } else {
+                false
+            }
+        }
+    }
+
     struct SendEmailBodyMatcher;
 
     impl wiremock::Match for SendEmailBodyMatcher {
@@ -0,0 +1,37 @@
+use crate::domain::newsletter_work_queue::{NewsletterWorkItem, NewsletterWorkQueue};
+use anyhow::Context;
+use async_trait::async_trait;
+use aws_sdk_sqs::Client;
+
+#[derive(Debug, Clone)]
+pub struct SqsNewsletterWorkQueue {
+    client: Client,
+    queue_url: String,
+}
+
+impl SqsNewsletterWorkQueue {
+    pub fn new(client: Client, queue_url: String) -> Self {
+        Self { client, queue_url }
+    }
+}
+
+#[async_trait]
+impl NewsletterWorkQueue for SqsNewsletterWorkQueue {
+    #[tracing::instrument(skip(self, work_item), fields(recipients = work_item.recipients.len()))]
+    async fn enqueue(&self, work_item: &NewsletterWorkItem) -> Result<(), anyhow::Error> {
+        let body = serde_json::to_string(work_item).context("Failure serializing work item")?;
+
+        self.client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(body)
+            .send()
+            .await
+            .context(format!(
+                "Failure sending work item to SQS. Using queue {}",
+                &self.queue_url
+            ))?;
+
+        Ok(())
+    }
+}
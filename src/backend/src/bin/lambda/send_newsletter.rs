@@ -11,13 +11,17 @@ use lambda_extension::Extension;
 use backend::adapters::postmark_email_client::PostmarkEmailClient;
 use backend::configuration::{get_configuration, DatabaseSettings};
 use backend::domain::subscriber_email::SubscriberEmail;
+use backend::domain::unsubscribe_link::UnsubscribeLinks;
 use telemetry::{init_tracer, get_subscriber, init_subscriber, TraceFlushExtension};
 
 use lambda_runtime::{run, service_fn, Error, LambdaEvent};
 use tokio::sync::mpsc::unbounded_channel;
 
+use backend::adapters::dynamodb_newsletter_delivery_log::DynamoDbNewsletterDeliveryLog;
 use backend::adapters::dynamodb_subscriber_repository::DynamoDbSubscriberRepository;
 use backend::adapters::s3_newsletter_service::S3NewsletterMetadataStorage;
+use backend::adapters::sqs_newsletter_work_queue::SqsNewsletterWorkQueue;
+use backend::fan_out_newsletter_handler::FanOutNewsletterEventHandler;
 
 use backend::send_newsletter_handler::{SendNewsletterEventHandler};
 
@@ -63,6 +67,10 @@ async fn main() -> Result<(), Error> {
 
     let dynamo_client = aws_sdk_dynamodb::Client::from_conf(dynamo_config);
     let subscriber_repo = DynamoDbSubscriberRepository::new(
+        dynamo_client.clone(),
+        configuration.database.database_name.clone(),
+    );
+    let delivery_log = DynamoDbNewsletterDeliveryLog::new(
         dynamo_client,
         configuration.database.database_name.clone(),
     );
@@ -88,7 +96,46 @@ async fn main() -> Result<(), Error> {
         .register()
         .await?;
 
-    let handler = Arc::new(SendNewsletterEventHandler::new(request_done_sender));
+    // In fan-out mode this function only enqueues chunks of the audience, they are delivered by
+    // the send_newsletter_chunk function.
+    if let Some(fan_out) = configuration.fan_out.clone() {
+        let sqs_config = configure_sqs(&hyper_client).await;
+        let work_queue = SqsNewsletterWorkQueue::new(
+            aws_sdk_sqs::Client::from_conf(sqs_config),
+            fan_out.queue_url,
+        );
+
+        let handler = Arc::new(FanOutNewsletterEventHandler::new(
+            request_done_sender,
+            fan_out.chunk_size,
+        ));
+
+        tokio::try_join!(
+            run(service_fn(|event: LambdaEvent<SqsEvent>| {
+                let handler = handler.clone();
+                let repo = subscriber_repo.clone();
+                let work_queue = work_queue.clone();
+                let delivery_log = delivery_log.clone();
+
+                async move {
+                    handler
+                        .invoke(event, &repo, &work_queue, &delivery_log)
+                        .await
+                }
+            })),
+            extension.run(),
+        )?;
+
+        return Ok(());
+    }
+
+    let handler = Arc::new(SendNewsletterEventHandler::new(
+        request_done_sender,
+        UnsubscribeLinks::new(
+            configuration.base_url.clone(),
+            configuration.hmac_secret.clone(),
+        ),
+    ));
 
     //https://github.com/awslabs/aws-lambda-rust-runtime/blob/main/examples/extension-internal-flush/src/main.rs
     tokio::try_join!(
@@ -97,8 +144,13 @@ async fn main() -> Result<(), Error> {
             let email_adapter = email_adapter.clone();
             let repo = subscriber_repo.clone();
             let newsletter_store = newsletter_service.clone();
-            
-            async move { handler.invoke(event, &email_adapter, &repo, &newsletter_store).await }
+            let delivery_log = delivery_log.clone();
+
+            async move {
+                handler
+                    .invoke(event, &email_adapter, &repo, &newsletter_store, &delivery_log)
+                    .await
+            }
         })),
         extension.run(),
     )?;
@@ -168,3 +220,26 @@ async fn configure_dynamo(
         false => conf_builder.build(),
     }
 }
+
+async fn configure_sqs(hyper_client: &SharedHttpClient) -> aws_sdk_sqs::Config {
+    let region = RegionProviderChain::default_provider()
+        .or_else(Region::new("us-east-1"))
+        .region()
+        .await
+        .unwrap();
+
+    let credentials = DefaultCredentialsChain::builder()
+        .region(region.clone())
+        .build()
+        .await
+        .provide_credentials()
+        .await
+        .unwrap();
+
+    aws_sdk_sqs::Config::builder()
+        .behavior_version(BehaviorVersion::v2023_11_09())
+        .credentials_provider(credentials.clone())
+        .http_client(hyper_client.clone())
+        .region(region.clone())
+        .build()
+}
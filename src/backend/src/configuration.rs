@@ -16,6 +16,22 @@ pub struct Settings {
     pub telemetry: TelemetrySettings,
     pub email_settings: EmailClientSettings,
     pub base_url: String,
+    pub hmac_secret: Secret<String>,
+    #[serde(default)]
+    pub fan_out: Option<FanOutSettings>,
+}
+
+/// When present, `send_newsletter` splits the audience into chunks of `chunk_size` recipients
+/// and enqueues them on `queue_url` for the `send_newsletter_chunk` handler.
+#[derive(Deserialize, Clone)]
+pub struct FanOutSettings {
+    pub queue_url: String,
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+}
+
+fn default_chunk_size() -> usize {
+    50
 }
 
 #[derive(Deserialize, Clone)]
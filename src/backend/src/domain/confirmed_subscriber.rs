@@ -2,4 +2,6 @@ use crate::domain::subscriber_email::SubscriberEmail;
 
 pub struct ConfirmedSubscriber {
     pub email: SubscriberEmail,
+    /// Subscribers who signed up before names were stored don't have one.
+    pub name: Option<String>,
 }
@@ -10,4 +10,15 @@ pub trait EmailClient {
         html_content: &str,
         text_content: &str,
     ) -> Result<(), anyhow::Error>;
+
+    /// Send a newsletter issue, advertising `unsubscribe_url` through the `List-Unsubscribe`
+    /// headers so mail clients can offer a one-click unsubscribe.
+    async fn send_newsletter_to(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        unsubscribe_url: &str,
+    ) -> Result<(), anyhow::Error>;
 }
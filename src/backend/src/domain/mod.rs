@@ -1,6 +1,12 @@
 pub mod confirmed_subscriber;
 pub mod email_client;
+pub mod newsletter_delivery_log;
+pub mod newsletter_issue_id;
 pub mod newsletter_metadata;
 pub mod newsletter_store;
+pub mod newsletter_template;
+pub mod newsletter_work_queue;
+pub mod scheduled_newsletter_store;
 pub mod subscriber_email;
 pub mod subscriber_repository;
+pub mod unsubscribe_link;
@@ -0,0 +1,28 @@
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use crate::domain::subscriber_email::SubscriberEmail;
+use async_trait::async_trait;
+
+/// Records which subscribers have already received a given newsletter issue, so that a
+/// redelivered send request only mails the recipients that are still pending.
+#[async_trait]
+pub trait NewsletterDeliveryLog {
+    async fn has_been_delivered(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<bool, anyhow::Error>;
+
+    async fn record_delivery(
+        &self,
+        issue_id: &NewsletterIssueId,
+        recipient: &SubscriberEmail,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Record how many subscribers the issue is sent to, so the newsletter history can tell when
+    /// every one of them has received it.
+    async fn record_audience_size(
+        &self,
+        issue_id: &NewsletterIssueId,
+        audience_size: usize,
+    ) -> Result<(), anyhow::Error>;
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use ulid_rs::Ulid;
+
+/// Length of a ULID in its Crockford base32 form.
+const ULID_LENGTH: usize = 26;
+
+/// The `{ulid}-{slug}` id the api stores a newsletter issue under. Deliveries are recorded
+/// against it, so two issues with similar titles never share a delivery log.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct NewsletterIssueId(String);
+
+impl NewsletterIssueId {
+    pub fn parse(s: String) -> Result<NewsletterIssueId, String> {
+        let is_valid = match (s.get(..ULID_LENGTH), s.get(ULID_LENGTH..)) {
+            (Some(ulid), Some(slug)) => {
+                Ulid::unmarshal(ulid).is_ok() && slug.len() > 1 && slug.starts_with('-')
+            }
+            _ => false,
+        };
+
+        match is_valid {
+            true => Ok(Self(s)),
+            false => Err(format!("{} is not a valid newsletter issue id", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for NewsletterIssueId {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl From<NewsletterIssueId> for String {
+    fn from(value: NewsletterIssueId) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for NewsletterIssueId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for NewsletterIssueId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NewsletterIssueId;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn ids_stored_by_the_api_are_accepted() {
+        assert_ok!(NewsletterIssueId::parse(
+            "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1".to_string()
+        ));
+    }
+
+    #[test]
+    fn issue_titles_are_rejected() {
+        assert_err!(NewsletterIssueId::parse("Issue #1".to_string()));
+        assert_err!(NewsletterIssueId::parse(
+            "01HQ3Z5K8M2N4P6R8T0V2X4Z6B".to_string()
+        ));
+    }
+
+    #[test]
+    fn messages_with_an_invalid_id_fail_to_deserialize() {
+        assert_err!(serde_json::from_str::<NewsletterIssueId>(r#""Issue #1""#));
+    }
+}
@@ -0,0 +1,149 @@
+/// Name used for `{{ name }}` when a subscriber signed up before names were stored.
+const DEFAULT_NAME: &str = "subscriber";
+
+/// Values substituted into the `{{ name }}`, `{{ unsubscribe_url }}` and `{{ issue_title }}`
+/// placeholders of a newsletter issue.
+pub struct TemplateValues<'a> {
+    pub name: Option<&'a str>,
+    pub unsubscribe_url: &'a str,
+    pub issue_title: &'a str,
+}
+
+impl TemplateValues<'_> {
+    fn get(&self, placeholder: &str) -> Option<&str> {
+        match placeholder {
+            "name" => Some(self.name.unwrap_or(DEFAULT_NAME)),
+            "unsubscribe_url" => Some(self.unsubscribe_url),
+            "issue_title" => Some(self.issue_title),
+            _ => None,
+        }
+    }
+}
+
+/// Render the HTML content of an issue, escaping every substituted value.
+pub fn render_html(template: &str, values: &TemplateValues) -> String {
+    render(template, |placeholder| {
+        values.get(placeholder).map(html_escape)
+    })
+}
+
+pub fn render_text(template: &str, values: &TemplateValues) -> String {
+    render(template, |placeholder| {
+        values.get(placeholder).map(str::to_string)
+    })
+}
+
+pub fn contains_placeholder(template: &str, placeholder: &str) -> bool {
+    let mut found = false;
+    render(template, |candidate| {
+        found |= candidate == placeholder;
+        None
+    });
+    found
+}
+
+/// Templates are validated by the api when an issue is published. Anything that still isn't a
+/// known placeholder, like a stray `{{` in an older issue, is sent as written.
+fn render<F>(template: &str, mut substitute: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut remaining = template;
+
+    while let Some(start) = remaining.find("{{") {
+        rendered.push_str(&remaining[..start]);
+
+        let after_open = &remaining[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            remaining = &remaining[start..];
+            break;
+        };
+
+        match substitute(after_open[..end].trim()) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&remaining[start..start + 2 + end + 2]),
+        }
+
+        remaining = &after_open[end + 2..];
+    }
+
+    rendered.push_str(remaining);
+    rendered
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contains_placeholder, render_html, render_text, TemplateValues};
+
+    fn values() -> TemplateValues<'static> {
+        TemplateValues {
+            name: Some("Ursula"),
+            unsubscribe_url: "https://my.newsletter/unsubscribe?token=a.b",
+            issue_title: "Issue #1",
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced_with_the_subscriber_values() {
+        let rendered = render_text(
+            "Hi {{ name }}, welcome to {{issue_title}}. Leave at {{  unsubscribe_url }}",
+            &values(),
+        );
+
+        assert_eq!(
+            rendered,
+            "Hi Ursula, welcome to Issue #1. Leave at https://my.newsletter/unsubscribe?token=a.b"
+        );
+    }
+
+    #[test]
+    fn html_values_are_escaped() {
+        let values = TemplateValues {
+            name: Some("<script>alert('hi')</script>"),
+            ..values()
+        };
+
+        let rendered = render_html("<p>Hi {{ name }}</p>", &values);
+
+        assert_eq!(
+            rendered,
+            "<p>Hi &lt;script&gt;alert(&#x27;hi&#x27;)&lt;/script&gt;</p>"
+        );
+    }
+
+    #[test]
+    fn a_missing_name_uses_the_default() {
+        let values = TemplateValues {
+            name: None,
+            ..values()
+        };
+
+        assert_eq!(render_text("Hi {{ name }}", &values), "Hi subscriber");
+    }
+
+    #[test]
+    fn unknown_and_unterminated_placeholders_are_left_as_written() {
+        let rendered = render_text("{{ unknown }} and {{ name", &values());
+
+        assert_eq!(rendered, "{{ unknown }} and {{ name");
+    }
+
+    #[test]
+    fn placeholders_are_detected() {
+        assert!(contains_placeholder(
+            "Leave at {{unsubscribe_url}}",
+            "unsubscribe_url"
+        ));
+        assert!(!contains_placeholder("Hi {{ name }}", "unsubscribe_url"));
+    }
+}
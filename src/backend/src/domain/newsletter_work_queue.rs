@@ -0,0 +1,29 @@
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// A chunk of the newsletter audience, processed by a single invocation of the
+/// `send_newsletter_chunk` handler.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewsletterWorkItem {
+    pub trace_parent: String,
+    pub parent_span: String,
+    pub issue_id: NewsletterIssueId,
+    pub issue_title: String,
+    pub s3_pointer: String,
+    pub recipients: Vec<NewsletterRecipient>,
+    /// Test sends of a draft skip the delivery log.
+    #[serde(default)]
+    pub test_send: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct NewsletterRecipient {
+    pub email: String,
+    pub name: Option<String>,
+}
+
+#[async_trait]
+pub trait NewsletterWorkQueue {
+    async fn enqueue(&self, work_item: &NewsletterWorkItem) -> Result<(), anyhow::Error>;
+}
@@ -0,0 +1,25 @@
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
+use async_trait::async_trait;
+
+/// An issue the api stored with a `send_at` time, waiting to be sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledNewsletter {
+    pub issue_id: NewsletterIssueId,
+    pub send_at: i64,
+}
+
+#[async_trait]
+pub trait ScheduledNewsletterStore {
+    /// Scheduled issues with a send time at or before `now`, as a unix timestamp in seconds.
+    async fn get_due_newsletters(
+        &self,
+        now: i64,
+    ) -> Result<Vec<ScheduledNewsletter>, anyhow::Error>;
+
+    /// Turn a scheduled issue into a `NewsletterIssue`, which starts the send. Returns `false`
+    /// if the issue is no longer scheduled, because it was cancelled or already released.
+    async fn release_newsletter(
+        &self,
+        newsletter: &ScheduledNewsletter,
+    ) -> Result<bool, anyhow::Error>;
+}
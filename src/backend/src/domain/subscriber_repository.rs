@@ -17,9 +17,18 @@ impl std::fmt::Debug for DatabaseError {
     }
 }
 
+/// A single page of the confirmed audience. `next_cursor` is `None` once the last page has been
+/// read, otherwise it is passed back to `get_confirmed_subscribers_page` to read the next page.
+pub struct ConfirmedSubscriberPage {
+    pub subscribers: Vec<Result<ConfirmedSubscriber, anyhow::Error>>,
+    pub next_cursor: Option<String>,
+}
+
 #[async_trait]
 pub trait SubscriberRepository {
-    async fn get_confirmed_subscribers(
+    async fn get_confirmed_subscribers_page(
         &self,
-    ) -> Result<Vec<Result<ConfirmedSubscriber, anyhow::Error>>, anyhow::Error>;
+        cursor: Option<String>,
+        page_size: usize,
+    ) -> Result<ConfirmedSubscriberPage, anyhow::Error>;
 }
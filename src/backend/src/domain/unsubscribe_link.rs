@@ -0,0 +1,44 @@
+use crate::domain::subscriber_email::SubscriberEmail;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, Secret};
+use sha2::Sha256;
+
+/// Builds the per-recipient unsubscribe links added to every newsletter. Tokens are signed with
+/// the HMAC secret shared with the api, which verifies them on `/subscriptions/unsubscribe`.
+#[derive(Clone)]
+pub struct UnsubscribeLinks {
+    base_url: String,
+    hmac_secret: Secret<String>,
+}
+
+impl UnsubscribeLinks {
+    pub fn new(base_url: String, hmac_secret: Secret<String>) -> Self {
+        Self {
+            base_url,
+            hmac_secret,
+        }
+    }
+
+    pub fn link_for(&self, recipient: &SubscriberEmail) -> String {
+        format!(
+            "{}/subscriptions/unsubscribe?token={}",
+            self.base_url,
+            self.token_for(recipient)
+        )
+    }
+
+    fn token_for(&self, recipient: &SubscriberEmail) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.hmac_secret.expose_secret().as_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(b"unsubscribe:");
+        mac.update(recipient.as_ref().as_bytes());
+
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(recipient.as_ref()),
+            URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+        )
+    }
+}
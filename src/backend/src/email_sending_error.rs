@@ -0,0 +1,43 @@
+use crate::utils::error_chain_fmt;
+
+#[derive(thiserror::Error)]
+pub enum EmailSendingError {
+    #[error("{0}")]
+    ParseEmailError(String),
+    #[error("{0}")]
+    InvalidMessage(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl EmailSendingError {
+    /// Transient failures, like an unavailable email provider, may succeed when the message is
+    /// retried. Unparseable messages will keep failing until SQS moves them to the DLQ. That is
+    /// intended: every failed message reaches the DLQ through the queue's redrive policy, the
+    /// difference only shows in the level they are logged at.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::UnexpectedError(_))
+    }
+}
+
+impl std::fmt::Debug for EmailSendingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+/// Log an SQS record that is reported back as a batch item failure, at a level that tells
+/// transient failures apart from messages that will never succeed.
+pub fn log_record_failure(e: &EmailSendingError) {
+    if e.is_transient() {
+        lambda_extension::tracing::warn!(
+            "Transient failure handling SQS record, it will be retried. Error: {}",
+            e
+        );
+    } else {
+        lambda_extension::tracing::error!(
+            "Unparseable SQS record, it will be retried until moved to the DLQ. Error: {}",
+            e
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use crate::domain::newsletter_delivery_log::NewsletterDeliveryLog;
+use crate::domain::newsletter_work_queue::{
+    NewsletterRecipient, NewsletterWorkItem, NewsletterWorkQueue,
+};
+use crate::domain::subscriber_repository::SubscriberRepository;
+use crate::email_sending_error::EmailSendingError;
+use crate::send_newsletter_handler::{SendNewsletterEventHandler, SendNewsletterMessageBody};
+use crate::sqs_batch::handle_sqs_batch;
+use crate::telemetry::set_parent_context;
+use anyhow::Context;
+use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent, SqsMessage};
+use lambda_runtime::LambdaEvent;
+use serde_json::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Splits the audience of a published issue into chunks and enqueues one work item per chunk,
+/// to be delivered by the `send_newsletter_chunk` handler. A redelivered issue enqueues every
+/// chunk again, the delivery log stops subscribers receiving the issue twice.
+pub struct FanOutNewsletterEventHandler {
+    request_done_sender: UnboundedSender<()>,
+    chunk_size: usize,
+}
+
+impl FanOutNewsletterEventHandler {
+    pub fn new(request_done_sender: UnboundedSender<()>, chunk_size: usize) -> Self {
+        Self {
+            request_done_sender,
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    pub async fn invoke<
+        TRepo: SubscriberRepository,
+        TQueue: NewsletterWorkQueue,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
+        &self,
+        event: LambdaEvent<SqsEvent>,
+        repo: &TRepo,
+        work_queue: &TQueue,
+        delivery_log: &TDeliveryLog,
+    ) -> Result<SqsBatchResponse, Error> {
+        let response = handle_sqs_batch(event.payload.records, move |ctx, record| async move {
+            self.handle_record(&ctx, record, repo, work_queue, delivery_log)
+                .await
+        })
+        .await;
+
+        let _ = self.request_done_sender.send(()).map_err(Box::new);
+
+        Ok(response)
+    }
+
+    #[tracing::instrument(
+    name = "fan_out_queued_message",
+    skip(self, context, record, repo, work_queue, delivery_log),
+    fields(dd.trace_id=tracing::field::Empty, dd.span_id=tracing::field::Empty)
+    )]
+    pub async fn handle_record<
+        TRepo: SubscriberRepository,
+        TQueue: NewsletterWorkQueue,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
+        &self,
+        context: &opentelemetry::Context,
+        record: SqsMessage,
+        repo: &TRepo,
+        work_queue: &TQueue,
+        delivery_log: &TDeliveryLog,
+    ) -> Result<(), EmailSendingError> {
+        set_parent_context(context);
+
+        let message_body =
+            SendNewsletterEventHandler::parse_message_body(&record).map_err(|_| {
+                EmailSendingError::InvalidMessage("Failure parsing message body".to_string())
+            })?;
+
+        if let Some(recipient) = message_body.test_recipient.clone() {
+            Self::enqueue_test_send(work_queue, &message_body, recipient).await?;
+
+            tracing::info!("Enqueued a test send of {}", &message_body.issue_title);
+            return Ok(());
+        }
+
+        let audience =
+            Self::enqueue_chunks(repo, work_queue, &message_body, self.chunk_size).await?;
+
+        // The chunks record their deliveries against this, so the history can tell when they
+        // have all been delivered
+        delivery_log
+            .record_audience_size(&message_body.issue_id, audience.recipients)
+            .await
+            .context("Failure recording the audience size")?;
+
+        tracing::info!(
+            "Enqueued {} chunks for {}",
+            audience.chunks,
+            &message_body.issue_title
+        );
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "enqueue_test_send", skip(work_queue, message_body))]
+    async fn enqueue_test_send<TQueue: NewsletterWorkQueue>(
+        work_queue: &TQueue,
+        message_body: &SendNewsletterMessageBody,
+        recipient: String,
+    ) -> Result<(), anyhow::Error> {
+        let (trace_parent, parent_span) = telemetry::get_trace_and_span_id().unwrap_or((
+            message_body.trace_parent.clone(),
+            message_body.parent_span.clone(),
+        ));
+
+        let work_item = NewsletterWorkItem {
+            trace_parent,
+            parent_span,
+            issue_id: message_body.issue_id.clone(),
+            issue_title: message_body.issue_title.clone(),
+            s3_pointer: message_body.s3_pointer.clone(),
+            recipients: vec![NewsletterRecipient {
+                email: recipient,
+                name: None,
+            }],
+            test_send: true,
+        };
+
+        work_queue
+            .enqueue(&work_item)
+            .await
+            .context("Failure enqueuing newsletter test send")
+    }
+
+    #[tracing::instrument(name = "enqueue_chunks", skip(repo, work_queue, message_body))]
+    async fn enqueue_chunks<TRepo: SubscriberRepository, TQueue: NewsletterWorkQueue>(
+        repo: &TRepo,
+        work_queue: &TQueue,
+        message_body: &SendNewsletterMessageBody,
+        chunk_size: usize,
+    ) -> Result<EnqueuedAudience, anyhow::Error> {
+        // Chunk handlers continue the trace of this invocation
+        let (trace_parent, parent_span) = telemetry::get_trace_and_span_id().unwrap_or((
+            message_body.trace_parent.clone(),
+            message_body.parent_span.clone(),
+        ));
+
+        let mut cursor = None;
+        let mut audience = EnqueuedAudience::default();
+
+        // Each page of the audience becomes one work item
+        loop {
+            let page = repo
+                .get_confirmed_subscribers_page(cursor, chunk_size)
+                .await
+                .context("Failure retrieving confirmed subscribers")?;
+
+            let recipients: Vec<NewsletterRecipient> = page
+                .subscribers
+                .into_iter()
+                .filter_map(|subscriber| match subscriber {
+                    Ok(subscriber) => Some(NewsletterRecipient {
+                        email: subscriber.email.to_string(),
+                        name: subscriber.name,
+                    }),
+                    Err(error) => {
+                        tracing::warn!(
+                        error.cause_chain = ?error,
+                        error.message = %error,
+                        "Skipping a confirmed subscriber. Their stored contact details are invalid",
+                    );
+                        None
+                    }
+                })
+                .collect();
+
+            if !recipients.is_empty() {
+                audience.recipients += recipients.len();
+
+                let work_item = NewsletterWorkItem {
+                    trace_parent: trace_parent.clone(),
+                    parent_span: parent_span.clone(),
+                    issue_id: message_body.issue_id.clone(),
+                    issue_title: message_body.issue_title.clone(),
+                    s3_pointer: message_body.s3_pointer.clone(),
+                    recipients,
+                    test_send: false,
+                };
+
+                work_queue
+                    .enqueue(&work_item)
+                    .await
+                    .context("Failure enqueuing newsletter chunk")?;
+
+                audience.chunks += 1;
+            }
+
+            cursor = page.next_cursor;
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(audience)
+    }
+}
+
+/// The work items enqueued for an issue, and the recipients across all of them.
+#[derive(Debug, Default)]
+struct EnqueuedAudience {
+    chunks: usize,
+    recipients: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FanOutNewsletterEventHandler;
+    use crate::domain::newsletter_issue_id::NewsletterIssueId;
+    use crate::domain::newsletter_work_queue::{NewsletterWorkItem, NewsletterWorkQueue};
+    use crate::send_newsletter_handler::tests::TestSubscriberRepository;
+    use crate::send_newsletter_handler::SendNewsletterMessageBody;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct TestWorkQueue {
+        work_items: Mutex<Vec<NewsletterWorkItem>>,
+    }
+
+    #[async_trait]
+    impl NewsletterWorkQueue for TestWorkQueue {
+        async fn enqueue(&self, work_item: &NewsletterWorkItem) -> Result<(), anyhow::Error> {
+            let serialized = serde_json::to_string(work_item)?;
+            self.work_items
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str(&serialized)?);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn the_audience_is_split_into_chunks_of_the_configured_size() {
+        let emails: Vec<String> = (1..=5)
+            .map(|i| format!("subscriber{}@test.com", i))
+            .collect();
+        let repo = TestSubscriberRepository::new(emails.iter().map(String::as_str).collect());
+        let work_queue = TestWorkQueue::default();
+        let message_body = SendNewsletterMessageBody {
+            trace_parent: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            parent_span: "00f067aa0ba902b7".to_string(),
+            issue_id: NewsletterIssueId::parse("01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1".to_string())
+                .unwrap(),
+            issue_title: "Issue #1".to_string(),
+            s3_pointer: "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json".to_string(),
+            test_recipient: None,
+        };
+
+        let audience =
+            FanOutNewsletterEventHandler::enqueue_chunks(&repo, &work_queue, &message_body, 2)
+                .await
+                .unwrap();
+
+        let work_items = work_queue.work_items.lock().unwrap();
+        let recipients: Vec<Vec<String>> = work_items
+            .iter()
+            .map(|item| item.recipients.iter().map(|r| r.email.clone()).collect())
+            .collect();
+
+        assert_eq!(audience.chunks, 3);
+        assert_eq!(audience.recipients, 5);
+        assert_eq!(
+            recipients,
+            vec![
+                vec!["subscriber1@test.com", "subscriber2@test.com"],
+                vec!["subscriber3@test.com", "subscriber4@test.com"],
+                vec!["subscriber5@test.com"],
+            ]
+        );
+        assert!(work_items
+            .iter()
+            .all(|item| item.s3_pointer == "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json"));
+    }
+
+    #[tokio::test]
+    async fn a_test_send_is_enqueued_for_the_test_recipient_only() {
+        let work_queue = TestWorkQueue::default();
+        let message_body = SendNewsletterMessageBody {
+            trace_parent: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            parent_span: "00f067aa0ba902b7".to_string(),
+            issue_id: NewsletterIssueId::parse("01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1".to_string())
+                .unwrap(),
+            issue_title: "Issue #1".to_string(),
+            s3_pointer: "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json".to_string(),
+            test_recipient: Some("editor@test.com".to_string()),
+        };
+
+        FanOutNewsletterEventHandler::enqueue_test_send(
+            &work_queue,
+            &message_body,
+            "editor@test.com".to_string(),
+        )
+        .await
+        .unwrap();
+
+        let work_items = work_queue.work_items.lock().unwrap();
+        assert_eq!(work_items.len(), 1);
+        assert!(work_items[0].test_send);
+        assert_eq!(work_items[0].recipients[0].email, "editor@test.com");
+    }
+}
@@ -1,8 +1,13 @@
 pub mod adapters;
 pub mod configuration;
 pub mod domain;
+pub mod email_sending_error;
+pub mod fan_out_newsletter_handler;
+pub mod publish_scheduled_newsletters_handler;
 pub mod send_confirmation_handler;
+pub mod send_newsletter_chunk_handler;
 pub mod send_newsletter_handler;
+pub mod sqs_batch;
 pub mod startup;
 pub mod telemetry;
 pub mod utils;
@@ -0,0 +1,174 @@
+use crate::domain::scheduled_newsletter_store::ScheduledNewsletterStore;
+use anyhow::Context;
+use lambda_runtime::LambdaEvent;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Runs on a schedule and releases every scheduled issue whose send time has passed. Releasing
+/// an issue turns it into a `NewsletterIssue`, which the send newsletter pipe picks up.
+pub struct PublishScheduledNewslettersHandler {
+    request_done_sender: UnboundedSender<()>,
+}
+
+impl PublishScheduledNewslettersHandler {
+    pub fn new(request_done_sender: UnboundedSender<()>) -> Self {
+        Self {
+            request_done_sender,
+        }
+    }
+
+    pub async fn invoke<TStore: ScheduledNewsletterStore>(
+        &self,
+        _event: LambdaEvent<serde_json::Value>,
+        store: &TStore,
+    ) -> Result<(), anyhow::Error> {
+        let result = Self::release_due_newsletters(store, chrono::Utc::now().timestamp()).await;
+
+        let _ = self.request_done_sender.send(()).map_err(Box::new);
+
+        let released = result?;
+        tracing::info!("Released {} scheduled newsletters", released);
+
+        Ok(())
+    }
+
+    /// A failure releasing one issue doesn't stop the others, it is retried on the next run.
+    #[tracing::instrument(name = "release_due_newsletters", skip(store))]
+    async fn release_due_newsletters<TStore: ScheduledNewsletterStore>(
+        store: &TStore,
+        now: i64,
+    ) -> Result<usize, anyhow::Error> {
+        let due = store
+            .get_due_newsletters(now)
+            .await
+            .context("Failure retrieving due newsletters")?;
+
+        let mut released = 0;
+
+        for newsletter in due {
+            match store.release_newsletter(&newsletter).await {
+                Ok(true) => released += 1,
+                Ok(false) => tracing::info!(
+                    "{} is no longer scheduled, it was cancelled or already released",
+                    &newsletter.issue_id
+                ),
+                Err(error) => tracing::error!(
+                    error.cause_chain = ?error,
+                    error.message = %error,
+                    "Failure releasing {}",
+                    &newsletter.issue_id
+                ),
+            }
+        }
+
+        Ok(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PublishScheduledNewslettersHandler;
+    use crate::domain::newsletter_issue_id::NewsletterIssueId;
+    use crate::domain::scheduled_newsletter_store::{
+        ScheduledNewsletter, ScheduledNewsletterStore,
+    };
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct TestScheduledNewsletterStore {
+        scheduled: Mutex<Vec<ScheduledNewsletter>>,
+        failing_issue: Option<NewsletterIssueId>,
+    }
+
+    fn issue_id(slug: &str) -> NewsletterIssueId {
+        NewsletterIssueId::parse(format!("01HQ3Z5K8M2N4P6R8T0V2X4Z6B-{}", slug)).unwrap()
+    }
+
+    impl TestScheduledNewsletterStore {
+        fn new(scheduled: Vec<(&str, i64)>) -> Self {
+            Self {
+                scheduled: Mutex::new(
+                    scheduled
+                        .into_iter()
+                        .map(|(slug, send_at)| ScheduledNewsletter {
+                            issue_id: issue_id(slug),
+                            send_at,
+                        })
+                        .collect(),
+                ),
+                failing_issue: None,
+            }
+        }
+
+        fn remaining(&self) -> Vec<NewsletterIssueId> {
+            self.scheduled
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|newsletter| newsletter.issue_id.clone())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl ScheduledNewsletterStore for TestScheduledNewsletterStore {
+        async fn get_due_newsletters(
+            &self,
+            now: i64,
+        ) -> Result<Vec<ScheduledNewsletter>, anyhow::Error> {
+            Ok(self
+                .scheduled
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|newsletter| newsletter.send_at <= now)
+                .cloned()
+                .collect())
+        }
+
+        async fn release_newsletter(
+            &self,
+            newsletter: &ScheduledNewsletter,
+        ) -> Result<bool, anyhow::Error> {
+            if self.failing_issue.as_ref() == Some(&newsletter.issue_id) {
+                return Err(anyhow::anyhow!("DynamoDB is unavailable"));
+            }
+
+            let mut scheduled = self.scheduled.lock().unwrap();
+            let before = scheduled.len();
+            scheduled.retain(|candidate| candidate != newsletter);
+
+            Ok(scheduled.len() < before)
+        }
+    }
+
+    #[tokio::test]
+    async fn only_due_newsletters_are_released() {
+        let store = TestScheduledNewsletterStore::new(vec![
+            ("issue-1", 100),
+            ("issue-2", 200),
+            ("issue-3", 300),
+        ]);
+
+        let released = PublishScheduledNewslettersHandler::release_due_newsletters(&store, 200)
+            .await
+            .unwrap();
+
+        assert_eq!(released, 2);
+        assert_eq!(store.remaining(), vec![issue_id("issue-3")]);
+    }
+
+    #[tokio::test]
+    async fn a_failed_release_does_not_stop_the_others() {
+        let store = TestScheduledNewsletterStore {
+            failing_issue: Some(issue_id("issue-1")),
+            ..TestScheduledNewsletterStore::new(vec![("issue-1", 100), ("issue-2", 100)])
+        };
+
+        let released = PublishScheduledNewslettersHandler::release_due_newsletters(&store, 200)
+            .await
+            .unwrap();
+
+        assert_eq!(released, 1);
+        assert_eq!(store.remaining(), vec![issue_id("issue-1")]);
+    }
+}
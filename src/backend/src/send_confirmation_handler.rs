@@ -1,34 +1,19 @@
 use crate::domain::email_client::EmailClient;
 use crate::domain::subscriber_email::SubscriberEmail;
+use crate::email_sending_error::EmailSendingError;
 use anyhow::Context;
-use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent, SqsEventObj, SqsMessage};
+use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent, SqsMessage};
 use lambda_runtime::LambdaEvent;
-use opentelemetry::trace::TraceContextExt;
 use rand::distributions::Alphanumeric;
 use rand::{Rng, thread_rng};
 use serde::{Deserialize, Serialize};
 use serde_dynamo::AttributeValue;
 use serde_json::Error;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing_opentelemetry::OpenTelemetrySpanExt;
 use opentelemetry_sdk::trace::{config, Config, TracerProvider};
 use crate::configuration::Settings;
-use crate::telemetry::parse_context_from;
-use crate::utils::error_chain_fmt;
-
-#[derive(thiserror::Error)]
-pub enum EmailSendingError {
-    #[error("{0}")]
-    ParseEmailError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] anyhow::Error),
-}
-
-impl std::fmt::Debug for EmailSendingError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        error_chain_fmt(self, f)
-    }
-}
+use crate::sqs_batch::handle_sqs_batch;
+use crate::telemetry::set_parent_context;
 
 /// Implements the main event handler for processing events from an SQS queue.
 pub struct SendConfirmationEventHandler {
@@ -46,25 +31,15 @@ impl SendConfirmationEventHandler {
         configuration: &Settings,
         email_client: &TEmail,
     ) -> Result<SqsBatchResponse, Error> {
-        for record in event.payload.records {
-            let ctx = match parse_context_from(&record).await {
-                Ok(res) => res,
-                Err(_) => continue,
-            };
-
-            match self.handle(&ctx, record, email_client, &configuration.base_url).await {
-                Ok(_) => {}
-                Err(e) => {
-                    let error_msg = format!("Failure handling SQS record. Error: {}", e);
-
-                    lambda_extension::tracing::error!(error_msg);
-                }
-            };
-        }
-        
+        let response = handle_sqs_batch(event.payload.records, move |ctx, record| async move {
+            self.handle(&ctx, record, email_client, &configuration.base_url)
+                .await
+        })
+        .await;
+
         let _ = self.request_done_sender.send(()).map_err(Box::new);
 
-        Ok(SqsBatchResponse::default())
+        Ok(response)
     }
     
     pub async fn handle<TEmail: EmailClient>(
@@ -74,39 +49,24 @@ impl SendConfirmationEventHandler {
         email_client: &TEmail,
         base_url: &str,
     ) -> Result<(), EmailSendingError> {
-        tracing::Span::current().set_parent(context.clone());
-
-        let context = tracing::Span::current().context();
-        let span_context = context.span().span_context().clone();
+        set_parent_context(context);
 
-        let trace_id = span_context.trace_id().to_string().clone();
-        let span_id = span_context.span_id().to_string().clone();
+        let body = parse_message_body(&record).map_err(|_| {
+            EmailSendingError::InvalidMessage("Failure parsing message body".to_string())
+        })?;
 
-        let dd_trace_id = u64::from_str_radix(&trace_id[16..], 16)
-            .expect("Failed to convert string_trace_id to a u64.")
-            .to_string();
-
-        let dd_span_id = u64::from_str_radix(&span_id, 16)
-            .expect("Failed to convert string_span_id to a u64.")
-            .to_string();
-
-        tracing::Span::current().record("dd.trace_id", dd_trace_id);
-        tracing::Span::current().record("dd.span_id", dd_span_id);
-
-        let body = parse_message_body(&record).expect("Failure parsing message");
+        let email_address =
+            SubscriberEmail::parse(body.email_address).map_err(EmailSendingError::ParseEmailError)?;
 
         send_confirmation_email(
             email_client,
-            SubscriberEmail::parse(body.email_address).unwrap(),
+            email_address,
             &body.subscriber_token,
             base_url,
         )
             .await
             .context("Failed to send confirmation email")?;
 
-        // Notify the extension to flush traces.
-        let _ = self.request_done_sender.send(()).map_err(Box::new);
-
         Ok(())
     }
 }
@@ -140,8 +100,10 @@ pub async fn send_confirmation_email(
 }
 
 fn parse_message_body(record: &SqsMessage) -> Result<SendConfirmationMessageBody, ()> {
+    let body = record.body.as_ref().ok_or(())?;
+
     let message_body: Result<SendConfirmationMessageBody, serde_json::Error> =
-        serde_json::from_str(record.body.as_ref().unwrap().as_str());
+        serde_json::from_str(body);
 
     match message_body {
         Ok(body) => Ok(body),
@@ -0,0 +1,280 @@
+use crate::domain::confirmed_subscriber::ConfirmedSubscriber;
+use crate::domain::email_client::EmailClient;
+use crate::domain::newsletter_delivery_log::NewsletterDeliveryLog;
+use crate::domain::newsletter_store::NewsletterStore;
+use crate::domain::newsletter_work_queue::NewsletterWorkItem;
+use crate::domain::subscriber_email::SubscriberEmail;
+use crate::domain::unsubscribe_link::UnsubscribeLinks;
+use crate::email_sending_error::EmailSendingError;
+use crate::send_newsletter_handler::SendNewsletterEventHandler;
+use crate::sqs_batch::handle_sqs_batch;
+use crate::telemetry::set_parent_context;
+use anyhow::Context;
+use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent, SqsMessage};
+use lambda_runtime::LambdaEvent;
+use serde_json::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Delivers a newsletter issue to a single chunk of its audience, as enqueued by the
+/// `FanOutNewsletterEventHandler`.
+pub struct SendNewsletterChunkEventHandler {
+    request_done_sender: UnboundedSender<()>,
+    unsubscribe_links: UnsubscribeLinks,
+}
+
+impl SendNewsletterChunkEventHandler {
+    pub fn new(
+        request_done_sender: UnboundedSender<()>,
+        unsubscribe_links: UnsubscribeLinks,
+    ) -> Self {
+        Self {
+            request_done_sender,
+            unsubscribe_links,
+        }
+    }
+
+    pub async fn invoke<
+        TEmail: EmailClient,
+        TNewsletterStore: NewsletterStore,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
+        &self,
+        event: LambdaEvent<SqsEvent>,
+        email_client: &TEmail,
+        newsletter_store: &TNewsletterStore,
+        delivery_log: &TDeliveryLog,
+    ) -> Result<SqsBatchResponse, Error> {
+        let response = handle_sqs_batch(event.payload.records, move |ctx, record| async move {
+            self.handle_record(&ctx, record, email_client, newsletter_store, delivery_log)
+                .await
+        })
+        .await;
+
+        let _ = self.request_done_sender.send(()).map_err(Box::new);
+
+        Ok(response)
+    }
+
+    #[tracing::instrument(
+    name = "handle_queued_chunk",
+    skip(self, context, record, email_client, newsletter_store, delivery_log),
+    fields(dd.trace_id=tracing::field::Empty, dd.span_id=tracing::field::Empty)
+    )]
+    pub async fn handle_record<
+        TEmail: EmailClient,
+        TNewsletterStore: NewsletterStore,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
+        &self,
+        context: &opentelemetry::Context,
+        record: SqsMessage,
+        email_client: &TEmail,
+        newsletter_store: &TNewsletterStore,
+        delivery_log: &TDeliveryLog,
+    ) -> Result<(), EmailSendingError> {
+        set_parent_context(context);
+
+        let work_item: NewsletterWorkItem = record
+            .body
+            .as_deref()
+            .and_then(|body| serde_json::from_str(body).ok())
+            .ok_or_else(|| {
+                EmailSendingError::InvalidMessage("Failure parsing work item".to_string())
+            })?;
+
+        tracing::info!(
+            "Delivering {} to a chunk of {} recipients",
+            &work_item.issue_title,
+            work_item.recipients.len()
+        );
+
+        let newsletter_information = newsletter_store
+            .retrieve_newsletter(work_item.s3_pointer.as_str())
+            .await
+            .context("Failure retrieving metadata information")?;
+
+        if work_item.test_send {
+            for recipient in work_item.recipients {
+                SendNewsletterEventHandler::send_test(
+                    email_client,
+                    &self.unsubscribe_links,
+                    recipient.email,
+                    &newsletter_information,
+                )
+                .await?;
+            }
+
+            return Ok(());
+        }
+
+        for recipient in work_item.recipients {
+            let subscriber = match SubscriberEmail::parse(recipient.email) {
+                Ok(email) => ConfirmedSubscriber {
+                    email,
+                    name: recipient.name,
+                },
+                Err(error) => {
+                    tracing::warn!(
+                        error.message = %error,
+                        "Skipping a recipient with an invalid email address",
+                    );
+                    continue;
+                }
+            };
+
+            SendNewsletterEventHandler::deliver_to_subscriber(
+                email_client,
+                delivery_log,
+                &self.unsubscribe_links,
+                &work_item.issue_id,
+                &subscriber,
+                &newsletter_information,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SendNewsletterChunkEventHandler;
+    use crate::email_sending_error::EmailSendingError;
+    use crate::send_newsletter_handler::tests::{
+        sqs_record, unsubscribe_links, TestDeliveryLog, TestEmailClient, TestNewsletterStore,
+    };
+    use claims::{assert_err, assert_ok};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    const ISSUE_ID: &str = "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1";
+
+    async fn handle_chunk(
+        recipients: Vec<&str>,
+        test_send: bool,
+        email_client: &TestEmailClient,
+        delivery_log: &TestDeliveryLog,
+    ) -> Result<(), EmailSendingError> {
+        let (request_done_sender, _request_done_receiver) = unbounded_channel::<()>();
+        let handler =
+            SendNewsletterChunkEventHandler::new(request_done_sender, unsubscribe_links());
+        let recipients: Vec<_> = recipients
+            .into_iter()
+            .map(|email| serde_json::json!({ "email": email, "name": null }))
+            .collect();
+        let body = serde_json::json!({
+            "trace_parent": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "parent_span": "00f067aa0ba902b7",
+            "issue_id": ISSUE_ID,
+            "issue_title": "Issue #1",
+            "s3_pointer": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json",
+            "recipients": recipients,
+            "test_send": test_send,
+        });
+
+        handler
+            .handle_record(
+                &opentelemetry::Context::new(),
+                sqs_record("chunk", &body.to_string()),
+                email_client,
+                &TestNewsletterStore,
+                delivery_log,
+            )
+            .await
+    }
+
+    fn delivered(delivery_log: &TestDeliveryLog, recipient: &str) -> bool {
+        delivery_log
+            .delivered
+            .lock()
+            .unwrap()
+            .contains(&(ISSUE_ID.to_string(), recipient.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_sends_skip_the_delivery_log() {
+        let email_client = TestEmailClient::default();
+        let delivery_log = TestDeliveryLog::default();
+
+        let outcome =
+            handle_chunk(vec!["editor@test.com"], true, &email_client, &delivery_log).await;
+
+        assert_ok!(outcome);
+        assert_eq!(
+            *email_client.sent_to.lock().unwrap(),
+            vec!["editor@test.com"]
+        );
+        assert!(delivery_log.delivered.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn recipients_with_an_invalid_email_address_are_skipped() {
+        let email_client = TestEmailClient::default();
+        let delivery_log = TestDeliveryLog::default();
+
+        let outcome = handle_chunk(
+            vec!["first@test.com", "not-an-email", "third@test.com"],
+            false,
+            &email_client,
+            &delivery_log,
+        )
+        .await;
+
+        assert_ok!(outcome);
+        assert_eq!(
+            *email_client.sent_to.lock().unwrap(),
+            vec!["first@test.com", "third@test.com"]
+        );
+        assert!(delivered(&delivery_log, "first@test.com"));
+        assert!(delivered(&delivery_log, "third@test.com"));
+    }
+
+    #[tokio::test]
+    async fn a_failed_send_stops_the_rest_of_the_chunk() {
+        let email_client = TestEmailClient::default();
+        let delivery_log = TestDeliveryLog::default();
+        *email_client.failing_recipient.lock().unwrap() = Some("second@test.com".to_string());
+
+        let outcome = handle_chunk(
+            vec!["first@test.com", "second@test.com", "third@test.com"],
+            false,
+            &email_client,
+            &delivery_log,
+        )
+        .await;
+
+        assert_err!(outcome);
+        assert_eq!(
+            *email_client.sent_to.lock().unwrap(),
+            vec!["first@test.com"]
+        );
+        assert!(delivered(&delivery_log, "first@test.com"));
+        assert!(!delivered(&delivery_log, "second@test.com"));
+        assert!(!delivered(&delivery_log, "third@test.com"));
+    }
+
+    #[tokio::test]
+    async fn recipients_in_the_delivery_log_are_skipped() {
+        let email_client = TestEmailClient::default();
+        let delivery_log = TestDeliveryLog::default();
+        delivery_log
+            .delivered
+            .lock()
+            .unwrap()
+            .insert((ISSUE_ID.to_string(), "second@test.com".to_string()));
+
+        let outcome = handle_chunk(
+            vec!["first@test.com", "second@test.com", "third@test.com"],
+            false,
+            &email_client,
+            &delivery_log,
+        )
+        .await;
+
+        assert_ok!(outcome);
+        assert_eq!(
+            *email_client.sent_to.lock().unwrap(),
+            vec!["first@test.com", "third@test.com"]
+        );
+    }
+}
@@ -1,86 +1,88 @@
 use crate::domain::confirmed_subscriber::ConfirmedSubscriber;
 use crate::domain::email_client::EmailClient;
+use crate::domain::newsletter_delivery_log::NewsletterDeliveryLog;
+use crate::domain::newsletter_issue_id::NewsletterIssueId;
 use crate::domain::newsletter_metadata::NewsletterMetadata;
 use crate::domain::newsletter_store::NewsletterStore;
+use crate::domain::newsletter_template::{
+    contains_placeholder, render_html, render_text, TemplateValues,
+};
+use crate::domain::subscriber_email::SubscriberEmail;
 use crate::domain::subscriber_repository::SubscriberRepository;
-use crate::utils::error_chain_fmt;
+use crate::domain::unsubscribe_link::UnsubscribeLinks;
+use crate::email_sending_error::EmailSendingError;
 use anyhow::Context;
 use aws_lambda_events::sqs::{SqsBatchResponse, SqsEvent, SqsMessage};
 use lambda_runtime::LambdaEvent;
-use opentelemetry::trace::TraceContextExt;
 use serde::Deserialize;
 use serde_json::Error;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing_opentelemetry::OpenTelemetrySpanExt;
 use crate::configuration::Settings;
 use crate::send_confirmation_handler::SendConfirmationEventHandler;
-use crate::telemetry::parse_context_from;
-
-#[derive(thiserror::Error)]
-pub enum EmailSendingError {
-    #[error("{0}")]
-    ParseEmailError(String),
-    #[error(transparent)]
-    UnexpectedError(#[from] anyhow::Error),
-}
+use crate::sqs_batch::handle_sqs_batch;
+use crate::telemetry::set_parent_context;
 
-impl std::fmt::Debug for EmailSendingError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        error_chain_fmt(self, f)
-    }
-}
+/// Number of confirmed subscribers read from the repository at a time.
+const SUBSCRIBER_PAGE_SIZE: usize = 100;
 
 /// Implements the main event handler for processing events from an SQS queue.
 pub struct SendNewsletterEventHandler {
     request_done_sender: UnboundedSender<()>,
+    unsubscribe_links: UnsubscribeLinks,
 }
 
 impl SendNewsletterEventHandler {
-    pub fn new(request_done_sender: UnboundedSender<()>) -> Self {
-        Self { request_done_sender }
+    pub fn new(
+        request_done_sender: UnboundedSender<()>,
+        unsubscribe_links: UnsubscribeLinks,
+    ) -> Self {
+        Self {
+            request_done_sender,
+            unsubscribe_links,
+        }
     }
 
     pub async fn invoke<
         TEmail: EmailClient,
         TRepo: SubscriberRepository,
         TNewsletterStore: NewsletterStore,
+        TDeliveryLog: NewsletterDeliveryLog,
     >(
         &self,
         event: LambdaEvent<SqsEvent>,
         email_client: &TEmail,
         repo: &TRepo,
         newsletter_store: &TNewsletterStore,
+        delivery_log: &TDeliveryLog,
     ) -> Result<SqsBatchResponse, Error> {
-        for record in event.payload.records {
-            let ctx = match parse_context_from(&record).await {
-                Ok(res) => res,
-                Err(_) => continue,
-            };
-
-            match self.handle_record(&ctx, record, email_client, repo, newsletter_store).await {
-                Ok(_) => {}
-                Err(e) => {
-                    let error_msg = format!("Failure handling SQS record. Error: {}", e);
-
-                    lambda_extension::tracing::error!(error_msg);
-                }
-            };
-        }
+        let response = handle_sqs_batch(event.payload.records, move |ctx, record| async move {
+            self.handle_record(
+                &ctx,
+                record,
+                email_client,
+                repo,
+                newsletter_store,
+                delivery_log,
+            )
+            .await
+        })
+        .await;
 
         let _ = self.request_done_sender.send(()).map_err(Box::new);
 
-        Ok(SqsBatchResponse::default())
+        Ok(response)
     }
 
     #[tracing::instrument(
     name = "handle_queued_message",
-    skip(self, context, record, email_client, repo, newsletter_store),
+    skip(self, context, record, email_client, repo, newsletter_store, delivery_log),
     fields(dd.trace_id=tracing::field::Empty, dd.span_id=tracing::field::Empty)
     )]
     pub async fn handle_record<
         TEmail: EmailClient,
         TRepo: SubscriberRepository,
         TNewsletterStore: NewsletterStore,
+        TDeliveryLog: NewsletterDeliveryLog,
     >(
         &self,
         context: &opentelemetry::Context,
@@ -88,27 +90,13 @@ impl SendNewsletterEventHandler {
         email_client: &TEmail,
         repo: &TRepo,
         newsletter_store: &TNewsletterStore,
+        delivery_log: &TDeliveryLog,
     ) -> Result<(), EmailSendingError> {
-        tracing::Span::current().set_parent(context.clone());
-
-        let context = tracing::Span::current().context();
-        let span_context = context.span().span_context().clone();
+        set_parent_context(context);
 
-        let trace_id = span_context.trace_id().to_string().clone();
-        let span_id = span_context.span_id().to_string().clone();
-
-        let dd_trace_id = u64::from_str_radix(&trace_id[16..], 16)
-            .expect("Failed to convert string_trace_id to a u64.")
-            .to_string();
-
-        let dd_span_id = u64::from_str_radix(&span_id, 16)
-            .expect("Failed to convert string_span_id to a u64.")
-            .to_string();
-
-        tracing::Span::current().record("dd.trace_id", dd_trace_id);
-        tracing::Span::current().record("dd.span_id", dd_span_id);
-
-        let newsletter_data_path = Self::parse_message_body(&record).unwrap();
+        let newsletter_data_path = Self::parse_message_body(&record).map_err(|_| {
+            EmailSendingError::InvalidMessage("Failure parsing message body".to_string())
+        })?;
 
         tracing::info!(
         "Newsletter data path is {}",
@@ -120,63 +108,207 @@ impl SendNewsletterEventHandler {
             .await
             .context("Failure retrieving metadata informationx")?;
 
-        let send_res = Self::send_emails_to_subscribers(email_client, repo, &newsletter_information).await;
+        match newsletter_data_path.test_recipient {
+            Some(recipient) => {
+                Self::send_test(
+                    email_client,
+                    &self.unsubscribe_links,
+                    recipient,
+                    &newsletter_information,
+                )
+                .await?
+            }
+            None => {
+                Self::send_emails_to_subscribers(
+                    email_client,
+                    repo,
+                    delivery_log,
+                    &self.unsubscribe_links,
+                    &newsletter_data_path.issue_id,
+                    &newsletter_information,
+                )
+                .await?
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a draft to a single address. Test sends skip the delivery log, so the recipient still
+    /// gets the issue once it is published.
+    #[tracing::instrument(skip(email_client, unsubscribe_links, newsletter_information))]
+    pub(crate) async fn send_test<TEmail: EmailClient>(
+        email_client: &TEmail,
+        unsubscribe_links: &UnsubscribeLinks,
+        recipient: String,
+        newsletter_information: &NewsletterMetadata,
+    ) -> Result<(), EmailSendingError> {
+        let recipient = ConfirmedSubscriber {
+            email: SubscriberEmail::parse(recipient).map_err(EmailSendingError::ParseEmailError)?,
+            name: None,
+        };
+
+        Self::send_email(
+            email_client,
+            &recipient,
+            &unsubscribe_links.link_for(&recipient.email),
+            newsletter_information,
+        )
+        .await?;
 
         Ok(())
     }
 
     #[tracing::instrument(
     name = "send_emails_to_subscribers",
-    skip(email_client, repo, newsletter_information)
+    skip(email_client, repo, delivery_log, unsubscribe_links, newsletter_information)
     )]
-    async fn send_emails_to_subscribers<TEmail: EmailClient, TRepo: SubscriberRepository>(
+    async fn send_emails_to_subscribers<
+        TEmail: EmailClient,
+        TRepo: SubscriberRepository,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
         email_client: &TEmail,
         repo: &TRepo,
+        delivery_log: &TDeliveryLog,
+        unsubscribe_links: &UnsubscribeLinks,
+        issue_id: &NewsletterIssueId,
         newsletter_information: &NewsletterMetadata,
     ) -> Result<(), anyhow::Error> {
-        let subscribers = repo
-            .get_confirmed_subscribers()
-            .await
-            .context("Failure retrieving confirmed subscribers")?;
+        let mut cursor = None;
+        let mut subscriber_count = 0;
+        let mut audience_size = 0;
 
-        tracing::info!("There are {} confirmed subscribers", subscribers.len());
+        loop {
+            let page = repo
+                .get_confirmed_subscribers_page(cursor, SUBSCRIBER_PAGE_SIZE)
+                .await
+                .context("Failure retrieving confirmed subscribers")?;
 
-        for subscriber in subscribers {
-            match subscriber {
-                Ok(subscriber) => {
-                    tracing::info!("Sending email to {}", &subscriber.email.to_string());
+            subscriber_count += page.subscribers.len();
 
-                    Self::send_email(email_client, &subscriber, newsletter_information)
-                        .await
-                        .with_context(|| {
-                            format!("Failed to send newsletter issue to {}", subscriber.email)
-                        })?;
-                }
-                Err(error) => {
-                    tracing::warn!(
-                    error.cause_chain = ?error,
-                    error.message = %error,
-                    "Skipping a confirmed subscriber. Their stored contact details are invalid",
-                );
+            for subscriber in page.subscribers {
+                match subscriber {
+                    Ok(subscriber) => {
+                        Self::deliver_to_subscriber(
+                            email_client,
+                            delivery_log,
+                            unsubscribe_links,
+                            issue_id,
+                            &subscriber,
+                            newsletter_information,
+                        )
+                        .await?;
+
+                        audience_size += 1;
+                    }
+                    Err(error) => {
+                        tracing::warn!(
+                        error.cause_chain = ?error,
+                        error.message = %error,
+                        "Skipping a confirmed subscriber. Their stored contact details are invalid",
+                    );
+                    }
                 }
             }
+
+            cursor = page.next_cursor;
+
+            if cursor.is_none() {
+                break;
+            }
         }
 
+        tracing::info!("Sent to {} confirmed subscribers", subscriber_count);
+
+        // Recorded once every subscriber has the issue, which marks the send as complete
+        delivery_log
+            .record_audience_size(issue_id, audience_size)
+            .await
+            .context("Failure recording the audience size")?;
+
+        Ok(())
+    }
+
+    /// Send the issue to a single subscriber, unless the delivery log shows they already have it.
+    pub(crate) async fn deliver_to_subscriber<
+        TEmail: EmailClient,
+        TDeliveryLog: NewsletterDeliveryLog,
+    >(
+        email_client: &TEmail,
+        delivery_log: &TDeliveryLog,
+        unsubscribe_links: &UnsubscribeLinks,
+        issue_id: &NewsletterIssueId,
+        subscriber: &ConfirmedSubscriber,
+        newsletter_information: &NewsletterMetadata,
+    ) -> Result<(), anyhow::Error> {
+        let already_delivered = delivery_log
+            .has_been_delivered(issue_id, &subscriber.email)
+            .await
+            .context("Failure reading the newsletter delivery log")?;
+
+        if already_delivered {
+            tracing::info!(
+                "Skipping {}, the issue has already been delivered",
+                &subscriber.email.to_string()
+            );
+            return Ok(());
+        }
+
+        tracing::info!("Sending email to {}", &subscriber.email.to_string());
+
+        Self::send_email(
+            email_client,
+            subscriber,
+            &unsubscribe_links.link_for(&subscriber.email),
+            newsletter_information,
+        )
+        .await?;
+
+        delivery_log
+            .record_delivery(issue_id, &subscriber.email)
+            .await
+            .context("Failure recording the newsletter delivery")?;
+
         Ok(())
     }
 
-    #[tracing::instrument(skip(email_client, subscriber, newsletter_information))]
+    #[tracing::instrument(
+    skip(email_client, subscriber, unsubscribe_url, newsletter_information)
+    )]
     async fn send_email<TEmail: EmailClient>(
         email_client: &TEmail,
         subscriber: &ConfirmedSubscriber,
+        unsubscribe_url: &str,
         newsletter_information: &NewsletterMetadata,
     ) -> Result<(), anyhow::Error> {
+        let values = TemplateValues {
+            name: subscriber.name.as_deref(),
+            unsubscribe_url,
+            issue_title: &newsletter_information.issue_title,
+        };
+
+        let mut html_content = render_html(&newsletter_information.html_content, &values);
+        let mut text_content = render_text(&newsletter_information.text_content, &values);
+
+        // Every issue carries an unsubscribe link, even if the author didn't place one
+        if !contains_placeholder(&newsletter_information.html_content, "unsubscribe_url") {
+            html_content.push_str(&format!(
+                "<p><a href=\"{}\">Unsubscribe</a></p>",
+                unsubscribe_url
+            ));
+        }
+        if !contains_placeholder(&newsletter_information.text_content, "unsubscribe_url") {
+            text_content.push_str(&format!("\n\nUnsubscribe: {}", unsubscribe_url));
+        }
+
         email_client
-            .send_email_to(
+            .send_newsletter_to(
                 &subscriber.email,
                 &newsletter_information.issue_title,
-                &newsletter_information.html_content,
-                &newsletter_information.text_content,
+                &html_content,
+                &text_content,
+                unsubscribe_url,
             )
             .await
             .with_context(|| format!("Failed to send newsletter issue to {}", subscriber.email))?;
@@ -184,9 +316,13 @@ impl SendNewsletterEventHandler {
         Ok(())
     }
 
-    fn parse_message_body(record: &SqsMessage) -> Result<SendNewsletterMessageBody, ()> {
+    pub(crate) fn parse_message_body(
+        record: &SqsMessage,
+    ) -> Result<SendNewsletterMessageBody, ()> {
+        let body = record.body.as_ref().ok_or(())?;
+
         let message_body: Result<SendNewsletterMessageBody, serde_json::Error> =
-            serde_json::from_str(record.body.as_ref().unwrap().as_str());
+            serde_json::from_str(body);
 
         match message_body {
             Ok(body) => Ok(body),
@@ -197,9 +333,378 @@ impl SendNewsletterEventHandler {
 
 
 #[derive(Deserialize)]
-struct SendNewsletterMessageBody {
-    trace_parent: String,
-    parent_span: String,
-    issue_title: String,
-    s3_pointer: String,
+pub(crate) struct SendNewsletterMessageBody {
+    pub(crate) trace_parent: String,
+    pub(crate) parent_span: String,
+    pub(crate) issue_id: NewsletterIssueId,
+    pub(crate) issue_title: String,
+    pub(crate) s3_pointer: String,
+    /// Set for a test send of a draft, which only goes to this address.
+    #[serde(default)]
+    pub(crate) test_recipient: Option<String>,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::SendNewsletterEventHandler;
+    use crate::domain::confirmed_subscriber::ConfirmedSubscriber;
+    use crate::domain::email_client::EmailClient;
+    use crate::domain::newsletter_delivery_log::NewsletterDeliveryLog;
+    use crate::domain::newsletter_issue_id::NewsletterIssueId;
+    use crate::domain::newsletter_metadata::NewsletterMetadata;
+    use crate::domain::newsletter_store::{NewsletterStore, NewsletterStoreError};
+    use crate::domain::subscriber_email::SubscriberEmail;
+    use crate::domain::subscriber_repository::{ConfirmedSubscriberPage, SubscriberRepository};
+    use crate::domain::unsubscribe_link::UnsubscribeLinks;
+    use async_trait::async_trait;
+    use aws_lambda_events::sqs::{SqsEvent, SqsMessage};
+    use claims::{assert_err, assert_ok};
+    use lambda_runtime::{Context, LambdaEvent};
+    use secrecy::Secret;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Mutex;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    /// Returns confirmed subscribers two at a time, whatever page size is asked for.
+    pub(crate) struct TestSubscriberRepository {
+        emails: Vec<String>,
+        page_size: usize,
+    }
+
+    impl TestSubscriberRepository {
+        pub(crate) fn new(emails: Vec<&str>) -> Self {
+            Self {
+                emails: emails.into_iter().map(String::from).collect(),
+                page_size: 2,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SubscriberRepository for TestSubscriberRepository {
+        async fn get_confirmed_subscribers_page(
+            &self,
+            cursor: Option<String>,
+            _page_size: usize,
+        ) -> Result<ConfirmedSubscriberPage, anyhow::Error> {
+            let start: usize = cursor.map(|c| c.parse().unwrap()).unwrap_or(0);
+            let end = (start + self.page_size).min(self.emails.len());
+
+            Ok(ConfirmedSubscriberPage {
+                subscribers: self.emails[start..end]
+                    .iter()
+                    .map(|email| {
+                        Ok(ConfirmedSubscriber {
+                            email: SubscriberEmail::parse(email.clone()).unwrap(),
+                            name: None,
+                        })
+                    })
+                    .collect(),
+                next_cursor: (end < self.emails.len()).then(|| end.to_string()),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    pub(crate) struct TestEmailClient {
+        pub(crate) failing_recipient: Mutex<Option<String>>,
+        pub(crate) sent_to: Mutex<Vec<String>>,
+        pub(crate) text_bodies: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl EmailClient for TestEmailClient {
+        async fn send_email_to(
+            &self,
+            recipient: &SubscriberEmail,
+            _subject: &str,
+            _html_content: &str,
+            text_content: &str,
+        ) -> Result<(), anyhow::Error> {
+            if self.failing_recipient.lock().unwrap().as_deref() == Some(recipient.as_ref()) {
+                return Err(anyhow::anyhow!("The email provider is unavailable"));
+            }
+
+            self.sent_to.lock().unwrap().push(recipient.to_string());
+            self.text_bodies.lock().unwrap().push(text_content.to_string());
+            Ok(())
+        }
+
+        async fn send_newsletter_to(
+            &self,
+            recipient: &SubscriberEmail,
+            subject: &str,
+            html_content: &str,
+            text_content: &str,
+            _unsubscribe_url: &str,
+        ) -> Result<(), anyhow::Error> {
+            self.send_email_to(recipient, subject, html_content, text_content)
+                .await
+        }
+    }
+
+    pub(crate) fn unsubscribe_links() -> UnsubscribeLinks {
+        UnsubscribeLinks::new(
+            "https://my.newsletter".to_string(),
+            Secret::new("a-secret".to_string()),
+        )
+    }
+
+    #[derive(Default)]
+    pub(crate) struct TestDeliveryLog {
+        pub(crate) delivered: Mutex<HashSet<(String, String)>>,
+        pub(crate) audience_sizes: Mutex<HashMap<String, usize>>,
+    }
+
+    #[async_trait]
+    impl NewsletterDeliveryLog for TestDeliveryLog {
+        async fn has_been_delivered(
+            &self,
+            issue_id: &NewsletterIssueId,
+            recipient: &SubscriberEmail,
+        ) -> Result<bool, anyhow::Error> {
+            Ok(self
+                .delivered
+                .lock()
+                .unwrap()
+                .contains(&(issue_id.to_string(), recipient.to_string())))
+        }
+
+        async fn record_delivery(
+            &self,
+            issue_id: &NewsletterIssueId,
+            recipient: &SubscriberEmail,
+        ) -> Result<(), anyhow::Error> {
+            self.delivered
+                .lock()
+                .unwrap()
+                .insert((issue_id.to_string(), recipient.to_string()));
+            Ok(())
+        }
+
+        async fn record_audience_size(
+            &self,
+            issue_id: &NewsletterIssueId,
+            audience_size: usize,
+        ) -> Result<(), anyhow::Error> {
+            self.audience_sizes
+                .lock()
+                .unwrap()
+                .insert(issue_id.to_string(), audience_size);
+            Ok(())
+        }
+    }
+
+    pub(crate) struct TestNewsletterStore;
+
+    #[async_trait]
+    impl NewsletterStore for TestNewsletterStore {
+        async fn retrieve_newsletter(
+            &self,
+            _path: &str,
+        ) -> Result<NewsletterMetadata, NewsletterStoreError> {
+            Ok(NewsletterMetadata::new("Issue #1", "Text", "<p>HTML</p>"))
+        }
+    }
+
+    fn issue_id() -> NewsletterIssueId {
+        NewsletterIssueId::parse("01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1".to_string()).unwrap()
+    }
+
+    pub(crate) fn sqs_record(message_id: &str, body: &str) -> SqsMessage {
+        SqsMessage {
+            message_id: Some(message_id.to_string()),
+            body: Some(body.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn newsletter_record(message_id: &str) -> SqsMessage {
+        let body = serde_json::json!({
+            "trace_parent": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "parent_span": "00f067aa0ba902b7",
+            "issue_id": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1",
+            "issue_title": "Issue #1",
+            "s3_pointer": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json",
+        });
+
+        sqs_record(message_id, &body.to_string())
+    }
+
+    async fn invoke_with(
+        records: Vec<SqsMessage>,
+        email_client: &TestEmailClient,
+    ) -> Vec<String> {
+        let (request_done_sender, _request_done_receiver) = unbounded_channel::<()>();
+        let handler = SendNewsletterEventHandler::new(request_done_sender, unsubscribe_links());
+        let repo = TestSubscriberRepository::new(vec!["first@test.com"]);
+        let event = LambdaEvent::new(SqsEvent { records }, Context::default());
+
+        let response = handler
+            .invoke(
+                event,
+                email_client,
+                &repo,
+                &TestNewsletterStore,
+                &TestDeliveryLog::default(),
+            )
+            .await
+            .unwrap();
+
+        response
+            .batch_item_failures
+            .into_iter()
+            .map(|failure| failure.item_identifier)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn unparseable_records_are_reported_as_batch_item_failures() {
+        let email_client = TestEmailClient::default();
+        let records = vec![
+            newsletter_record("valid"),
+            sqs_record("not-json", "this is not a newsletter"),
+            sqs_record(
+                "missing-trace",
+                r#"{"issue_id": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1", "s3_pointer": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json"}"#,
+            ),
+        ];
+
+        let failures = invoke_with(records, &email_client).await;
+
+        assert_eq!(failures, vec!["not-json", "missing-trace"]);
+        assert_eq!(*email_client.sent_to.lock().unwrap(), vec!["first@test.com"]);
+    }
+
+    #[tokio::test]
+    async fn email_provider_failures_are_reported_as_batch_item_failures() {
+        let email_client = TestEmailClient::default();
+        *email_client.failing_recipient.lock().unwrap() = Some("first@test.com".to_string());
+
+        let failures = invoke_with(vec![newsletter_record("valid")], &email_client).await;
+
+        assert_eq!(failures, vec!["valid"]);
+    }
+
+    #[tokio::test]
+    async fn a_redelivered_issue_is_only_sent_to_pending_recipients() {
+        let repo = TestSubscriberRepository::new(vec![
+            "first@test.com",
+            "second@test.com",
+            "third@test.com",
+        ]);
+        let email_client = TestEmailClient::default();
+        let delivery_log = TestDeliveryLog::default();
+        let newsletter = NewsletterMetadata::new("Issue #1", "Text", "<p>HTML</p>");
+
+        // The first attempt fails part way through the audience
+        *email_client.failing_recipient.lock().unwrap() = Some("second@test.com".to_string());
+        let outcome = SendNewsletterEventHandler::send_emails_to_subscribers(
+            &email_client,
+            &repo,
+            &delivery_log,
+            &unsubscribe_links(),
+            &issue_id(),
+            &newsletter,
+        )
+        .await;
+        assert_err!(outcome);
+        assert!(delivery_log.audience_sizes.lock().unwrap().is_empty());
+
+        // The redelivery skips everyone who already received the issue
+        *email_client.failing_recipient.lock().unwrap() = None;
+        let outcome = SendNewsletterEventHandler::send_emails_to_subscribers(
+            &email_client,
+            &repo,
+            &delivery_log,
+            &unsubscribe_links(),
+            &issue_id(),
+            &newsletter,
+        )
+        .await;
+        assert_ok!(outcome);
+
+        assert_eq!(
+            *email_client.sent_to.lock().unwrap(),
+            vec!["first@test.com", "second@test.com", "third@test.com"]
+        );
+        assert_eq!(
+            delivery_log.audience_sizes.lock().unwrap()[&issue_id().to_string()],
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn every_newsletter_carries_the_recipients_unsubscribe_link() {
+        let repo = TestSubscriberRepository::new(vec!["first@test.com", "second@test.com"]);
+        let email_client = TestEmailClient::default();
+        let newsletter = NewsletterMetadata::new("Issue #1", "Text", "<p>HTML</p>");
+
+        let outcome = SendNewsletterEventHandler::send_emails_to_subscribers(
+            &email_client,
+            &repo,
+            &TestDeliveryLog::default(),
+            &unsubscribe_links(),
+            &issue_id(),
+            &newsletter,
+        )
+        .await;
+        assert_ok!(outcome);
+
+        let text_bodies = email_client.text_bodies.lock().unwrap();
+        let recipients = ["first@test.com", "second@test.com"];
+        for (recipient, body) in recipients.iter().zip(text_bodies.iter()) {
+            let recipient = SubscriberEmail::parse(recipient.to_string()).unwrap();
+            let link = unsubscribe_links().link_for(&recipient);
+            assert!(body.ends_with(&format!("Unsubscribe: {}", link)));
+        }
+        assert_ne!(text_bodies[0], text_bodies[1]);
+    }
+
+    #[tokio::test]
+    async fn newsletters_are_personalised_for_each_subscriber() {
+        let repo = TestSubscriberRepository::new(vec!["first@test.com"]);
+        let email_client = TestEmailClient::default();
+        let newsletter = NewsletterMetadata::new(
+            "Issue #1",
+            "Hi {{ name }}, this is {{ issue_title }}. Leave at {{ unsubscribe_url }}",
+            "<p>HTML</p>",
+        );
+
+        let outcome = SendNewsletterEventHandler::send_emails_to_subscribers(
+            &email_client,
+            &repo,
+            &TestDeliveryLog::default(),
+            &unsubscribe_links(),
+            &issue_id(),
+            &newsletter,
+        )
+        .await;
+        assert_ok!(outcome);
+
+        let recipient = SubscriberEmail::parse("first@test.com".to_string()).unwrap();
+        let link = unsubscribe_links().link_for(&recipient);
+        assert_eq!(
+            email_client.text_bodies.lock().unwrap()[0],
+            format!("Hi subscriber, this is Issue #1. Leave at {}", link)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sends_only_go_to_the_test_recipient() {
+        let email_client = TestEmailClient::default();
+        let body = serde_json::json!({
+            "trace_parent": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "parent_span": "00f067aa0ba902b7",
+            "issue_id": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1",
+            "issue_title": "Issue #1",
+            "s3_pointer": "01HQ3Z5K8M2N4P6R8T0V2X4Z6B-issue-1.json",
+            "test_recipient": "editor@test.com",
+        });
+
+        let failures = invoke_with(vec![sqs_record("test", &body.to_string())], &email_client).await;
+
+        assert!(failures.is_empty());
+        assert_eq!(*email_client.sent_to.lock().unwrap(), vec!["editor@test.com"]);
+    }
 }
@@ -0,0 +1,45 @@
+use crate::email_sending_error::{log_record_failure, EmailSendingError};
+use crate::telemetry::parse_context_from;
+use aws_lambda_events::sqs::{BatchItemFailure, SqsBatchResponse, SqsMessage};
+use std::future::Future;
+
+/// Handle each record of an SQS batch with the trace context it was sent with. Failed records
+/// are reported back as batch item failures, so only they are retried.
+pub async fn handle_sqs_batch<F, Fut>(
+    records: Vec<SqsMessage>,
+    mut handle_record: F,
+) -> SqsBatchResponse
+where
+    F: FnMut(opentelemetry::Context, SqsMessage) -> Fut,
+    Fut: Future<Output = Result<(), EmailSendingError>>,
+{
+    let mut batch_item_failures = Vec::new();
+
+    for record in records {
+        let message_id = record.message_id.clone();
+
+        let result = match parse_context_from(&record).await {
+            Ok(ctx) => handle_record(ctx, record).await,
+            Err(_) => Err(EmailSendingError::InvalidMessage(
+                "Failure parsing trace context from SQS record".to_string(),
+            )),
+        };
+
+        if let Err(e) = result {
+            log_record_failure(&e);
+
+            match message_id {
+                Some(item_identifier) => {
+                    batch_item_failures.push(BatchItemFailure { item_identifier })
+                }
+                None => lambda_extension::tracing::error!(
+                    "SQS record has no message id and cannot be reported as failed"
+                ),
+            }
+        }
+    }
+
+    SqsBatchResponse {
+        batch_item_failures,
+    }
+}
@@ -1,18 +1,20 @@
 use aws_lambda_events::sqs::SqsMessage;
 use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
 use serde::Deserialize;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 pub async fn parse_context_from(record: &SqsMessage) -> Result<opentelemetry::Context, ()> {
-    let message_body: Result<TracedMessage, serde_json::Error> =
-        serde_json::from_str(record.body.as_ref().unwrap().as_str());
+    let body = record.body.as_ref().ok_or(())?;
+
+    let message_body: Result<TracedMessage, serde_json::Error> = serde_json::from_str(body);
 
     let traced_message = match message_body {
         Ok(message) => message,
         Err(_) => return Err(()),
     };
 
-    let trace_id = TraceId::from_hex(traced_message.trace_parent.as_str()).unwrap();
-    let span_id = SpanId::from_hex(traced_message.parent_span.as_str()).unwrap();
+    let trace_id = TraceId::from_hex(traced_message.trace_parent.as_str()).map_err(|_| ())?;
+    let span_id = SpanId::from_hex(traced_message.parent_span.as_str()).map_err(|_| ())?;
 
     let span_context = SpanContext::new(
         trace_id,
@@ -27,6 +29,29 @@ pub async fn parse_context_from(record: &SqsMessage) -> Result<opentelemetry::Co
     Ok(ctx)
 }
 
+/// Continue the trace parsed from a queued message in the current span, recording the Datadog
+/// formatted trace and span ids. The current span must declare `dd.trace_id` and `dd.span_id`.
+pub fn set_parent_context(context: &opentelemetry::Context) {
+    tracing::Span::current().set_parent(context.clone());
+
+    let context = tracing::Span::current().context();
+    let span_context = context.span().span_context().clone();
+
+    let trace_id = span_context.trace_id().to_string().clone();
+    let span_id = span_context.span_id().to_string().clone();
+
+    let dd_trace_id = u64::from_str_radix(&trace_id[16..], 16)
+        .expect("Failed to convert string_trace_id to a u64.")
+        .to_string();
+
+    let dd_span_id = u64::from_str_radix(&span_id, 16)
+        .expect("Failed to convert string_span_id to a u64.")
+        .to_string();
+
+    tracing::Span::current().record("dd.trace_id", dd_trace_id);
+    tracing::Span::current().record("dd.span_id", dd_span_id);
+}
+
 #[derive(Deserialize)]
 struct TracedMessage {
     trace_parent: String,
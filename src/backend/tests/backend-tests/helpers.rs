@@ -1,14 +1,11 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
 use std::time::Duration;
 
-use aws_lambda_events::dynamodb::{EventRecord, StreamRecord};
 use aws_lambda_events::sqs::SqsMessage;
 
-use opentelemetry::trace::TracerProvider;
 use opentelemetry::Context;
 use secrecy::Secret;
-use serde_dynamo::AttributeValue;
+use tokio::sync::mpsc::unbounded_channel;
 use tracing::info;
 use uuid::Uuid;
 
@@ -16,7 +13,8 @@ use backend::adapters::postmark_email_client::PostmarkEmailClient;
 use backend::configuration::get_configuration;
 use backend::domain::email_client::EmailClient;
 use backend::domain::subscriber_email::SubscriberEmail;
-use backend::send_confirmation_handler::{handle_record, EmailSendingError};
+use backend::email_sending_error::EmailSendingError;
+use backend::send_confirmation_handler::SendConfirmationEventHandler;
 use telemetry::{get_subscriber, init_subscriber, init_tracer};
 use wiremock::MockServer;
 
@@ -33,30 +31,25 @@ impl TestApp {
         &self,
         email_address: &str,
     ) -> Result<(), EmailSendingError> {
-        let mut hash_map: HashMap<String, AttributeValue> = HashMap::new();
-        hash_map.insert(
-            "EmailAddress".to_string(),
-            AttributeValue::S(email_address.to_string()),
-        );
-        hash_map.insert(
-            "Type".to_string(),
-            AttributeValue::S("SubscriberToken".to_string()),
-        );
-
-        let record = SqsMessage{
-            message_id: None,
-            receipt_handle: None,
-            body: None,
-            md5_of_body: None,
-            md5_of_message_attributes: None,
-            attributes: Default::default(),
-            message_attributes: Default::default(),
-            event_source_arn: None,
-            event_source: None,
-            aws_region: None,
+        let body = serde_json::json!({
+            "trace_parent": "4bf92f3577b34da6a3ce929d0e0e4736",
+            "parent_span": "00f067aa0ba902b7",
+            "email_address": email_address,
+            "subscriber_token": Uuid::new_v4().to_string(),
+        });
+
+        let record = SqsMessage {
+            message_id: Some(Uuid::new_v4().to_string()),
+            body: Some(body.to_string()),
+            ..Default::default()
         };
 
-        handle_record(&Context::new(), record, &self.email_client, &self.base_url).await
+        let (request_done_sender, _request_done_receiver) = unbounded_channel::<()>();
+        let handler = SendConfirmationEventHandler::new(request_done_sender);
+
+        handler
+            .handle(&Context::new(), record, &self.email_client, &self.base_url)
+            .await
     }
 }
 
@@ -120,4 +113,16 @@ impl EmailClient for TestEmailClient {
         info!("Sending email to {}", recipient.inner());
         Ok(())
     }
+
+    async fn send_newsletter_to(
+        &self,
+        recipient: &SubscriberEmail,
+        subject: &str,
+        html_content: &str,
+        text_content: &str,
+        _unsubscribe_url: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.send_email_to(recipient, subject, html_content, text_content)
+            .await
+    }
 }